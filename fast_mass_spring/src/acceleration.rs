@@ -0,0 +1,91 @@
+use std::fmt;
+
+use crate::math::{Number, Vector3};
+
+/// A custom acceleration field, called with the position of a particle and the simulated time.
+pub type AccelerationFn = Box<dyn Fn(&Vector3, Number) -> Vector3>;
+
+/// Acceleration of the particles independent of their mass, e.g. gravity.
+pub enum AccelerationField {
+    /// The same acceleration everywhere, like gravity near the ground.
+    Constant(Vector3),
+    /// Pulls towards `center` with `strength / r^2`, like the gravity of a planet.
+    PointAttractor {
+        center: Vector3,
+        strength: Number,
+        /// The distance is clamped to at least this, so that a particle passing through the
+        /// center is not flung away.
+        min_distance: Number,
+    },
+    /// Pushes away from `center` with a magnitude of `strength` at any distance. A negative
+    /// strength pulls towards it.
+    Radial { center: Vector3, strength: Number },
+    /// Called with the position of each particle and the simulated time, on every substep.
+    Custom(AccelerationFn),
+}
+
+impl Default for AccelerationField {
+    fn default() -> Self {
+        AccelerationField::Constant(Vector3::zeros())
+    }
+}
+
+impl fmt::Debug for AccelerationField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccelerationField::Constant(acceleration) => {
+                f.debug_tuple("Constant").field(acceleration).finish()
+            }
+            AccelerationField::PointAttractor {
+                center,
+                strength,
+                min_distance,
+            } => f
+                .debug_struct("PointAttractor")
+                .field("center", center)
+                .field("strength", strength)
+                .field("min_distance", min_distance)
+                .finish(),
+            AccelerationField::Radial { center, strength } => f
+                .debug_struct("Radial")
+                .field("center", center)
+                .field("strength", strength)
+                .finish(),
+            AccelerationField::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl AccelerationField {
+    /// The acceleration if it is the same for every particle.
+    #[inline]
+    pub fn as_constant(&self) -> Option<Vector3> {
+        match self {
+            AccelerationField::Constant(acceleration) => Some(*acceleration),
+            _ => None,
+        }
+    }
+
+    pub fn acceleration_at(&self, position: &Vector3, time: Number) -> Vector3 {
+        match self {
+            AccelerationField::Constant(acceleration) => *acceleration,
+            AccelerationField::PointAttractor {
+                center,
+                strength,
+                min_distance,
+            } => {
+                let offset = center - position;
+                let distance = offset.norm().max(*min_distance);
+                if distance > 0.0 {
+                    offset * (strength / (distance * distance * distance))
+                } else {
+                    Vector3::zeros()
+                }
+            }
+            AccelerationField::Radial { center, strength } => (position - center)
+                .try_normalize(Number::EPSILON)
+                .map_or_else(Vector3::zeros, |direction| direction * *strength),
+            AccelerationField::Custom(field) => field(position, time),
+        }
+    }
+}
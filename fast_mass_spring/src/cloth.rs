@@ -1,7 +1,18 @@
-use nalgebra::Point3;
-use simulation::{math::Isometry3, Mesh};
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
 
-use crate::math::{DVector, Number, Vector3};
+use nalgebra::{Matrix2, Matrix3x2, Point3, Vector2};
+use simulation::{
+    closest_point_on_triangle, math::Isometry3, ray_triangle_intersection, triangle_normal_facing,
+    Aabb, Bvh, Mesh, Ray,
+};
+
+use crate::{
+    math::{DVector, Number, Vector3},
+    refine::RefinedParticle,
+};
 
 pub struct Cloth {
     pub particle_masses: Vec<Number>,
@@ -9,6 +20,17 @@ pub struct Cloth {
     pub prev_particle_positions: DVector,
     pub springs: Vec<Spring>,
     pub attachments: Vec<Attachment>,
+    pub long_range_attachments: Vec<LongRangeAttachment>,
+    pub seams: Vec<Seam>,
+    pub area_constraints: Vec<AreaConstraint>,
+    pub volume_constraints: Vec<VolumeConstraint>,
+    triangles: Vec<[usize; 3]>,
+    /// Hierarchy over the triangles, refit to the particle positions by
+    /// [`Cloth::update_surface_bvh`].
+    surface_bvh: Bvh,
+    triangle_aabbs: Vec<Aabb>,
+    /// The particles added by [`Cloth::refine`], oldest first.
+    refined_particles: Vec<RefinedParticle>,
 }
 
 impl Cloth {
@@ -23,6 +45,14 @@ impl Cloth {
             prev_particle_positions,
             springs: vec![],
             attachments: vec![],
+            long_range_attachments: vec![],
+            seams: vec![],
+            area_constraints: vec![],
+            volume_constraints: vec![],
+            triangles: vec![],
+            surface_bvh: Bvh::default(),
+            triangle_aabbs: vec![],
+            refined_particles: vec![],
         }
     }
 
@@ -34,6 +64,36 @@ impl Cloth {
         self.attachments.extend(attachments)
     }
 
+    /// Attach each particle of `particle_indices` at its current position.
+    pub fn attach_particles(
+        &mut self,
+        particle_indices: impl IntoIterator<Item = usize>,
+        stiffness: Number,
+    ) {
+        for particle_index in particle_indices {
+            self.attachments.push(Attachment {
+                particle_index,
+                target_position: self.get_particle_position(particle_index),
+                stiffness,
+            });
+        }
+    }
+
+    /// Attach every particle whose current position matches `predicate` at that position.
+    /// Returns the number of attachments added.
+    pub fn attach_where(
+        &mut self,
+        mut predicate: impl FnMut(&Vector3) -> bool,
+        stiffness: Number,
+    ) -> usize {
+        let particle_indices: Vec<usize> = (0..self.num_particles())
+            .filter(|&i| predicate(&self.get_particle_position(i)))
+            .collect();
+        let count = particle_indices.len();
+        self.attach_particles(particle_indices, stiffness);
+        count
+    }
+
     pub fn num_particles(&self) -> usize {
         self.particle_positions.len() / 3
     }
@@ -42,9 +102,311 @@ impl Cloth {
         self.springs.len()
     }
 
+    /// Multiply the rest length of every spring by `factor`. A factor below one pulls the
+    /// cloth together as if it shrank, a factor above one lets it expand.
+    pub fn scale_rest_lengths(&mut self, factor: Number) {
+        for spring in &mut self.springs {
+            spring.rest_length *= factor;
+        }
+    }
+
     #[inline]
     pub fn num_constraints(&self) -> usize {
-        self.num_springs() + self.attachments.len()
+        self.num_springs()
+            + self.attachments.len()
+            + self.long_range_attachments.len()
+            + self.seams.len()
+            + self.area_constraints.len() * AreaConstraint::NUM_SLOTS
+            + self
+                .volume_constraints
+                .iter()
+                .map(|volume| volume.particle_indices().len())
+                .sum::<usize>()
+    }
+
+    /// Sew particle pairs together. Each seam starts at the current distance between its
+    /// particles, so the cloth does not snap, and is closed by tightening it, see
+    /// [`Cloth::tighten_seams`].
+    pub fn add_seams(
+        &mut self,
+        particle_pairs: impl IntoIterator<Item = (usize, usize)>,
+        stiffness: Number,
+    ) {
+        for (particle_index_0, particle_index_1) in particle_pairs {
+            let rest_length = (self.get_particle_position(particle_index_0)
+                - self.get_particle_position(particle_index_1))
+            .magnitude();
+            self.seams.push(Seam {
+                particle_index_0,
+                particle_index_1,
+                stiffness,
+                rest_length,
+            });
+        }
+    }
+
+    /// Shorten the rest length of every seam by `distance`, down to zero.
+    pub fn tighten_seams(&mut self, distance: Number) {
+        for seam in &mut self.seams {
+            seam.rest_length = (seam.rest_length - distance).max(0.0);
+        }
+    }
+
+    /// The largest distance between the two particles of a seam, zero without seams.
+    pub fn max_seam_gap(&self) -> Number {
+        self.seams
+            .iter()
+            .map(|seam| {
+                (self.get_particle_position(seam.particle_index_0)
+                    - self.get_particle_position(seam.particle_index_1))
+                .magnitude()
+            })
+            .fold(0.0, Number::max)
+    }
+
+    /// The volume enclosed by the triangles of the cloth, which should form a closed surface
+    /// facing outwards.
+    pub fn volume(&self) -> Number {
+        signed_volume(&self.particle_positions, &self.triangles)
+    }
+
+    /// Keep the volume enclosed by the triangles of the cloth at its current value, like the
+    /// gas inside a balloon. Returns the index of the constraint, whose target volume can be
+    /// changed to inflate or deflate the cloth.
+    ///
+    /// The triangles should form a closed surface facing outwards. [`Cloth::refine`] and
+    /// [`Cloth::coarsen`] keep the constraint on the triangles they split and merge.
+    pub fn add_volume_constraint(&mut self, stiffness: Number) -> usize {
+        self.volume_constraints.push(VolumeConstraint::new(
+            &self.triangles,
+            self.volume(),
+            stiffness,
+        ));
+        self.volume_constraints.len() - 1
+    }
+
+    /// Keep the area of every triangle of the cloth at its current value, which resists the
+    /// shearing that springs alone let the triangles collapse into under compression. Returns
+    /// the number of constraints added, degenerate triangles are skipped.
+    ///
+    /// [`Cloth::refine`] splits the constraint of a split triangle in two, and
+    /// [`Cloth::coarsen`] merges them back.
+    pub fn add_area_constraints(&mut self, stiffness: Number) -> usize {
+        let before = self.area_constraints.len();
+        for &triangle in &self.triangles {
+            if let Some(area) = AreaConstraint::new(triangle, &self.particle_positions, stiffness) {
+                self.area_constraints.push(area);
+            }
+        }
+        self.area_constraints.len() - before
+    }
+
+    /// Merge another cloth into this one, e.g. to sew panels built separately. The particles
+    /// of `other` are appended, and its constraints and triangles are moved over with their
+    /// particle indices offset. Returns that offset, the index of the first particle of `other`.
+    pub fn append(&mut self, other: Cloth) -> usize {
+        let offset = self.num_particles();
+        let append = |a: &DVector, b: &DVector| {
+            DVector::from_iterator(a.len() + b.len(), a.iter().chain(b.iter()).copied())
+        };
+        self.particle_positions = append(&self.particle_positions, &other.particle_positions);
+        self.prev_particle_positions = append(
+            &self.prev_particle_positions,
+            &other.prev_particle_positions,
+        );
+        self.particle_masses.extend(other.particle_masses);
+        self.springs
+            .extend(other.springs.into_iter().map(|spring| Spring {
+                particle_index_0: spring.particle_index_0 + offset,
+                particle_index_1: spring.particle_index_1 + offset,
+                ..spring
+            }));
+        self.attachments
+            .extend(other.attachments.into_iter().map(|attachment| Attachment {
+                particle_index: attachment.particle_index + offset,
+                ..attachment
+            }));
+        self.long_range_attachments
+            .extend(
+                other
+                    .long_range_attachments
+                    .into_iter()
+                    .map(|lra| LongRangeAttachment {
+                        particle_index: lra.particle_index + offset,
+                        anchor_index: lra.anchor_index + offset,
+                        ..lra
+                    }),
+            );
+        self.seams.extend(other.seams.into_iter().map(|seam| Seam {
+            particle_index_0: seam.particle_index_0 + offset,
+            particle_index_1: seam.particle_index_1 + offset,
+            ..seam
+        }));
+        self.area_constraints
+            .extend(other.area_constraints.into_iter().map(|mut area| {
+                area.particle_indices = area.particle_indices.map(|i| i + offset);
+                area
+            }));
+        self.volume_constraints
+            .extend(other.volume_constraints.into_iter().map(|mut volume| {
+                for particle_index in &mut volume.particle_indices {
+                    *particle_index += offset;
+                }
+                volume
+            }));
+        self.refined_particles
+            .extend(
+                other
+                    .refined_particles
+                    .iter()
+                    .map(|refined| RefinedParticle {
+                        particle_index: refined.particle_index + offset,
+                        parents: refined.parents.map(|i| i + offset),
+                    }),
+            );
+        let mut triangles = std::mem::take(&mut self.triangles);
+        triangles.extend(
+            other
+                .triangles
+                .iter()
+                .map(|triangle| triangle.map(|i| i + offset)),
+        );
+        self.set_triangles(triangles);
+        offset
+    }
+
+    /// Remove the particles flagged in `removed` and shift the indices of the particles after
+    /// them. No constraint or triangle may refer to a removed particle. Does not rebuild the
+    /// surface hierarchy.
+    pub(crate) fn remove_particles(&mut self, removed: &[bool]) {
+        debug_assert_eq!(removed.len(), self.num_particles());
+        let mut new_indices = vec![usize::MAX; removed.len()];
+        let mut num_kept = 0;
+        for (new_index, _) in new_indices.iter_mut().zip(removed).filter(|(_, &r)| !r) {
+            *new_index = num_kept;
+            num_kept += 1;
+        }
+        let remap = |i: &mut usize| {
+            debug_assert!(!removed[*i], "particle {} is still referred to", i);
+            *i = new_indices[*i];
+        };
+        let compact = |positions: &DVector| {
+            DVector::from_iterator(
+                num_kept * 3,
+                positions
+                    .as_slice()
+                    .chunks(3)
+                    .zip(removed)
+                    .filter(|(_, &r)| !r)
+                    .flat_map(|(position, _)| position.iter().copied()),
+            )
+        };
+        self.particle_positions = compact(&self.particle_positions);
+        self.prev_particle_positions = compact(&self.prev_particle_positions);
+        let mut index = 0;
+        self.particle_masses.retain(|_| {
+            index += 1;
+            !removed[index - 1]
+        });
+
+        for spring in &mut self.springs {
+            remap(&mut spring.particle_index_0);
+            remap(&mut spring.particle_index_1);
+        }
+        for attachment in &mut self.attachments {
+            remap(&mut attachment.particle_index);
+        }
+        for lra in &mut self.long_range_attachments {
+            remap(&mut lra.particle_index);
+            remap(&mut lra.anchor_index);
+        }
+        for seam in &mut self.seams {
+            remap(&mut seam.particle_index_0);
+            remap(&mut seam.particle_index_1);
+        }
+        for area in &mut self.area_constraints {
+            area.particle_indices.iter_mut().for_each(remap);
+        }
+        for volume in &mut self.volume_constraints {
+            volume.particle_indices.iter_mut().for_each(remap);
+        }
+        for triangle in &mut self.triangles {
+            triangle.iter_mut().for_each(remap);
+        }
+        for refined in &mut self.refined_particles {
+            remap(&mut refined.particle_index);
+            refined.parents.iter_mut().for_each(remap);
+        }
+    }
+
+    /// Add a long range attachment from every free particle to its nearest attached particle,
+    /// limiting the particle to the geodesic distance measured over the springs at rest.
+    /// Returns the number of constraints added.
+    ///
+    /// Should be called after the attachments and springs are set up.
+    pub fn add_long_range_attachments(&mut self, stiffness: Number) -> usize {
+        let nearest = self.compute_geodesic_distances_to_attachments();
+        let before = self.long_range_attachments.len();
+        for (particle_index, nearest) in nearest.into_iter().enumerate() {
+            if let Some((anchor_index, max_distance)) = nearest {
+                if anchor_index != particle_index {
+                    self.long_range_attachments.push(LongRangeAttachment {
+                        particle_index,
+                        anchor_index,
+                        max_distance,
+                        stiffness,
+                    });
+                }
+            }
+        }
+        self.long_range_attachments.len() - before
+    }
+
+    /// For each particle, the nearest attached particle and the distance to it along the
+    /// spring graph, using the rest lengths as edge weights. `None` for particles that are
+    /// not connected to any attachment.
+    pub fn compute_geodesic_distances_to_attachments(&self) -> Vec<Option<(usize, Number)>> {
+        let num_particles = self.num_particles();
+        let mut neighbors = vec![vec![]; num_particles];
+        for spring in &self.springs {
+            neighbors[spring.particle_index_0].push((spring.particle_index_1, spring.rest_length));
+            neighbors[spring.particle_index_1].push((spring.particle_index_0, spring.rest_length));
+        }
+
+        let mut nearest: Vec<Option<(usize, Number)>> = vec![None; num_particles];
+        let mut heap = BinaryHeap::new();
+        for attachment in &self.attachments {
+            let index = attachment.particle_index;
+            nearest[index] = Some((index, 0.0));
+            heap.push(GeodesicState {
+                distance: 0.0,
+                particle_index: index,
+                anchor_index: index,
+            });
+        }
+        // multi source dijkstra
+        while let Some(state) = heap.pop() {
+            match nearest[state.particle_index] {
+                Some((_, distance)) if state.distance > distance => continue,
+                _ => {}
+            }
+            for &(neighbor, length) in &neighbors[state.particle_index] {
+                let distance = state.distance + length;
+                let closer = match nearest[neighbor] {
+                    Some((_, current)) => distance < current,
+                    None => true,
+                };
+                if closer {
+                    nearest[neighbor] = Some((state.anchor_index, distance));
+                    heap.push(GeodesicState {
+                        distance,
+                        particle_index: neighbor,
+                        anchor_index: state.anchor_index,
+                    });
+                }
+            }
+        }
+        nearest
     }
 
     pub fn get_particle_position(&self, index: usize) -> Vector3 {
@@ -53,6 +415,186 @@ impl Cloth {
         let z = self.particle_positions[index * 3 + 2];
         Vector3::new(x, y, z)
     }
+
+    /// Pick the particle closest to a ray. Particles behind the ray origin or further
+    /// than `max_distance` from the ray are ignored.
+    pub fn pick_particle(
+        &self,
+        ray_origin: Vector3,
+        ray_direction: Vector3,
+        max_distance: Number,
+    ) -> Option<usize> {
+        let direction = ray_direction.normalize();
+        let mut picked = None;
+        let mut min_distance = max_distance;
+        for i in 0..self.num_particles() {
+            let offset = self.get_particle_position(i) - ray_origin;
+            let t = offset.dot(&direction);
+            if t < 0.0 {
+                continue;
+            }
+            let distance = (offset - direction * t).magnitude();
+            if distance < min_distance {
+                min_distance = distance;
+                picked = Some(i);
+            }
+        }
+        picked
+    }
+
+    /// Bounding box of the particles.
+    pub fn compute_aabb(&self) -> Aabb {
+        let mut aabb = Aabb::empty();
+        for i in 0..self.num_particles() {
+            aabb.grow(&self.get_particle_position(i));
+        }
+        aabb
+    }
+
+    /// A sphere around all particles as `(center, radius)`, centered on the bounding box. Not
+    /// the smallest enclosing sphere, but cheap and tight enough for culling.
+    pub fn compute_bounding_sphere(&self) -> (Vector3, Number) {
+        let center = self.compute_aabb().center();
+        let radius_squared = (0..self.num_particles())
+            .map(|i| (self.get_particle_position(i) - center).magnitude_squared())
+            .fold(0.0, Number::max);
+        (center, radius_squared.sqrt())
+    }
+
+    /// The triangles of the cloth surface, used by the surface queries.
+    #[inline]
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
+    /// Set the triangles of the cloth surface and build the hierarchy over them.
+    pub fn set_triangles(&mut self, triangles: Vec<[usize; 3]>) {
+        self.triangles = triangles;
+        self.compute_triangle_aabbs();
+        self.surface_bvh = Bvh::build(&self.triangle_aabbs);
+    }
+
+    /// The triangles, for edits that rebuild the hierarchy with [`Self::set_triangles`] after.
+    #[inline]
+    pub(crate) fn triangles_mut(&mut self) -> &mut Vec<[usize; 3]> {
+        &mut self.triangles
+    }
+
+    /// The particles added by [`Self::refine`] that [`Self::coarsen`] can collapse, oldest
+    /// first.
+    #[inline]
+    pub fn refined_particles(&self) -> &[RefinedParticle] {
+        &self.refined_particles
+    }
+
+    #[inline]
+    pub(crate) fn refined_particles_mut(&mut self) -> &mut Vec<RefinedParticle> {
+        &mut self.refined_particles
+    }
+
+    /// Refit the surface hierarchy to the current particle positions. The solver calls this
+    /// after every step; call it after moving the particles by other means.
+    pub fn update_surface_bvh(&mut self) {
+        if self.triangles.is_empty() {
+            return;
+        }
+        self.compute_triangle_aabbs();
+        self.surface_bvh.refit(&self.triangle_aabbs);
+    }
+
+    fn compute_triangle_aabbs(&mut self) {
+        self.triangle_aabbs.clear();
+        for triangle in &self.triangles {
+            let [a, b, c] = triangle.map(|i| self.get_particle_position(i));
+            self.triangle_aabbs.push(Aabb::from_points([&a, &b, &c]));
+        }
+    }
+
+    /// The point of the cloth surface closest to `point`, as the triangle index, the
+    /// barycentric coordinates in that triangle and the point itself. `None` if the cloth has
+    /// no triangles.
+    pub fn closest_point(&self, point: &Vector3) -> Option<(usize, Vector3, Vector3)> {
+        let mut closest: Option<(usize, Vector3, Vector3)> = None;
+        self.surface_bvh.query_closest(point, |triangle_index| {
+            let [a, b, c] = self.triangles[triangle_index].map(|i| self.get_particle_position(i));
+            let (surface_point, barycentric) = closest_point_on_triangle(point, &a, &b, &c);
+            let distance_squared = (point - surface_point).magnitude_squared();
+            let is_closer =
+                closest.is_none_or(|(_, _, p)| distance_squared < (point - p).magnitude_squared());
+            if is_closer {
+                closest = Some((triangle_index, barycentric, surface_point));
+            }
+            distance_squared
+        });
+        closest
+    }
+
+    /// Cast a ray against the triangles of the cloth, and return the closest hit within
+    /// `max_distance`. Like [`Self::closest_point`], it uses the surface hierarchy as of the
+    /// last [`Self::update_surface_bvh`].
+    pub fn raycast(&self, ray: &Ray, max_distance: Number) -> Option<ClothRayHit> {
+        let mut closest: Option<ClothRayHit> = None;
+        self.surface_bvh
+            .query_ray(ray, max_distance, |triangle_index| {
+                let triangle = self.triangles[triangle_index];
+                let [a, b, c] = triangle.map(|i| self.get_particle_position(i));
+                let Some((distance, barycentric)) = ray_triangle_intersection(ray, &a, &b, &c)
+                else {
+                    return;
+                };
+                if distance > closest.as_ref().map_or(max_distance, |hit| hit.distance) {
+                    return;
+                }
+                closest = Some(ClothRayHit {
+                    distance,
+                    point: ray.point_at(distance),
+                    normal: triangle_normal_facing(&a, &b, &c, &ray.direction),
+                    triangle_index,
+                    particle_index: triangle[barycentric.imax()],
+                    barycentric,
+                });
+            });
+        closest
+    }
+
+    /// Relative elongation of a spring, `length / rest_length - 1`. Positive when stretched,
+    /// negative when compressed.
+    pub fn spring_strain(&self, spring: &Spring) -> Number {
+        let p0 = self.get_particle_position(spring.particle_index_0);
+        let p1 = self.get_particle_position(spring.particle_index_1);
+        if spring.rest_length <= Number::EPSILON {
+            return 0.0;
+        }
+        (p1 - p0).magnitude() / spring.rest_length - 1.0
+    }
+
+    /// For each particle, the strain of its most deformed spring. Particles without springs
+    /// get zero.
+    pub fn particle_strains(&self) -> Vec<Number> {
+        let mut strains: Vec<Number> = vec![0.0; self.num_particles()];
+        for spring in &self.springs {
+            let strain = self.spring_strain(spring);
+            for index in [spring.particle_index_0, spring.particle_index_1] {
+                if strain.abs() > strains[index].abs() {
+                    strains[index] = strain;
+                }
+            }
+        }
+        strains
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClothRayHit {
+    pub distance: Number,
+    pub point: Vector3,
+    /// Normal of the hit triangle, facing the ray origin.
+    pub normal: Vector3,
+    /// Index into [`Cloth::triangles`].
+    pub triangle_index: usize,
+    /// The particle of the triangle closest to the hit point.
+    pub particle_index: usize,
+    pub barycentric: Vector3,
 }
 
 #[derive(Clone)]
@@ -70,25 +612,451 @@ pub struct Attachment {
     pub stiffness: Number,
 }
 
+/// Keep a particle within `max_distance` of an anchor particle. The constraint is inactive
+/// while the particle is closer than that, so it only prevents over-stretching.
+#[derive(Clone)]
+pub struct LongRangeAttachment {
+    pub particle_index: usize,
+    pub anchor_index: usize,
+    pub max_distance: Number,
+    pub stiffness: Number,
+}
+
+/// Pull two particles together, e.g. along the matching edges of two panels of a garment.
+/// Acts like a spring whose rest length is tightened to zero as the seam is sewn.
+#[derive(Clone)]
+pub struct Seam {
+    pub particle_index_0: usize,
+    pub particle_index_1: usize,
+    pub stiffness: Number,
+    pub rest_length: Number,
+}
+
+/// Keep the volume enclosed by a closed surface at a target, e.g. the gas pressure inside a
+/// balloon or an airbag.
+///
+/// Every particle of the surface is projected onto the closest configuration with the target
+/// volume, linearized around the current one.
+#[derive(Clone)]
+pub struct VolumeConstraint {
+    /// The particles of the surface, each of them takes one slot of the projection.
+    particle_indices: Vec<usize>,
+    /// The triangles, indexing into `particle_indices`.
+    triangles: Vec<[usize; 3]>,
+    pub target_volume: Number,
+    pub stiffness: Number,
+}
+
+impl VolumeConstraint {
+    /// `triangles` index the particles of the cloth and should form a closed surface facing
+    /// outwards.
+    pub fn new(triangles: &[[usize; 3]], target_volume: Number, stiffness: Number) -> Self {
+        let mut particle_indices: Vec<usize> = triangles.iter().flatten().copied().collect();
+        particle_indices.sort_unstable();
+        particle_indices.dedup();
+        let triangles = triangles
+            .iter()
+            .map(|triangle| {
+                triangle.map(|i| {
+                    particle_indices
+                        .binary_search(&i)
+                        .expect("particle of the triangle was collected")
+                })
+            })
+            .collect();
+        Self {
+            particle_indices,
+            triangles,
+            target_volume,
+            stiffness,
+        }
+    }
+
+    /// The particles of the surface, in the order of their projections.
+    #[inline]
+    pub fn particle_indices(&self) -> &[usize] {
+        &self.particle_indices
+    }
+
+    /// The triangles of the surface, indexing the particles of the cloth.
+    pub fn triangles(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        self.triangles
+            .iter()
+            .map(|triangle| triangle.map(|i| self.particle_indices[i]))
+    }
+
+    /// Split the triangles of the edges in `midpoints`, keyed by their particles in increasing
+    /// order, at the midpoint particle as [`Cloth::split_edge`] splits the cloth triangles.
+    /// A triangle is split at most once.
+    pub(crate) fn split_edges(&mut self, midpoints: &HashMap<(usize, usize), usize>) {
+        let edge_midpoint = |particle_indices: &[usize], triangle: &[usize; 3], k: usize| {
+            let (a, b) = (
+                particle_indices[triangle[k]],
+                particle_indices[triangle[(k + 1) % 3]],
+            );
+            midpoints.get(&(a.min(b), a.max(b))).copied()
+        };
+        // the midpoints are new particles, appending them in order keeps the indices sorted
+        let particle_indices = &self.particle_indices;
+        let mut new_particles: Vec<usize> = self
+            .triangles
+            .iter()
+            .flat_map(|triangle| {
+                (0..3).filter_map(move |k| edge_midpoint(particle_indices, triangle, k))
+            })
+            .collect();
+        if new_particles.is_empty() {
+            return;
+        }
+        new_particles.sort_unstable();
+        new_particles.dedup();
+        debug_assert!(self.particle_indices.last() < new_particles.first());
+        self.particle_indices.extend(new_particles);
+
+        for triangle_index in 0..self.triangles.len() {
+            let triangle = self.triangles[triangle_index];
+            let Some((k, m)) = (0..3)
+                .find_map(|k| Some((k, edge_midpoint(&self.particle_indices, &triangle, k)?)))
+            else {
+                continue;
+            };
+            let km = self
+                .particle_indices
+                .binary_search(&m)
+                .expect("midpoint was added");
+            let (ka, kb) = (triangle[k], triangle[(k + 1) % 3]);
+            self.triangles[triangle_index] = triangle.map(|i| if i == kb { km } else { i });
+            self.triangles
+                .push(triangle.map(|i| if i == ka { km } else { i }));
+        }
+    }
+
+    /// Undo [`Self::split_edges`] for the midpoints in `parents`: the triangles of a midpoint
+    /// and its second parent are removed, and the midpoint is replaced by the second parent in
+    /// the others.
+    pub(crate) fn collapse_midpoints(&mut self, parents: &HashMap<usize, [usize; 2]>) {
+        let particle_indices = &self.particle_indices;
+        self.triangles.retain_mut(|triangle| {
+            let Some(km) = triangle
+                .iter()
+                .position(|k| parents.contains_key(&particle_indices[*k]))
+            else {
+                return true;
+            };
+            let [_, b] = parents[&particle_indices[triangle[km]]];
+            if triangle.iter().any(|&k| particle_indices[k] == b) {
+                return false;
+            }
+            triangle[km] = particle_indices
+                .binary_search(&b)
+                .expect("the parent is on the surface");
+            true
+        });
+        let mut new_indices = vec![usize::MAX; self.particle_indices.len()];
+        let mut kept = vec![];
+        for (k, &particle_index) in self.particle_indices.iter().enumerate() {
+            if !parents.contains_key(&particle_index) {
+                new_indices[k] = kept.len();
+                kept.push(particle_index);
+            }
+        }
+        for triangle in &mut self.triangles {
+            *triangle = triangle.map(|k| new_indices[k]);
+        }
+        self.particle_indices = kept;
+    }
+
+    /// Whether every edge is shared by exactly two triangles with opposite orientations, so
+    /// that the surface encloses a well defined volume.
+    pub fn is_closed(&self) -> bool {
+        let mut edges: Vec<(usize, usize)> = self
+            .triangles
+            .iter()
+            .flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)])
+            .collect();
+        edges.sort_unstable();
+        let unique = edges.windows(2).all(|pair| pair[0] != pair[1]);
+        unique
+            && edges
+                .iter()
+                .all(|&(a, b)| edges.binary_search(&(b, a)).is_ok())
+    }
+
+    /// The volume currently enclosed by the surface.
+    pub fn volume(&self, positions: &DVector) -> Number {
+        let centroid = self.centroid(positions);
+        let point = |k: usize| self.relative_position(positions, k, &centroid);
+        self.triangles
+            .iter()
+            .map(|&[a, b, c]| point(a).dot(&point(b).cross(&point(c))))
+            .sum::<Number>()
+            / 6.0
+    }
+
+    /// Write the projection of each particle of [`Self::particle_indices`] into `projections`.
+    pub fn project(&self, positions: &DVector, projections: &mut [Vector3]) {
+        debug_assert_eq!(projections.len(), self.particle_indices.len());
+        // relative to the centroid, which leaves the volume and its gradient unchanged on a
+        // closed surface but keeps the products small
+        let centroid = self.centroid(positions);
+        let point = |k: usize| self.relative_position(positions, k, &centroid);
+        // accumulate the volume gradient in place of the projections
+        let gradients = projections;
+        gradients.fill(Vector3::zeros());
+        let mut volume = 0.0;
+        for &[a, b, c] in &self.triangles {
+            let (pa, pb, pc) = (point(a), point(b), point(c));
+            volume += pa.dot(&pb.cross(&pc)) / 6.0;
+            gradients[a] += pb.cross(&pc) / 6.0;
+            gradients[b] += pc.cross(&pa) / 6.0;
+            gradients[c] += pa.cross(&pb) / 6.0;
+        }
+        let gradient_norm_squared: Number = gradients.iter().map(|g| g.norm_squared()).sum();
+        let lambda = if gradient_norm_squared > Number::EPSILON {
+            (volume - self.target_volume) / gradient_norm_squared
+        } else {
+            0.0
+        };
+        for (k, &particle_index) in self.particle_indices.iter().enumerate() {
+            let position = positions.fixed_rows::<3>(particle_index * 3);
+            gradients[k] = position - gradients[k] * lambda;
+        }
+    }
+
+    fn centroid(&self, positions: &DVector) -> Vector3 {
+        let sum = self
+            .particle_indices
+            .iter()
+            .map(|&i| positions.fixed_rows::<3>(i * 3).into_owned())
+            .sum::<Vector3>();
+        sum / (self.particle_indices.len() as Number).max(1.0)
+    }
+
+    #[inline]
+    fn relative_position(&self, positions: &DVector, k: usize, centroid: &Vector3) -> Vector3 {
+        positions.fixed_rows::<3>(self.particle_indices[k] * 3) - centroid
+    }
+}
+
+/// Keep the area of a triangle at its rest value, letting it stretch along one direction as
+/// long as it shrinks along the other.
+///
+/// The deformation gradient of the triangle is projected onto the closest one with the rest
+/// area, which takes two slots of the projection, one for each edge of the rest shape.
+#[derive(Clone)]
+pub struct AreaConstraint {
+    pub particle_indices: [usize; 3],
+    /// Inverse of the rest edges `[x1 - x0, x2 - x0]` in the plane of the triangle.
+    rest_shape_inverse: Matrix2<Number>,
+    rest_area: Number,
+    /// Scaled by the rest area, so that the stiffness of the cloth does not depend on the size
+    /// of its triangles.
+    pub stiffness: Number,
+}
+
+impl AreaConstraint {
+    pub const NUM_SLOTS: usize = 2;
+
+    /// The rest shape is the current one. `None` for a degenerate triangle.
+    pub fn new(
+        particle_indices: [usize; 3],
+        positions: &DVector,
+        stiffness: Number,
+    ) -> Option<Self> {
+        let [p0, p1, p2] = particle_indices.map(|i| positions.fixed_rows::<3>(i * 3).into_owned());
+        let (e1, e2) = (p1 - p0, p2 - p0);
+        let rest_area = 0.5 * e1.cross(&e2).norm();
+        if rest_area <= Number::EPSILON {
+            return None;
+        }
+        // express the edges in a basis of the plane of the triangle
+        let u = e1.normalize();
+        let v = (e2 - u * u.dot(&e2)).normalize();
+        let rest_shape = Matrix2::new(e1.dot(&u), e2.dot(&u), 0.0, e2.dot(&v));
+        Some(Self {
+            particle_indices,
+            rest_shape_inverse: rest_shape.try_inverse()?,
+            rest_area,
+            stiffness,
+        })
+    }
+
+    /// A constraint with the rest positions of its particles in a plane, oriented like the
+    /// rest shapes of [`Self::new`]. `None` for a degenerate triangle.
+    pub(crate) fn from_rest_points(
+        particle_indices: [usize; 3],
+        [p0, p1, p2]: [Vector2<Number>; 3],
+        stiffness: Number,
+    ) -> Option<Self> {
+        let rest_shape = Matrix2::from_columns(&[p1 - p0, p2 - p0]);
+        let rest_area = 0.5 * rest_shape.determinant();
+        if rest_area <= Number::EPSILON {
+            return None;
+        }
+        Some(Self {
+            particle_indices,
+            rest_shape_inverse: rest_shape.try_inverse()?,
+            rest_area,
+            stiffness,
+        })
+    }
+
+    /// The rest positions of the particles in the plane of the rest shape, the first one at
+    /// the origin.
+    pub(crate) fn rest_points(&self) -> [Vector2<Number>; 3] {
+        let rest_shape = self
+            .rest_shape_inverse
+            .try_inverse()
+            .unwrap_or_else(Matrix2::zeros);
+        [
+            Vector2::zeros(),
+            rest_shape.column(0).into_owned(),
+            rest_shape.column(1).into_owned(),
+        ]
+    }
+
+    #[inline]
+    pub fn rest_area(&self) -> Number {
+        self.rest_area
+    }
+
+    /// The stiffness scaled by the rest area, the weight of each slot in the system.
+    #[inline]
+    pub fn weight(&self) -> Number {
+        self.stiffness * self.rest_area
+    }
+
+    /// Coefficients of the particles in slot `slot`, which is a column of the deformation
+    /// gradient `[x1 - x0, x2 - x0] * rest_shape_inverse`.
+    #[inline]
+    pub fn slot_coefficients(&self, slot: usize) -> [Number; 3] {
+        let (c1, c2) = (
+            self.rest_shape_inverse[(0, slot)],
+            self.rest_shape_inverse[(1, slot)],
+        );
+        [-c1 - c2, c1, c2]
+    }
+
+    /// The two columns of the closest deformation gradient with the rest area.
+    pub fn project(&self, positions: &DVector) -> [Vector3; 2] {
+        let [p0, p1, p2] = self
+            .particle_indices
+            .map(|i| positions.fixed_rows::<3>(i * 3).into_owned());
+        let deformation = Matrix3x2::from_columns(&[p1 - p0, p2 - p0]) * self.rest_shape_inverse;
+        let svd = deformation.svd(true, true);
+        let (Some(u), Some(v_t)) = (svd.u, svd.v_t) else {
+            return [
+                deformation.column(0).into_owned(),
+                deformation.column(1).into_owned(),
+            ];
+        };
+        let [s0, s1] = [svd.singular_values[0], svd.singular_values[1]];
+        let [d0, d1] = closest_unit_product(s0, s1);
+        let projection = u * Matrix2::new(d0, 0.0, 0.0, d1) * v_t;
+        [
+            projection.column(0).into_owned(),
+            projection.column(1).into_owned(),
+        ]
+    }
+}
+
+/// The point `(d0, d1)` with `d0 * d1 = 1` closest to the singular values `(s0, s1)`, found
+/// with a few Newton steps on `d0` from the uniform scaling of `(s0, s1)`.
+fn closest_unit_product(s0: Number, s1: Number) -> [Number; 2] {
+    const MIN_SINGULAR_VALUE: Number = 1e-4;
+    let (s0, s1) = (s0.max(MIN_SINGULAR_VALUE), s1.max(MIN_SINGULAR_VALUE));
+    let mut d = s0 / (s0 * s1).sqrt();
+    for _ in 0..4 {
+        // half the first and second derivatives of (d - s0)^2 + (1 / d - s1)^2
+        let gradient = d - s0 - (1.0 / d - s1) / (d * d);
+        let hessian = 1.0 + (3.0 / d - 2.0 * s1) / (d * d * d);
+        if hessian <= 0.0 {
+            break;
+        }
+        d = (d - gradient / hessian).max(MIN_SINGULAR_VALUE);
+    }
+    [d, 1.0 / d]
+}
+
+fn signed_volume(positions: &DVector, triangles: &[[usize; 3]]) -> Number {
+    triangles
+        .iter()
+        .map(|triangle| {
+            let [a, b, c] = triangle.map(|i| positions.fixed_rows::<3>(i * 3).into_owned());
+            a.dot(&b.cross(&c))
+        })
+        .sum::<Number>()
+        / 6.0
+}
+
+/// Min-heap entry for the geodesic distance search.
+struct GeodesicState {
+    distance: Number,
+    particle_index: usize,
+    anchor_index: usize,
+}
+
+impl PartialEq for GeodesicState {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for GeodesicState {}
+
+impl PartialOrd for GeodesicState {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GeodesicState {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, so that the max-heap pops the smallest distance first
+        other
+            .distance
+            .partial_cmp(&self.distance)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
 pub struct ClothFromMeshBuilder<'a> {
     pub mesh: &'a Mesh,
     pub mass: f32,
     pub spring_stiffness: f32,
+    /// Stiffness of the bending springs, which connect the two vertices opposite each interior
+    /// edge and resist folding along it. Zero adds no bending springs.
+    pub bend_stiffness: f32,
+    /// A factor per vertex of the mesh, e.g. read from a vertex attribute. The stiffness of
+    /// each spring is multiplied by the mean of the factors of its two particles.
+    pub stiffness_multipliers: Option<&'a [Number]>,
 }
 
 impl<'a> ClothFromMeshBuilder<'a> {
     pub fn build(self) -> Cloth {
         let vertices = self.mesh.vertices();
         let num_particles = self.mesh.vertices().len();
+        if let Some(multipliers) = self.stiffness_multipliers {
+            assert_eq!(
+                multipliers.len(),
+                num_particles,
+                "one stiffness multiplier per vertex"
+            );
+        }
+        let multiplier = |index0: usize, index1: usize| {
+            self.stiffness_multipliers.map_or(1.0, |multipliers| {
+                0.5 * (multipliers[index0] + multipliers[index1])
+            })
+        };
         let mut particle_positions = Vec::with_capacity(num_particles * 3);
         particle_positions.extend(vertices.iter().flatten());
         let mut springs = vec![];
         let particle_mass = self.mass / num_particles as Number;
         let edges = self.mesh.compute_edges();
-        for edge in edges {
+        for edge in &edges {
             let index0 = edge.v0();
             let index1 = edge.v1();
-            let stiffness = self.spring_stiffness;
+            let stiffness = self.spring_stiffness * multiplier(index0, index1);
             let p0 = vertices[index0];
             let p1 = vertices[index1];
             springs.push(Spring {
@@ -98,38 +1066,137 @@ impl<'a> ClothFromMeshBuilder<'a> {
                 rest_length: (p0 - p1).magnitude(),
             });
         }
+        if self.bend_stiffness > 0.0 {
+            let edge_triangles = self.mesh.compute_edge_triangles();
+            // in the order of the edges rather than of the map, so that the springs are the
+            // same on every build
+            for edge in &edges {
+                // only edges shared by exactly two triangles have a well defined fold
+                let (t0, t1) = match edge_triangles[edge][..] {
+                    [t0, t1] => (t0, t1),
+                    _ => continue,
+                };
+                let opposite = |triangle: usize| {
+                    self.mesh.indices()[triangle * 3..triangle * 3 + 3]
+                        .iter()
+                        .map(|&i| i as usize)
+                        .find(|&i| i != edge.v0() && i != edge.v1())
+                };
+                if let (Some(index0), Some(index1)) = (opposite(t0), opposite(t1)) {
+                    if index0 == index1 {
+                        continue;
+                    }
+                    springs.push(Spring {
+                        particle_index_0: index0,
+                        particle_index_1: index1,
+                        stiffness: self.bend_stiffness * multiplier(index0, index1),
+                        rest_length: (vertices[index0] - vertices[index1]).magnitude(),
+                    });
+                }
+            }
+        }
         let prev_particle_positions = particle_positions.clone();
-        Cloth {
+        let mut cloth = Cloth {
             particle_masses: vec![particle_mass; num_particles],
             particle_positions: DVector::from_vec(particle_positions),
             prev_particle_positions: DVector::from_vec(prev_particle_positions),
             springs,
             attachments: vec![],
-        }
+            long_range_attachments: vec![],
+            seams: vec![],
+            area_constraints: vec![],
+            volume_constraints: vec![],
+            triangles: vec![],
+            surface_bvh: Bvh::default(),
+            triangle_aabbs: vec![],
+            refined_particles: vec![],
+        };
+        cloth.set_triangles(
+            self.mesh
+                .indices()
+                .chunks_exact(3)
+                .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+                .collect(),
+        );
+        cloth
     }
 }
 
-/// Build a cloth that modeled as a grid plane. The order of the vertices is from -y to y, from -x to x.
+/// Build a cloth that modeled as a grid plane, with the same vertices and triangles as
+/// [`GridPlaneBuilder`](simulation::GridPlaneBuilder). The order of the vertices is from -y to
+/// y, from -x to x.
 pub struct ClothBuilder {
-    pub size: Number,
-    pub resolution: usize,
+    /// Extent along x.
+    pub width: Number,
+    /// Extent along y.
+    pub height: Number,
+    /// Number of cells along x, at least one.
+    pub width_segments: usize,
+    /// Number of cells along y, at least one.
+    pub height_segments: usize,
     pub structural_spring_stiffness: f32,
     pub shear_spring_stiffness: f32,
     pub mass: Number,
     pub transform: Isometry3,
+    /// Scales the stiffness of the springs over the grid, e.g. stiffer hems and a softer
+    /// center. `None` keeps the same stiffness everywhere.
+    pub stiffness_map: Option<StiffnessMap>,
+}
+
+/// A stiffness multiplier over a grid cloth, sampled at the midpoint of each spring in grid
+/// coordinates `(u, v)`, each from 0 to 1 across the cloth. `u` follows the first index of
+/// the grid and `v` the second, see [`ClothBuilder`].
+pub enum StiffnessMap {
+    Function(Box<dyn Fn(Number, Number) -> Number>),
+    /// Values on a `width` by `height` grid over the cloth, `width` along `u`, stored row by
+    /// row of constant `v`, and interpolated bilinearly between them.
+    Grid {
+        width: usize,
+        height: usize,
+        values: Vec<Number>,
+    },
+}
+
+impl StiffnessMap {
+    pub fn value_at(&self, u: Number, v: Number) -> Number {
+        match self {
+            StiffnessMap::Function(function) => function(u, v),
+            StiffnessMap::Grid {
+                width,
+                height,
+                values,
+            } => {
+                assert!(
+                    *width > 0 && *height > 0 && values.len() == width * height,
+                    "stiffness map needs width * height values"
+                );
+                // position in the grid of values, and its cell
+                let x = u.clamp(0.0, 1.0) * width.saturating_sub(1) as Number;
+                let y = v.clamp(0.0, 1.0) * height.saturating_sub(1) as Number;
+                let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+                let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+                let (tx, ty) = (x - x0 as Number, y - y0 as Number);
+                let value = |x: usize, y: usize| values[y * width + x];
+                let bottom = value(x0, y0) * (1.0 - tx) + value(x1, y0) * tx;
+                let top = value(x0, y1) * (1.0 - tx) + value(x1, y1) * tx;
+                bottom * (1.0 - ty) + top * ty
+            }
+        }
+    }
 }
 
 impl ClothBuilder {
     pub fn build(self) -> Cloth {
-        let resolution = self.resolution;
-        let num_vertices = resolution * resolution;
+        let (columns, rows) = (self.num_columns(), self.num_rows());
+        let num_vertices = columns * rows;
         let mut vertices = Vec::with_capacity(num_vertices * 3);
-        let cell_size = self.size / ((resolution as Number) - 1.0);
-        for i in 0..resolution {
-            for j in 0..resolution {
+        let dx = self.width / self.width_segments as Number;
+        let dy = self.height / self.height_segments as Number;
+        for i in 0..columns {
+            for j in 0..rows {
                 let local_point = Point3::new(
-                    -0.5 * self.size + i as Number * cell_size,
-                    -0.5 * self.size + j as Number * cell_size,
+                    -0.5 * self.width + i as Number * dx,
+                    -0.5 * self.height + j as Number * dy,
                     0.0,
                 );
                 let point = self.transform * local_point;
@@ -143,27 +1210,37 @@ impl ClothBuilder {
             let p1 = Vector3::from_column_slice(&vertices[j * 3..j * 3 + 3]);
             (p0 - p1).magnitude()
         };
+        let stiffness = |base: Number, index0: usize, index1: usize| {
+            let Some(map) = &self.stiffness_map else {
+                return base;
+            };
+            // grid coordinates of the midpoint of the spring
+            let u = (index0 / rows + index1 / rows) as Number / (2 * self.width_segments) as Number;
+            let v =
+                (index0 % rows + index1 % rows) as Number / (2 * self.height_segments) as Number;
+            base * map.value_at(u, v)
+        };
 
         //generate structural springs
         let mut springs = vec![];
-        for i in 0..resolution {
-            for j in 0..resolution {
-                let index = i * resolution + j;
-                if i + 1 < resolution {
-                    let index1 = (i + 1) * resolution + j;
+        for i in 0..columns {
+            for j in 0..rows {
+                let index = i * rows + j;
+                if i + 1 < columns {
+                    let index1 = (i + 1) * rows + j;
                     springs.push(Spring {
                         particle_index_0: index,
                         particle_index_1: index1,
-                        stiffness: self.structural_spring_stiffness,
+                        stiffness: stiffness(self.structural_spring_stiffness, index, index1),
                         rest_length: rest_length(index, index1),
                     });
                 }
-                if j + 1 < resolution {
-                    let index1 = i * resolution + j + 1;
+                if j + 1 < rows {
+                    let index1 = i * rows + j + 1;
                     springs.push(Spring {
                         particle_index_0: index,
                         particle_index_1: index1,
-                        stiffness: self.structural_spring_stiffness,
+                        stiffness: stiffness(self.structural_spring_stiffness, index, index1),
                         rest_length: rest_length(index, index1),
                     });
                 }
@@ -171,36 +1248,70 @@ impl ClothBuilder {
         }
 
         //generate shear springs
-        for i in 0..resolution {
-            for j in 0..resolution {
-                let index = i * resolution + j;
-                if i + 1 < resolution && j + 1 < resolution {
-                    let index1 = (i + 1) * resolution + j + 1;
+        for i in 0..columns {
+            for j in 0..rows {
+                let index = i * rows + j;
+                if i + 1 < columns && j + 1 < rows {
+                    let index1 = (i + 1) * rows + j + 1;
                     springs.push(Spring {
                         particle_index_0: index,
                         particle_index_1: index1,
-                        stiffness: self.shear_spring_stiffness,
+                        stiffness: stiffness(self.shear_spring_stiffness, index, index1),
                         rest_length: rest_length(index, index1),
                     });
                 }
-                if i + 1 < resolution && j > 0 {
-                    let index1 = (i + 1) * resolution + j - 1;
+                if i + 1 < columns && j > 0 {
+                    let index1 = (i + 1) * rows + j - 1;
                     springs.push(Spring {
                         particle_index_0: index,
                         particle_index_1: index1,
-                        stiffness: self.shear_spring_stiffness,
+                        stiffness: stiffness(self.shear_spring_stiffness, index, index1),
                         rest_length: rest_length(index, index1),
                     });
                 }
             }
         }
-        Cloth {
+        // same triangulation as `GridPlaneBuilder`
+        let mut triangles = Vec::with_capacity(self.width_segments * self.height_segments * 2);
+        for i in 0..self.width_segments {
+            for j in 0..self.height_segments {
+                let i0 = i * rows + j;
+                let i1 = i0 + 1;
+                let i2 = i0 + rows;
+                let i3 = i2 + 1;
+                triangles.push([i0, i2, i1]);
+                triangles.push([i1, i2, i3]);
+            }
+        }
+        let mut cloth = Cloth {
             particle_masses: vec![particle_mass; num_vertices],
             particle_positions: DVector::from_vec(vertices.clone()),
             prev_particle_positions: DVector::from_vec(vertices),
             springs,
             attachments: vec![],
-        }
+            long_range_attachments: vec![],
+            seams: vec![],
+            area_constraints: vec![],
+            volume_constraints: vec![],
+            triangles: vec![],
+            surface_bvh: Bvh::default(),
+            triangle_aabbs: vec![],
+            refined_particles: vec![],
+        };
+        cloth.set_triangles(triangles);
+        cloth
+    }
+
+    /// Number of vertices along x, each a column of the grid.
+    #[inline]
+    pub fn num_columns(&self) -> usize {
+        self.width_segments + 1
+    }
+
+    /// Number of vertices along y, each a row of the grid.
+    #[inline]
+    pub fn num_rows(&self) -> usize {
+        self.height_segments + 1
     }
 
     pub fn down_left_vertex_index(&self) -> usize {
@@ -208,14 +1319,46 @@ impl ClothBuilder {
     }
 
     pub fn top_left_vertex_index(&self) -> usize {
-        self.resolution - 1
+        self.height_segments
     }
 
     pub fn down_right_vertex_index(&self) -> usize {
-        self.resolution * (self.resolution - 1)
+        self.width_segments * self.num_rows()
     }
 
     pub fn top_right_vertex_index(&self) -> usize {
-        self.resolution * self.resolution - 1
+        self.width_segments * self.num_rows() + self.height_segments
+    }
+
+    /// The vertices along +y, from -x to x.
+    pub fn top_row_indices(&self) -> impl Iterator<Item = usize> {
+        let rows = self.num_rows();
+        (0..self.num_columns()).map(move |i| i * rows + rows - 1)
+    }
+
+    /// The vertices along -y, from -x to x.
+    pub fn bottom_row_indices(&self) -> impl Iterator<Item = usize> {
+        let rows = self.num_rows();
+        (0..self.num_columns()).map(move |i| i * rows)
+    }
+
+    /// The vertices along -x, from -y to y.
+    pub fn left_column_indices(&self) -> impl Iterator<Item = usize> {
+        0..self.num_rows()
+    }
+
+    /// The vertices along +x, from -y to y.
+    pub fn right_column_indices(&self) -> impl Iterator<Item = usize> {
+        let start = self.down_right_vertex_index();
+        start..start + self.num_rows()
+    }
+
+    /// The vertices on the border of the grid, each once.
+    pub fn border_indices(&self) -> impl Iterator<Item = usize> {
+        let (columns, rows) = (self.num_columns(), self.num_rows());
+        (0..columns * rows).filter(move |&index| {
+            let (i, j) = (index / rows, index % rows);
+            i == 0 || j == 0 || i + 1 == columns || j + 1 == rows
+        })
     }
 }
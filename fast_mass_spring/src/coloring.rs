@@ -0,0 +1,71 @@
+use crate::cloth::Spring;
+
+/// A partition of constraints into colors, such that no two constraints of the same color
+/// share a particle. The constraints of a color can be projected onto the particle positions
+/// in parallel without write conflicts, e.g. in Gauss-Seidel style sweeps.
+#[derive(Debug, Clone, Default)]
+pub struct ConstraintColoring {
+    colors: Vec<Vec<usize>>,
+}
+
+impl ConstraintColoring {
+    /// Greedy coloring. `constraints` yields the particles touched by each constraint, the
+    /// colors hold the indices of the constraints in that order.
+    pub fn build<'a>(
+        num_particles: usize,
+        constraints: impl IntoIterator<Item = &'a [usize]>,
+    ) -> Self {
+        // bit set of the colors already used by the constraints around each particle
+        let mut particle_colors: Vec<u64> = vec![0; num_particles];
+        // the overflow of constraints that do not fit into the first 64 colors
+        let mut overflow: Vec<usize> = vec![];
+        let mut colors: Vec<Vec<usize>> = vec![];
+        for (constraint_index, particles) in constraints.into_iter().enumerate() {
+            let used = particles
+                .iter()
+                .fold(0u64, |used, &particle| used | particle_colors[particle]);
+            let color = (!used).trailing_zeros() as usize;
+            if color >= u64::BITS as usize {
+                overflow.push(constraint_index);
+                continue;
+            }
+            for &particle in particles {
+                particle_colors[particle] |= 1 << color;
+            }
+            if color == colors.len() {
+                colors.push(vec![]);
+            }
+            colors[color].push(constraint_index);
+        }
+        // one color each, so they never conflict
+        colors.extend(
+            overflow
+                .into_iter()
+                .map(|constraint_index| vec![constraint_index]),
+        );
+        Self { colors }
+    }
+
+    pub fn from_springs(num_particles: usize, springs: &[Spring]) -> Self {
+        let particles: Vec<[usize; 2]> = springs
+            .iter()
+            .map(|spring| [spring.particle_index_0, spring.particle_index_1])
+            .collect();
+        Self::build(num_particles, particles.iter().map(|p| p.as_slice()))
+    }
+
+    #[inline]
+    pub fn num_colors(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// The indices of the constraints with the given color.
+    #[inline]
+    pub fn color(&self, color: usize) -> &[usize] {
+        &self.colors[color]
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = &[usize]> {
+        self.colors.iter().map(|c| c.as_slice())
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    fs,
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use simulation::{compute_vertex_normals, write_obj, write_ply};
+
+use crate::{
+    cloth::Cloth,
+    math::{Number, Vector3},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshFormat {
+    Obj,
+    Ply,
+}
+
+impl MeshFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            MeshFormat::Obj => "obj",
+            MeshFormat::Ply => "ply",
+        }
+    }
+}
+
+/// Write the cloth surface to numbered mesh files, one per exported frame, e.g. to import the
+/// simulation into a DCC tool for offline rendering.
+pub struct MeshSequenceExporter {
+    output_dir: PathBuf,
+    format: MeshFormat,
+    write_normals: bool,
+    next_frame: usize,
+}
+
+impl MeshSequenceExporter {
+    /// The files are written as `<output_dir>/frame_00000.<ext>`, ... The directory is
+    /// created on the first export.
+    pub fn new(output_dir: impl Into<PathBuf>, format: MeshFormat) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+            format,
+            write_normals: false,
+            next_frame: 0,
+        }
+    }
+
+    /// Also write area weighted vertex normals.
+    pub fn set_write_normals(&mut self, write_normals: bool) {
+        self.write_normals = write_normals;
+    }
+
+    #[inline]
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    /// The number of frames exported so far.
+    #[inline]
+    pub fn num_frames(&self) -> usize {
+        self.next_frame
+    }
+
+    /// Write the current particle positions with the triangle `indices`, usually the render
+    /// indices of the cloth, and return the path of the file.
+    pub fn export_frame(&mut self, cloth: &Cloth, indices: &[u32]) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.output_dir)?;
+        let path = self.output_dir.join(format!(
+            "frame_{:05}.{}",
+            self.next_frame,
+            self.format.extension()
+        ));
+        let vertices: Vec<Vector3> = (0..cloth.num_particles())
+            .map(|i| cloth.get_particle_position(i))
+            .collect();
+        let normals = self
+            .write_normals
+            .then(|| compute_vertex_normals(&vertices, indices));
+        let file = BufWriter::new(fs::File::create(&path)?);
+        match self.format {
+            MeshFormat::Obj => write_obj(file, &vertices, indices, normals.as_deref())?,
+            MeshFormat::Ply => write_ply(file, &vertices, indices, normals.as_deref())?,
+        }
+        self.next_frame += 1;
+        Ok(path)
+    }
+}
+
+/// Record the cloth over a simulation and write it as a glTF asset with one morph target per
+/// frame, animated by stepping the morph weights. Game engines and viewers play it back
+/// without the solver.
+///
+/// The first recorded frame is the base mesh. Every frame is kept in memory until written.
+pub struct GltfAnimationRecorder {
+    indices: Vec<u32>,
+    /// `(time, positions)` of each recorded frame.
+    frames: Vec<(Number, Vec<Vector3>)>,
+}
+
+impl GltfAnimationRecorder {
+    /// Record the triangles `indices`, usually the render indices of the cloth.
+    pub fn new(indices: &[u32]) -> Self {
+        Self {
+            indices: indices.to_vec(),
+            frames: vec![],
+        }
+    }
+
+    /// Record the current particle positions at `time`, e.g. the solver time. Times must
+    /// increase from one frame to the next, and the number of particles must not change.
+    pub fn record_frame(&mut self, cloth: &Cloth, time: Number) {
+        let positions: Vec<Vector3> = (0..cloth.num_particles())
+            .map(|i| cloth.get_particle_position(i))
+            .collect();
+        if let Some((_, base)) = self.frames.first() {
+            assert_eq!(base.len(), positions.len(), "the particle count changed");
+        }
+        self.frames.push((time, positions));
+    }
+
+    #[inline]
+    pub fn num_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Write the `.gltf` file at `path` and its buffer next to it, with the `.bin` extension.
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let Some((start_time, base)) = self.frames.first() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no frame was recorded",
+            ));
+        };
+        let num_vertices = base.len();
+        let num_frames = self.frames.len();
+
+        let mut buffer: Vec<u8> = vec![];
+        let mut accessors: Vec<String> = vec![];
+        // buffer view 0: indices
+        for index in &self.indices {
+            buffer.extend_from_slice(&index.to_le_bytes());
+        }
+        let indices_length = buffer.len();
+        accessors.push(format!(
+            r#"{{"bufferView":0,"componentType":5125,"count":{},"type":"SCALAR"}}"#,
+            self.indices.len()
+        ));
+
+        // buffer view 1: base positions, then the displacement of each frame
+        let vertices_offset = buffer.len();
+        let vec3_accessor = |buffer: &mut Vec<u8>, vectors: &[Vector3]| {
+            let (min, max) = vectors.iter().fold(
+                (Vector3::repeat(Number::MAX), Vector3::repeat(Number::MIN)),
+                |(min, max), v| (min.inf(v), max.sup(v)),
+            );
+            let accessor = format!(
+                r#"{{"bufferView":1,"byteOffset":{},"componentType":5126,"count":{},"type":"VEC3","min":[{},{},{}],"max":[{},{},{}]}}"#,
+                buffer.len() - vertices_offset,
+                vectors.len(),
+                min.x,
+                min.y,
+                min.z,
+                max.x,
+                max.y,
+                max.z
+            );
+            push_floats(buffer, vectors.iter().flat_map(|v| [v.x, v.y, v.z]));
+            accessor
+        };
+        accessors.push(vec3_accessor(&mut buffer, base));
+        let mut displacement = Vec::with_capacity(num_vertices);
+        for (_, positions) in &self.frames {
+            displacement.clear();
+            displacement.extend(positions.iter().zip(base).map(|(p, b)| p - b));
+            accessors.push(vec3_accessor(&mut buffer, &displacement));
+        }
+        let vertices_length = buffer.len() - vertices_offset;
+
+        // buffer view 2: the key times, then a one hot weight vector per key
+        let animation_offset = buffer.len();
+        let last_time = self
+            .frames
+            .last()
+            .map_or(0.0, |(time, _)| time - start_time);
+        accessors.push(format!(
+            r#"{{"bufferView":2,"componentType":5126,"count":{},"type":"SCALAR","min":[0],"max":[{}]}}"#,
+            num_frames, last_time
+        ));
+        push_floats(
+            &mut buffer,
+            self.frames.iter().map(|(time, _)| time - start_time),
+        );
+        accessors.push(format!(
+            r#"{{"bufferView":2,"byteOffset":{},"componentType":5126,"count":{},"type":"SCALAR"}}"#,
+            buffer.len() - animation_offset,
+            num_frames * num_frames
+        ));
+        let weights = (0..num_frames).flat_map(|key| {
+            (0..num_frames).map(move |target| if key == target { 1.0 } else { 0.0 })
+        });
+        push_floats(&mut buffer, weights);
+        let animation_length = buffer.len() - animation_offset;
+
+        let bin_path = path.with_extension("bin");
+        let bin_uri = bin_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid file name"))?;
+        let targets = (0..num_frames)
+            .map(|frame| format!(r#"{{"POSITION":{}}}"#, frame + 2))
+            .collect::<Vec<_>>()
+            .join(",");
+        let initial_weights = vec!["0"; num_frames].join(",");
+        let times_accessor = num_frames + 2;
+        let json = format!(
+            r#"{{
+"asset":{{"version":"2.0","generator":"physics-rs"}},
+"scene":0,
+"scenes":[{{"nodes":[0]}}],
+"nodes":[{{"mesh":0}}],
+"meshes":[{{"primitives":[{{"attributes":{{"POSITION":1}},"indices":0,"mode":4,"targets":[{}]}}],"weights":[{}]}}],
+"animations":[{{"channels":[{{"sampler":0,"target":{{"node":0,"path":"weights"}}}}],"samplers":[{{"input":{},"output":{},"interpolation":"LINEAR"}}]}}],
+"accessors":[{}],
+"bufferViews":[{{"buffer":0,"byteOffset":0,"byteLength":{},"target":34963}},{{"buffer":0,"byteOffset":{},"byteLength":{},"byteStride":12,"target":34962}},{{"buffer":0,"byteOffset":{},"byteLength":{}}}],
+"buffers":[{{"uri":"{}","byteLength":{}}}]
+}}
+"#,
+            targets,
+            initial_weights,
+            times_accessor,
+            times_accessor + 1,
+            accessors.join(","),
+            indices_length,
+            vertices_offset,
+            vertices_length,
+            animation_offset,
+            animation_length,
+            bin_uri,
+            buffer.len()
+        );
+        fs::write(&bin_path, &buffer)?;
+        fs::write(path, json)
+    }
+}
+
+fn push_floats(buffer: &mut Vec<u8>, values: impl IntoIterator<Item = f32>) {
+    for value in values {
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+}
@@ -1,4 +1,19 @@
+/// Enter a tracing span until the end of the enclosing block. Expands to nothing without the
+/// `tracing` feature.
+macro_rules! profile_span {
+    ($name:expr) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($name).entered();
+    };
+}
+
+pub mod acceleration;
 pub mod cloth;
+pub mod coloring;
+pub mod export;
+pub mod refine;
 pub mod solver;
+pub mod wind;
+pub mod world;
 
 use simulation::math;
@@ -0,0 +1,699 @@
+use std::collections::{BTreeMap, HashMap};
+
+use nalgebra::Vector2;
+use simulation::{Aabb, TransformedCollider};
+
+use crate::{
+    cloth::{AreaConstraint, Cloth, LongRangeAttachment, Spring},
+    math::{DVector, Number, Vector3},
+};
+
+/// When [`Cloth::refine`] splits an edge and [`Cloth::coarsen`] collapses it back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RefinementCriteria {
+    /// Split the edges whose two triangles bend by more than this angle, in radians.
+    pub max_dihedral_angle: Number,
+    /// Collapse the refined particles whose triangles all bend by less than this angle, in
+    /// radians. Keep it below `max_dihedral_angle`, or collapsed edges are split again.
+    pub coarsen_dihedral_angle: Number,
+    /// Split the edges whose midpoint is within this distance of a collider, and keep the
+    /// refined particles this close from being collapsed. Zero disables refinement near
+    /// contacts.
+    pub contact_distance: Number,
+    /// Edges shorter than this are never split.
+    pub min_edge_length: Number,
+    /// Stop splitting once the cloth has this many particles.
+    pub max_particles: usize,
+}
+
+impl Default for RefinementCriteria {
+    fn default() -> Self {
+        Self {
+            max_dihedral_angle: 0.5,
+            coarsen_dihedral_angle: 0.1,
+            contact_distance: 0.0,
+            min_edge_length: 0.05,
+            max_particles: 2048,
+        }
+    }
+}
+
+/// A particle added at the midpoint of the edge between its two parents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefinedParticle {
+    pub particle_index: usize,
+    pub parents: [usize; 2],
+}
+
+/// An edge split by [`Cloth::insert_edge_midpoint`].
+struct Split {
+    a: usize,
+    b: usize,
+    midpoint: usize,
+    /// Rest length of the whole edge.
+    rest_length: Number,
+}
+
+/// The triangles and the spring of every edge, keyed by its particles in increasing order,
+/// kept up to date while edges are split.
+struct EdgeMap {
+    triangles: HashMap<(usize, usize), Vec<usize>>,
+    springs: HashMap<(usize, usize), usize>,
+}
+
+impl EdgeMap {
+    fn new(cloth: &Cloth) -> Self {
+        let mut triangles: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (triangle_index, triangle) in cloth.triangles().iter().enumerate() {
+            for k in 0..3 {
+                triangles
+                    .entry(edge(triangle[k], triangle[(k + 1) % 3]))
+                    .or_default()
+                    .push(triangle_index);
+            }
+        }
+        let springs = cloth
+            .springs
+            .iter()
+            .enumerate()
+            .map(|(spring_index, spring)| {
+                (
+                    edge(spring.particle_index_0, spring.particle_index_1),
+                    spring_index,
+                )
+            })
+            .collect();
+        Self { triangles, springs }
+    }
+
+    fn add_triangle(&mut self, a: usize, b: usize, triangle_index: usize) {
+        self.triangles
+            .entry(edge(a, b))
+            .or_default()
+            .push(triangle_index);
+    }
+
+    fn replace_triangle(&mut self, a: usize, b: usize, old: usize, new: usize) {
+        if let Some(triangles) = self.triangles.get_mut(&edge(a, b)) {
+            for triangle_index in triangles.iter_mut().filter(|t| **t == old) {
+                *triangle_index = new;
+            }
+        }
+    }
+}
+
+#[inline]
+fn edge(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+impl Cloth {
+    /// Split the edge between particles `a` and `b` at its midpoint. Returns the index of the
+    /// new particle, or `None` if no triangle has this edge.
+    ///
+    /// The triangles on both sides of the edge are split in two. The spring along the edge is
+    /// halved and springs from the midpoint to the opposite particles are added with the same
+    /// stiffness. The new particle takes a quarter of the mass of each end. The area and
+    /// volume constraints of the triangles are split along, and the midpoint gets a long
+    /// range attachment to the anchor of the closer end.
+    pub fn split_edge(&mut self, a: usize, b: usize) -> Option<usize> {
+        let mut edges = EdgeMap::new(self);
+        let split = self.insert_edge_midpoint(a, b, &mut edges)?;
+        let particle_index = split.midpoint;
+        self.split_constraints(&[split]);
+        let triangles = std::mem::take(self.triangles_mut());
+        self.set_triangles(triangles);
+        Some(particle_index)
+    }
+
+    /// Split the edges matching `criteria` once, longest first. An edge is skipped if one of
+    /// its triangles was already split in this pass, so call it again to refine further.
+    /// Returns the number of edges split.
+    ///
+    /// The new particles are appended and recorded in [`Self::refined_particles`], so that
+    /// [`Self::coarsen`] can collapse them once the region flattens. The solver constraint set
+    /// must be rebuilt afterwards, see
+    /// [`FastMassSpringSolver::refine`](crate::solver::FastMassSpringSolver::refine).
+    pub fn refine(
+        &mut self,
+        criteria: &RefinementCriteria,
+        colliders: &[TransformedCollider],
+    ) -> usize {
+        let num_triangles = self.triangles().len();
+        let mut edges = EdgeMap::new(self);
+        let normals = self.triangle_normals();
+        let collider_aabbs = contact_aabbs(colliders, criteria.contact_distance);
+
+        let mut candidates = vec![];
+        for (&(a, b), triangles) in &edges.triangles {
+            let pa = self.get_particle_position(a);
+            let pb = self.get_particle_position(b);
+            let length = (pb - pa).magnitude();
+            if length <= criteria.min_edge_length {
+                continue;
+            }
+            let bend = match triangles[..] {
+                [t0, t1] => match (normals[t0], normals[t1]) {
+                    (Some(n0), Some(n1)) => n0.dot(&n1).clamp(-1.0, 1.0).acos(),
+                    _ => 0.0,
+                },
+                _ => 0.0,
+            };
+            let near_contact = criteria.contact_distance > 0.0
+                && is_near_contact(
+                    colliders,
+                    &collider_aabbs,
+                    &((pa + pb) * 0.5),
+                    criteria.contact_distance,
+                );
+            if bend > criteria.max_dihedral_angle || near_contact {
+                candidates.push((length, a, b));
+            }
+        }
+        // the map is visited in a random order, so edges of the same length, common on a
+        // regular grid, are ordered by their particles to split the same edges on every run
+        candidates.sort_by(|x, y| {
+            y.0.total_cmp(&x.0)
+                .then_with(|| (x.1, x.2).cmp(&(y.1, y.2)))
+        });
+
+        // the triangles added by the splits of this pass count as split
+        let mut split_triangles = vec![false; num_triangles];
+        let mut splits = vec![];
+        for (_, a, b) in candidates {
+            if self.num_particles() >= criteria.max_particles {
+                break;
+            }
+            let triangles = &edges.triangles[&(a, b)];
+            if triangles
+                .iter()
+                .any(|&t| split_triangles.get(t).copied().unwrap_or(true))
+            {
+                continue;
+            }
+            for &t in triangles {
+                split_triangles[t] = true;
+            }
+            splits.extend(self.insert_edge_midpoint(a, b, &mut edges));
+        }
+        if !splits.is_empty() {
+            self.split_constraints(&splits);
+            let triangles = std::mem::take(self.triangles_mut());
+            self.set_triangles(triangles);
+        }
+        splits.len()
+    }
+
+    /// Collapse the refined particles whose triangles are flat by `criteria` and that are not
+    /// near a collider back into the edge they split, newest first. A particle is kept while
+    /// an attachment or a seam refers to it, while it is the parent of another refined
+    /// particle, or when its triangles were edited since the split. Returns the number of
+    /// particles removed.
+    ///
+    /// The refined particles after a removed one shift down, the other particles keep their
+    /// indices. The solver constraint set must be rebuilt afterwards, see
+    /// [`FastMassSpringSolver::coarsen`](crate::solver::FastMassSpringSolver::coarsen).
+    pub fn coarsen(
+        &mut self,
+        criteria: &RefinementCriteria,
+        colliders: &[TransformedCollider],
+    ) -> usize {
+        if self.refined_particles().is_empty() {
+            return 0;
+        }
+        let num_particles = self.num_particles();
+        let mut particle_triangles: Vec<Vec<usize>> = vec![vec![]; num_particles];
+        for (triangle_index, triangle) in self.triangles().iter().enumerate() {
+            for &particle_index in triangle {
+                particle_triangles[particle_index].push(triangle_index);
+            }
+        }
+        let normals = self.triangle_normals();
+        let collider_aabbs = contact_aabbs(colliders, criteria.contact_distance);
+
+        let mut kept = vec![false; num_particles];
+        for attachment in &self.attachments {
+            kept[attachment.particle_index] = true;
+        }
+        for seam in &self.seams {
+            kept[seam.particle_index_0] = true;
+            kept[seam.particle_index_1] = true;
+        }
+        for lra in &self.long_range_attachments {
+            kept[lra.anchor_index] = true;
+        }
+        for refined in self.refined_particles() {
+            for &parent in &refined.parents {
+                kept[parent] = true;
+            }
+        }
+
+        let mut collapsed_triangles = vec![false; self.triangles().len()];
+        let mut collapses = vec![];
+        for &refined in self.refined_particles().iter().rev() {
+            let m = refined.particle_index;
+            let ring = &particle_triangles[m];
+            if kept[m]
+                || !self.is_split_ring(refined, ring)
+                || ring.iter().any(|&t| collapsed_triangles[t])
+            {
+                continue;
+            }
+            let flat = ring.iter().all(|&t0| {
+                ring.iter().all(|&t1| match (normals[t0], normals[t1]) {
+                    (Some(n0), Some(n1)) => {
+                        n0.dot(&n1).clamp(-1.0, 1.0).acos() <= criteria.coarsen_dihedral_angle
+                    }
+                    _ => true,
+                })
+            });
+            let near_contact = criteria.contact_distance > 0.0
+                && is_near_contact(
+                    colliders,
+                    &collider_aabbs,
+                    &self.get_particle_position(m),
+                    criteria.contact_distance,
+                );
+            if !flat || near_contact {
+                continue;
+            }
+            for &t in ring {
+                collapsed_triangles[t] = true;
+            }
+            collapses.push(refined);
+        }
+        if !collapses.is_empty() {
+            self.collapse_midpoints(&collapses, &particle_triangles);
+        }
+        collapses.len()
+    }
+
+    /// Split an edge and update the edge map, without updating the constraints or
+    /// rebuilding the surface hierarchy.
+    fn insert_edge_midpoint(&mut self, a: usize, b: usize, edges: &mut EdgeMap) -> Option<Split> {
+        let adjacent = edges.triangles.remove(&edge(a, b))?;
+        let opposite: Vec<usize> = adjacent
+            .iter()
+            .map(|&triangle_index| {
+                self.triangles()[triangle_index]
+                    .into_iter()
+                    .find(|&i| i != a && i != b)
+                    .expect("triangle with a repeated particle")
+            })
+            .collect();
+
+        let m = self.num_particles();
+        let midpoint = |positions: &DVector| {
+            (positions.fixed_rows::<3>(a * 3) + positions.fixed_rows::<3>(b * 3)) * 0.5
+        };
+        let position: Vector3 = midpoint(&self.particle_positions);
+        let prev_position: Vector3 = midpoint(&self.prev_particle_positions);
+        push_point(&mut self.particle_positions, &position);
+        push_point(&mut self.prev_particle_positions, &prev_position);
+        let mass = 0.25 * (self.particle_masses[a] + self.particle_masses[b]);
+        self.particle_masses[a] *= 0.75;
+        self.particle_masses[b] *= 0.75;
+        self.particle_masses.push(mass);
+
+        let edge_spring = edges.springs.remove(&edge(a, b));
+        let (stiffness, edge_rest_length) = match edge_spring {
+            Some(index) => (
+                self.springs[index].stiffness,
+                self.springs[index].rest_length,
+            ),
+            None => {
+                // the stiffness of another edge of the triangles
+                let stiffness = opposite
+                    .iter()
+                    .flat_map(|&c| [edge(a, c), edge(b, c)])
+                    .find_map(|e| edges.springs.get(&e))
+                    .map_or(0.0, |&index| self.springs[index].stiffness);
+                (
+                    stiffness,
+                    (self.get_particle_position(b) - self.get_particle_position(a)).magnitude(),
+                )
+            }
+        };
+        let half = Spring {
+            particle_index_0: a,
+            particle_index_1: m,
+            stiffness,
+            rest_length: 0.5 * edge_rest_length,
+        };
+        let half_index = match edge_spring {
+            Some(index) => {
+                self.springs[index] = half.clone();
+                index
+            }
+            None => {
+                self.springs.push(half.clone());
+                self.springs.len() - 1
+            }
+        };
+        edges.springs.insert(edge(a, m), half_index);
+        edges.springs.insert(edge(m, b), self.springs.len());
+        self.springs.push(Spring {
+            particle_index_0: m,
+            particle_index_1: b,
+            ..half
+        });
+
+        for (triangle_index, c) in adjacent.into_iter().zip(opposite) {
+            // length of the median from c, if the rest lengths of the triangle are known
+            let rest_length = match (
+                edges.springs.get(&edge(a, c)),
+                edges.springs.get(&edge(b, c)),
+            ) {
+                (Some(&ac), Some(&bc)) => {
+                    let ac = self.springs[ac].rest_length;
+                    let bc = self.springs[bc].rest_length;
+                    (0.5 * (ac * ac + bc * bc) - 0.25 * edge_rest_length * edge_rest_length)
+                        .max(0.0)
+                        .sqrt()
+                }
+                _ => (self.get_particle_position(c) - position).magnitude(),
+            };
+            edges.springs.insert(edge(m, c), self.springs.len());
+            self.springs.push(Spring {
+                particle_index_0: m,
+                particle_index_1: c,
+                stiffness,
+                rest_length,
+            });
+            // (a, b, c) becomes (a, m, c) and (m, b, c), keeping the orientation
+            let triangles = self.triangles_mut();
+            let triangle = triangles[triangle_index];
+            let new_index = triangles.len();
+            triangles[triangle_index] = triangle.map(|i| if i == b { m } else { i });
+            triangles.push(triangle.map(|i| if i == a { m } else { i }));
+            edges.add_triangle(a, m, triangle_index);
+            edges.add_triangle(m, b, new_index);
+            edges.add_triangle(m, c, triangle_index);
+            edges.add_triangle(m, c, new_index);
+            edges.replace_triangle(b, c, triangle_index, new_index);
+        }
+        self.refined_particles_mut().push(RefinedParticle {
+            particle_index: m,
+            parents: [a, b],
+        });
+        Some(Split {
+            a,
+            b,
+            midpoint: m,
+            rest_length: edge_rest_length,
+        })
+    }
+
+    /// Split the area and volume constraints of the split triangles, and limit the midpoints
+    /// by the long range attachment of the closer end.
+    fn split_constraints(&mut self, splits: &[Split]) {
+        let midpoints: HashMap<(usize, usize), usize> = splits
+            .iter()
+            .map(|split| (edge(split.a, split.b), split.midpoint))
+            .collect();
+
+        let mut halves = vec![];
+        for area in &mut self.area_constraints {
+            let indices = area.particle_indices;
+            let Some((ka, m)) = (0..3).find_map(|k| {
+                let m = midpoints.get(&edge(indices[k], indices[(k + 1) % 3]))?;
+                Some((k, *m))
+            }) else {
+                continue;
+            };
+            let kb = (ka + 1) % 3;
+            let points = area.rest_points();
+            let midpoint = (points[ka] + points[kb]) * 0.5;
+            let half = |k: usize| {
+                let mut half_indices = indices;
+                let mut half_points = points;
+                half_indices[k] = m;
+                half_points[k] = midpoint;
+                AreaConstraint::from_rest_points(half_indices, half_points, area.stiffness)
+            };
+            // a sliver too thin to split keeps constraining the particles of the old triangle
+            if let (Some(first), Some(second)) = (half(kb), half(ka)) {
+                *area = first;
+                halves.push(second);
+            }
+        }
+        self.area_constraints.extend(halves);
+
+        for volume in &mut self.volume_constraints {
+            volume.split_edges(&midpoints);
+        }
+
+        if self.long_range_attachments.is_empty() {
+            return;
+        }
+        let mut particle_lras: HashMap<usize, usize> = HashMap::new();
+        let mut anchors: HashMap<usize, Number> = HashMap::new();
+        for (index, lra) in self.long_range_attachments.iter().enumerate() {
+            particle_lras.entry(lra.particle_index).or_insert(index);
+            anchors.entry(lra.anchor_index).or_insert(lra.stiffness);
+        }
+        // (anchor, max distance, stiffness) bounding a particle
+        let bound = |particle_index: usize| {
+            if let Some(&stiffness) = anchors.get(&particle_index) {
+                return Some((particle_index, 0.0, stiffness));
+            }
+            let lra = &self.long_range_attachments[*particle_lras.get(&particle_index)?];
+            Some((lra.anchor_index, lra.max_distance, lra.stiffness))
+        };
+        let added: Vec<LongRangeAttachment> = splits
+            .iter()
+            .filter_map(|split| {
+                let (anchor_index, max_distance, stiffness) = [bound(split.a), bound(split.b)]
+                    .into_iter()
+                    .flatten()
+                    .min_by(|x, y| x.1.total_cmp(&y.1))?;
+                Some(LongRangeAttachment {
+                    particle_index: split.midpoint,
+                    anchor_index,
+                    max_distance: max_distance + 0.5 * split.rest_length,
+                    stiffness,
+                })
+            })
+            .collect();
+        self.long_range_attachments.extend(added);
+    }
+
+    /// Whether the triangles around a refined particle are still the ones of its split:
+    /// for each opposite particle `c`, one triangle with each parent.
+    fn is_split_ring(&self, refined: RefinedParticle, ring: &[usize]) -> bool {
+        let [a, b] = refined.parents;
+        if ring.len() != 2 && ring.len() != 4 {
+            return false;
+        }
+        let (mut with_a, mut with_b) = (vec![], vec![]);
+        for &triangle_index in ring {
+            let triangle = self.triangles()[triangle_index];
+            let Some(c) = triangle
+                .into_iter()
+                .find(|&i| i != refined.particle_index && i != a && i != b)
+            else {
+                return false;
+            };
+            match (triangle.contains(&a), triangle.contains(&b)) {
+                (true, false) => with_a.push(c),
+                (false, true) => with_b.push(c),
+                _ => return false,
+            }
+        }
+        with_a.sort_unstable();
+        with_b.sort_unstable();
+        with_a == with_b
+    }
+
+    /// Undo the splits of `collapses`: merge the triangles, springs, area and volume
+    /// constraints of each midpoint back, give its mass to its parents and remove it.
+    fn collapse_midpoints(
+        &mut self,
+        collapses: &[RefinedParticle],
+        particle_triangles: &[Vec<usize>],
+    ) {
+        let parents: HashMap<usize, [usize; 2]> = collapses
+            .iter()
+            .map(|refined| (refined.particle_index, refined.parents))
+            .collect();
+
+        // (a, m, c) becomes (a, b, c) again and (m, b, c) is removed
+        let mut removed_triangles = vec![false; self.triangles().len()];
+        let triangles = self.triangles_mut();
+        for refined in collapses {
+            let m = refined.particle_index;
+            let [_, b] = refined.parents;
+            for &triangle_index in &particle_triangles[m] {
+                let triangle = &mut triangles[triangle_index];
+                if triangle.contains(&b) {
+                    removed_triangles[triangle_index] = true;
+                } else {
+                    *triangle = triangle.map(|i| if i == m { b } else { i });
+                }
+            }
+        }
+        let mut triangle_index = 0;
+        triangles.retain(|_| {
+            triangle_index += 1;
+            !removed_triangles[triangle_index - 1]
+        });
+
+        // the two halves of the edge merge back, the springs to the opposite particles go
+        let mut half_springs: HashMap<usize, Vec<Spring>> = HashMap::new();
+        self.springs.retain(|spring| {
+            let (i, j) = (spring.particle_index_0, spring.particle_index_1);
+            let (m, other) = if parents.contains_key(&i) {
+                (i, j)
+            } else if parents.contains_key(&j) {
+                (j, i)
+            } else {
+                return true;
+            };
+            if parents[&m].contains(&other) {
+                half_springs.entry(m).or_default().push(spring.clone());
+            }
+            false
+        });
+        for refined in collapses {
+            let m = refined.particle_index;
+            let [a, b] = refined.parents;
+            if let Some(halves) = half_springs.get(&m) {
+                let rest_length = halves.iter().map(|half| half.rest_length).sum::<Number>() * 2.0
+                    / halves.len() as Number;
+                self.springs.push(Spring {
+                    particle_index_0: a,
+                    particle_index_1: b,
+                    stiffness: halves[0].stiffness,
+                    rest_length,
+                });
+            }
+            let half_mass = 0.5 * self.particle_masses[m];
+            self.particle_masses[a] += half_mass;
+            self.particle_masses[b] += half_mass;
+        }
+
+        // the halves of each triangle merge back, by the opposite particle, in a fixed order
+        let mut half_areas: BTreeMap<(usize, usize), [Option<AreaConstraint>; 2]> = BTreeMap::new();
+        self.area_constraints.retain(|area| {
+            let Some(&m) = area
+                .particle_indices
+                .iter()
+                .find(|&&i| parents.contains_key(&i))
+            else {
+                return true;
+            };
+            let [a, b] = parents[&m];
+            if let Some(&c) = area
+                .particle_indices
+                .iter()
+                .find(|&&i| i != m && i != a && i != b)
+            {
+                let slot = usize::from(area.particle_indices.contains(&b));
+                half_areas.entry((m, c)).or_default()[slot] = Some(area.clone());
+            }
+            false
+        });
+        for ((m, _), halves) in half_areas {
+            if let [Some(first), Some(second)] = halves {
+                self.area_constraints
+                    .extend(merge_area_halves(&first, &second, m, parents[&m]));
+            }
+        }
+
+        for volume in &mut self.volume_constraints {
+            volume.collapse_midpoints(&parents);
+        }
+        self.long_range_attachments
+            .retain(|lra| !parents.contains_key(&lra.particle_index));
+        self.refined_particles_mut()
+            .retain(|refined| !parents.contains_key(&refined.particle_index));
+
+        let mut removed = vec![false; self.num_particles()];
+        for &m in parents.keys() {
+            removed[m] = true;
+        }
+        self.remove_particles(&removed);
+        let triangles = std::mem::take(self.triangles_mut());
+        self.set_triangles(triangles);
+    }
+
+    /// The unit normal of each triangle, `None` for a degenerate one.
+    fn triangle_normals(&self) -> Vec<Option<Vector3>> {
+        self.triangles()
+            .iter()
+            .map(|triangle| {
+                let [a, b, c] = triangle.map(|i| self.get_particle_position(i));
+                (b - a).cross(&(c - a)).try_normalize(Number::EPSILON)
+            })
+            .collect()
+    }
+}
+
+/// The bounds of the colliders expanded by `distance`, to skip the exact distance query for
+/// points far from a collider.
+fn contact_aabbs(colliders: &[TransformedCollider], distance: Number) -> Vec<Aabb> {
+    colliders
+        .iter()
+        .map(|collider| collider.aabb().expanded(distance))
+        .collect()
+}
+
+fn is_near_contact(
+    colliders: &[TransformedCollider],
+    collider_aabbs: &[Aabb],
+    point: &Vector3,
+    distance: Number,
+) -> bool {
+    colliders
+        .iter()
+        .zip(collider_aabbs)
+        .any(|(collider, aabb)| {
+            aabb.contains_point(point)
+                && collider
+                    .distance_to_point((*point).into(), distance)
+                    .is_some()
+        })
+}
+
+/// The area constraint of `(a, b, c)` from those of `(a, m, c)` in `first` and `(m, b, c)` in
+/// `second`, with the rest lengths of both.
+fn merge_area_halves(
+    first: &AreaConstraint,
+    second: &AreaConstraint,
+    m: usize,
+    [a, b]: [usize; 2],
+) -> Option<AreaConstraint> {
+    let rest_length = |area: &AreaConstraint, i: usize, j: usize| {
+        let points = area.rest_points();
+        let k = |particle_index: usize| {
+            area.particle_indices
+                .iter()
+                .position(|&p| p == particle_index)
+        };
+        Some((points[k(i)?] - points[k(j)?]).magnitude())
+    };
+    let length = |i: usize, j: usize| {
+        if edge(i, j) == edge(a, b) {
+            Some(rest_length(first, a, m)? + rest_length(second, m, b)?)
+        } else if i == b || j == b {
+            rest_length(second, i, j)
+        } else {
+            rest_length(first, i, j)
+        }
+    };
+    let indices = first.particle_indices.map(|i| if i == m { b } else { i });
+    let [i0, i1, i2] = indices;
+    let (l01, l02, l12) = (length(i0, i1)?, length(i0, i2)?, length(i1, i2)?);
+    // the third point from the law of cosines
+    let x = (l01 * l01 + l02 * l02 - l12 * l12) / (2.0 * l01);
+    let y = (l02 * l02 - x * x).max(0.0).sqrt();
+    let points = [Vector2::zeros(), Vector2::new(l01, 0.0), Vector2::new(x, y)];
+    AreaConstraint::from_rest_points(indices, points, first.stiffness)
+}
+
+/// Append a point to a vector of positions, growing its buffer in place.
+fn push_point(positions: &mut DVector, point: &Vector3) {
+    let len = positions.len();
+    *positions = std::mem::replace(positions, DVector::zeros(0)).resize_vertically(len + 3, 0.0);
+    positions.fixed_rows_mut::<3>(len).copy_from(point);
+}
@@ -1,13 +1,343 @@
-use std::ops::AddAssign;
+use std::{
+    fmt,
+    ops::{AddAssign, Range},
+    time::{Duration, Instant},
+};
 
-use nalgebra::{point, Cholesky, Dyn, Matrix3};
-use simulation::{Collider, TransformedCollider};
+use nalgebra::{point, Cholesky, Dyn, Matrix3, Point3};
+use simulation::{Aabb, Bvh, Collider, TransformedCollider};
 
 use crate::{
-    cloth::Cloth,
+    acceleration::AccelerationField,
+    cloth::{AreaConstraint, Attachment, Cloth, Spring},
+    coloring::ConstraintColoring,
     math::{DMatrix, DVector, Isometry3, Number, Vector3},
+    refine::RefinementCriteria,
+    wind::{Aerodynamics, Wind},
 };
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolverBuildError {
+    InvalidTimeStep(Number),
+    EmptyCloth,
+    InvalidMass {
+        particle_index: usize,
+        mass: Number,
+    },
+    InvalidPosition {
+        particle_index: usize,
+    },
+    ParticleIndexOutOfRange {
+        constraint: &'static str,
+        constraint_index: usize,
+        particle_index: usize,
+    },
+    DegenerateSpring {
+        spring_index: usize,
+    },
+    DegenerateSeam {
+        seam_index: usize,
+    },
+    /// A triangle of the cloth surface, an area constraint or a triangle of a volume
+    /// constraint refers to the same particle twice.
+    DegenerateTriangle {
+        constraint: &'static str,
+        constraint_index: usize,
+        triangle: [usize; 3],
+    },
+    InvalidRestLength {
+        spring_index: usize,
+        rest_length: Number,
+    },
+    InvalidStiffness {
+        constraint: &'static str,
+        constraint_index: usize,
+        stiffness: Number,
+    },
+    /// The surface of a volume constraint has holes or inconsistently oriented triangles.
+    OpenVolumeSurface {
+        volume_index: usize,
+    },
+    /// The system matrix could not be factorized.
+    NotPositiveDefinite,
+}
+
+impl fmt::Display for SolverBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverBuildError::InvalidTimeStep(time_step) => {
+                write!(
+                    f,
+                    "time step must be positive and finite, got {}",
+                    time_step
+                )
+            }
+            SolverBuildError::EmptyCloth => write!(f, "cloth has no particles"),
+            SolverBuildError::InvalidMass {
+                particle_index,
+                mass,
+            } => write!(
+                f,
+                "particle {} has mass {}, masses must be positive and finite",
+                particle_index, mass
+            ),
+            SolverBuildError::InvalidPosition { particle_index } => {
+                write!(f, "particle {} has a non finite position", particle_index)
+            }
+            SolverBuildError::ParticleIndexOutOfRange {
+                constraint,
+                constraint_index,
+                particle_index,
+            } => write!(
+                f,
+                "{} {} refers to particle {} which does not exist",
+                constraint, constraint_index, particle_index
+            ),
+            SolverBuildError::DegenerateSpring { spring_index } => {
+                write!(f, "spring {} connects a particle to itself", spring_index)
+            }
+            SolverBuildError::DegenerateSeam { seam_index } => {
+                write!(f, "seam {} connects a particle to itself", seam_index)
+            }
+            SolverBuildError::DegenerateTriangle {
+                constraint,
+                constraint_index,
+                triangle,
+            } => write!(
+                f,
+                "{} {} has the triangle {:?} with a repeated particle",
+                constraint, constraint_index, triangle
+            ),
+            SolverBuildError::InvalidRestLength {
+                spring_index,
+                rest_length,
+            } => write!(
+                f,
+                "spring {} has rest length {}, rest lengths must be non negative and finite",
+                spring_index, rest_length
+            ),
+            SolverBuildError::InvalidStiffness {
+                constraint,
+                constraint_index,
+                stiffness,
+            } => write!(
+                f,
+                "{} {} has stiffness {}, stiffness must be non negative and finite",
+                constraint, constraint_index, stiffness
+            ),
+            SolverBuildError::OpenVolumeSurface { volume_index } => write!(
+                f,
+                "volume constraint {} is not a closed surface with consistent orientation",
+                volume_index
+            ),
+            SolverBuildError::NotPositiveDefinite => {
+                write!(f, "system matrix is not positive definite")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolverBuildError {}
+
+/// Adds custom external forces each step. Called with the cloth at the start of the step, the
+/// force vector to add to, packed like the particle positions, and the simulated time.
+pub type ForceHook = Box<dyn Fn(&Cloth, &mut DVector, Number)>;
+
+/// Refers to a collider until it is removed. Removing other colliders keeps it valid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ColliderHandle(pub(crate) usize);
+
+/// Colliders in the order they were added, with a stable handle each.
+#[derive(Default)]
+pub(crate) struct ColliderSet {
+    colliders: Vec<TransformedCollider>,
+    /// The handle of each collider.
+    handles: Vec<ColliderHandle>,
+    next_handle: usize,
+}
+
+impl ColliderSet {
+    pub(crate) fn add(&mut self, collider: Collider, transform: Isometry3) -> ColliderHandle {
+        let handle = ColliderHandle(self.next_handle);
+        self.next_handle += 1;
+        self.colliders
+            .push(TransformedCollider::new(collider, transform));
+        self.handles.push(handle);
+        handle
+    }
+
+    /// Remove a collider, keeping the order of the others. Returns `None` if it was already
+    /// removed.
+    pub(crate) fn remove(&mut self, handle: ColliderHandle) -> Option<TransformedCollider> {
+        let index = self.index(handle)?;
+        self.handles.remove(index);
+        Some(self.colliders.remove(index))
+    }
+
+    #[inline]
+    pub(crate) fn index(&self, handle: ColliderHandle) -> Option<usize> {
+        self.handles.iter().position(|&h| h == handle)
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, handle: ColliderHandle) -> Option<&TransformedCollider> {
+        self.index(handle).map(|index| &self.colliders[index])
+    }
+
+    pub(crate) fn get_mut(&mut self, handle: ColliderHandle) -> Option<&mut TransformedCollider> {
+        self.index(handle).map(|index| &mut self.colliders[index])
+    }
+
+    #[inline]
+    pub(crate) fn colliders(&self) -> &[TransformedCollider] {
+        &self.colliders
+    }
+
+    #[inline]
+    pub(crate) fn colliders_mut(&mut self) -> &mut [TransformedCollider] {
+        &mut self.colliders
+    }
+
+    #[inline]
+    pub(crate) fn handles(&self) -> &[ColliderHandle] {
+        &self.handles
+    }
+}
+
+/// A moving reference frame, e.g. the hand of an animated character.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FrameHandle(usize);
+
+/// Attach a particle to a point fixed in a frame. The attachment target follows the frame as
+/// it moves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameAttachment {
+    pub particle_index: usize,
+    pub frame: FrameHandle,
+    /// The target in the local space of the frame.
+    pub local_offset: Vector3,
+    pub stiffness: Number,
+}
+
+/// A particle pushed out of a collider during a step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactEvent {
+    pub particle_index: usize,
+    pub collider: ColliderHandle,
+    /// The collider is one of the shared colliders of the [`World`](crate::world::World)
+    /// stepping the solver, rather than one of the solver itself.
+    pub shared: bool,
+    /// How far the particle was pushed.
+    pub depth: Number,
+    /// Direction in which the particle was pushed, out of the collider.
+    pub normal: Vector3,
+}
+
+/// Measures of the last step, for tuning and monitoring, see
+/// [`FastMassSpringSolver::set_diagnostics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StepDiagnostics {
+    /// `0.5 * m * v^2` summed over the particles, at the end of the step.
+    pub kinetic_energy: Number,
+    /// Root mean square distance the particles moved in the last solver iteration of the last
+    /// substep. It goes to zero as the iterations converge, so a large value calls for more
+    /// iterations or substeps.
+    pub residual: Number,
+    /// Solver iterations of the last substep, which change with an [`IterationBudget`].
+    pub num_iterations: usize,
+}
+
+/// Adjust the number of iterations after every step to keep the steps within a time budget,
+/// see [`FastMassSpringSolver::set_iteration_budget`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IterationBudget {
+    /// Wall clock time allowed for a step, including its substeps and collisions.
+    pub step_budget: Duration,
+    pub min_iterations: usize,
+    pub max_iterations: usize,
+}
+
+impl IterationBudget {
+    pub fn from_millis(
+        step_budget_ms: Number,
+        min_iterations: usize,
+        max_iterations: usize,
+    ) -> Self {
+        Self {
+            step_budget: Duration::from_secs_f32(step_budget_ms.max(0.0) / 1000.0),
+            min_iterations,
+            max_iterations: max_iterations.max(min_iterations),
+        }
+    }
+}
+
+/// Stop stepping a cloth that came to rest, see [`FastMassSpringSolver::set_sleep`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SleepSettings {
+    /// Speed under which a particle is at rest.
+    pub velocity_threshold: Number,
+    /// Consecutive steps every particle must stay at rest before the cloth falls asleep.
+    pub steps_to_sleep: usize,
+}
+
+impl Default for SleepSettings {
+    fn default() -> Self {
+        Self {
+            velocity_threshold: 0.02,
+            steps_to_sleep: 60,
+        }
+    }
+}
+
+/// How the positions are advanced each substep.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Integrator {
+    /// Implicit Euler solved with projective dynamics, stable for any stiffness and time step.
+    #[default]
+    ProjectiveDynamics,
+    /// Explicit symplectic Euler with the forces of the same constraints. The velocity is
+    /// kept in the previous positions, which makes it the same scheme as position Verlet.
+    ///
+    /// It is only stable while the time step stays below about `2 * sqrt(m / k)` for the
+    /// stiffest constraint, so it is meant to compare against and validate the implicit
+    /// solver rather than for production.
+    SymplecticEuler,
+}
+
+/// Which part of the velocity [`FastMassSpringSolver::set_damping`] damps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DampingMode {
+    /// The whole velocity, so a free falling cloth falls slower than gravity and a spinning
+    /// one slows down.
+    #[default]
+    Velocity,
+    /// Only the velocity left after removing the rigid motion of the whole cloth, its
+    /// translation and rotation about the center of mass, which conserves the linear and
+    /// angular momentum. Separate pieces, e.g. after cutting, share one rigid motion.
+    Deformation,
+}
+
+/// Buffers reused by the collision pass, so that it does not allocate once warmed up.
+#[derive(Default)]
+struct CollisionScratch {
+    collider_aabbs: Vec<Aabb>,
+    broadphase: Bvh,
+    candidates: Vec<usize>,
+}
+
+/// Projective dynamics solver of a [`Cloth`].
+///
+/// # Determinism
+///
+/// The constraints are built, projected and collided in a fixed order, also with the
+/// `parallel` feature where each constraint writes its own slots, so the same cloth, settings
+/// and calls give bitwise identical positions on every run of the same build on the same
+/// machine. The floats can still differ:
+///
+/// - across machines, as the dense matrix products may pick SIMD kernels, e.g. with fused
+///   multiply-add, from the features of the CPU at runtime,
+/// - across platforms, as functions like `acos` come from the platform math library,
+/// - with an [`IterationBudget`], which changes the iterations with the wall clock time.
 pub struct FastMassSpringSolver {
     cloth: Cloth,
     vector_d: DVector,              // size = 3 * numSprings
@@ -16,126 +346,1677 @@ pub struct FastMassSpringSolver {
     impulse_term: DVector,          // size = 3 * numParticles
     inertial_impluse_term: DVector, // size = 3 * numParticles
     time_step: Number,
-    h2: Number,
+    substeps: usize,
+    h2: Number, // squared time step of a substep
+    acceleration_field: AccelerationField,
     cholesky: Cholesky<Number, Dyn>,
+    integrator: Integrator,
+    /// The stiffness matrix, only kept for the explicit integrator.
+    matrix_l: Option<DMatrix>, // size = (3 * numParticles) x (3 * numParticles)
+    /// A stiffness changed since the system was factorized, see [`Self::update_spring`].
+    system_dirty: bool,
     num_iterations: usize,
+    iteration_budget: Option<IterationBudget>,
+    /// Moving average of the step cost, to adapt the iterations to the budget.
+    smoothed_step_cost: Option<Duration>,
     damping: Number,
-    colliders: Vec<TransformedCollider>,
+    damping_mode: DampingMode,
+    friction: Number,
+    contact_velocity_correction: Number,
+    /// How fast the seams are shortened, in length per second.
+    seam_tightening_speed: Number,
+    wind: Wind,
+    aerodynamics: Aerodynamics,
+    colliders: ColliderSet,
+    frames: Vec<Isometry3>,
+    /// The attachment index and attachment of each frame attachment.
+    frame_attachments: Vec<(usize, FrameAttachment)>,
+    strain_limit: Option<Number>,
+    /// Built on the first strain limiting sweep after the springs changed.
+    spring_coloring: Option<ConstraintColoring>,
+    force_hook: Option<ForceHook>,
+    external_forces: DVector, // size = 3 * numParticles
+    time: Number,
+    // scratch buffers, so that a step does not allocate
+    inertial_positions: DVector, // size = 3 * numParticles
+    rhs: DVector,                // size = 3 * numParticles
+    volume_projections: Vec<Vector3>,
+    strain_corrections: Vec<Vector3>,
+    collision_scratch: CollisionScratch,
+    report_contacts: bool,
+    contact_events: Vec<ContactEvent>,
+    diagnostics: Option<StepDiagnostics>,
+    cloth_aabb: Aabb,
+    sleep: Option<SleepSettings>,
+    /// Consecutive steps every particle stayed under the sleep velocity threshold.
+    steps_at_rest: usize,
+    sleeping: bool,
 }
 
 impl FastMassSpringSolver {
-    pub fn new(cloth: Cloth, time_step: Number) -> Self {
+    pub fn new(cloth: Cloth, time_step: Number) -> Result<Self, SolverBuildError> {
+        if !(time_step > 0.0 && time_step.is_finite()) {
+            return Err(SolverBuildError::InvalidTimeStep(time_step));
+        }
+        validate_cloth(&cloth)?;
         let h2 = time_step * time_step;
         let num_constraints = cloth.num_constraints();
-        let matrix_l = compute_matrix_l(&cloth); // size = (3 * numParticles) x (3 * numParticles)
-        let matrix_j = compute_matrix_j(&cloth);
-        let matrix_m = compute_matrix_m(&cloth);
-        let system_matrix = &matrix_m + h2 * &matrix_l;
-        let cholesky = nalgebra::linalg::Cholesky::new(system_matrix).unwrap();
-        let impulse_term = DVector::zeros(cloth.num_particles() * 3);
-        Self {
+        let (h2_matrix_j, matrix_m, cholesky) = compute_system(&cloth, h2)?;
+        let num_particles = cloth.num_particles();
+        let impulse_term = DVector::zeros(num_particles * 3);
+        let cloth_aabb = cloth.compute_aabb();
+        Ok(Self {
             vector_d: DVector::zeros(num_constraints * 3),
-            h2_matrix_j: h2 * matrix_j,
+            h2_matrix_j,
             matrix_m,
             inertial_impluse_term: DVector::zeros(cloth.num_particles() * 3),
             cloth,
             time_step,
+            substeps: 1,
             h2,
+            acceleration_field: AccelerationField::default(),
             cholesky,
+            integrator: Integrator::default(),
+            matrix_l: None,
+            system_dirty: false,
             impulse_term,
             num_iterations: 2,
+            iteration_budget: None,
+            smoothed_step_cost: None,
             damping: 1.0,
-            colliders: vec![],
-        }
+            damping_mode: DampingMode::default(),
+            friction: 0.0,
+            contact_velocity_correction: 0.0,
+            seam_tightening_speed: 0.0,
+            wind: Wind::default(),
+            aerodynamics: Aerodynamics::default(),
+            colliders: ColliderSet::default(),
+            frames: vec![],
+            frame_attachments: vec![],
+            strain_limit: None,
+            spring_coloring: None,
+            strain_corrections: vec![],
+            force_hook: None,
+            external_forces: DVector::zeros(num_particles * 3),
+            time: 0.0,
+            inertial_positions: DVector::zeros(num_particles * 3),
+            rhs: DVector::zeros(num_particles * 3),
+            volume_projections: vec![],
+            collision_scratch: CollisionScratch::default(),
+            report_contacts: false,
+            contact_events: vec![],
+            diagnostics: None,
+            cloth_aabb,
+            sleep: None,
+            steps_at_rest: 0,
+            sleeping: false,
+        })
     }
 
+    /// Set the number of solver iterations per substep. With an iteration budget, this is
+    /// the count the adjustment starts from.
     pub fn set_num_iterations(&mut self, num_iterations: usize) {
         self.num_iterations = num_iterations;
     }
 
+    #[inline]
+    pub fn num_iterations(&self) -> usize {
+        self.num_iterations
+    }
+
+    /// Measure the cost of every step and add or remove an iteration to stay within the
+    /// budget, so that an interactive application loses accuracy rather than frames. `None`
+    /// keeps the number of iterations fixed.
+    pub fn set_iteration_budget(&mut self, budget: Option<IterationBudget>) {
+        self.iteration_budget = budget;
+        self.smoothed_step_cost = None;
+        if let Some(budget) = budget {
+            self.num_iterations = self
+                .num_iterations
+                .clamp(budget.min_iterations, budget.max_iterations);
+        }
+    }
+
+    #[inline]
+    pub fn iteration_budget(&self) -> Option<IterationBudget> {
+        self.iteration_budget
+    }
+
+    /// Switch between the implicit solver and explicit integration of the same forces. The
+    /// explicit integrator ignores the number of iterations.
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        if integrator == self.integrator {
+            return;
+        }
+        self.integrator = integrator;
+        self.rebuild_system();
+    }
+
+    #[inline]
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
+    /// Set a constant acceleration field, see [`Self::set_acceleration_field`].
     pub fn set_gravity(&mut self, gravity: Vector3) {
+        self.set_acceleration_field(AccelerationField::Constant(gravity));
+    }
+
+    /// Accelerate the particles by a field, e.g. towards a planet. A constant field is
+    /// precomputed, the others are evaluated for every particle on every substep.
+    pub fn set_acceleration_field(&mut self, field: AccelerationField) {
+        self.acceleration_field = field;
+        self.compute_impulse_term();
+        self.wake_up();
+    }
+
+    #[inline]
+    pub fn acceleration_field(&self) -> &AccelerationField {
+        &self.acceleration_field
+    }
+
+    fn compute_impulse_term(&mut self) {
+        // a varying field is added in `pre_compute_terms`
+        let acceleration = self
+            .acceleration_field
+            .as_constant()
+            .unwrap_or_else(Vector3::zeros);
         for (i, &mass) in self.cloth.particle_masses.iter().enumerate() {
             self.impulse_term
                 .fixed_rows_mut::<3>(i * 3)
-                .copy_from(&(mass * gravity * self.h2));
+                .copy_from(&(mass * acceleration * self.h2));
         }
     }
 
+    /// Set the fraction of the velocity kept from one step to the next.
+    /// `1.0` means no damping and `0.0` removes all velocity each step.
+    /// See [`DampingMode`] for which velocity is damped.
+    pub fn set_damping(&mut self, damping: Number) {
+        self.damping = damping;
+    }
+
+    #[inline]
+    pub fn damping(&self) -> Number {
+        self.damping
+    }
+
+    pub fn set_damping_mode(&mut self, damping_mode: DampingMode) {
+        self.damping_mode = damping_mode;
+    }
+
+    #[inline]
+    pub fn damping_mode(&self) -> DampingMode {
+        self.damping_mode
+    }
+
+    pub fn set_wind(&mut self, wind: Wind) {
+        self.wind = wind;
+        self.wake_up();
+    }
+
+    #[inline]
+    pub fn wind(&self) -> &Wind {
+        &self.wind
+    }
+
+    /// Set the lift and drag on the triangles of the cloth. The air moves with the velocity of
+    /// the [`Wind`], turbulence included, whatever its drag.
+    pub fn set_aerodynamics(&mut self, aerodynamics: Aerodynamics) {
+        self.aerodynamics = aerodynamics;
+        self.wake_up();
+    }
+
+    #[inline]
+    pub fn aerodynamics(&self) -> &Aerodynamics {
+        &self.aerodynamics
+    }
+
+    /// Set how much of the tangential motion of a colliding particle is removed, in range [0, 1].
+    pub fn set_friction(&mut self, friction: Number) {
+        self.friction = friction.clamp(0.0, 1.0);
+    }
+
+    /// Set how much of the velocity along the contact normal is removed when a particle is
+    /// pushed out of a collider, in range [0, 1].
+    ///
+    /// Pushing a particle out only moves its position, which the next step sees as a velocity
+    /// away from the collider. Resting contacts then bounce and jitter. With `1.0` the previous
+    /// position is moved along as well, so that the contact comes to rest.
+    pub fn set_contact_velocity_correction(&mut self, correction: Number) {
+        self.contact_velocity_correction = correction.clamp(0.0, 1.0);
+    }
+
+    /// Limit how far the springs stretch, as a fraction of their rest length, e.g. `0.1` lets
+    /// a spring grow to 110% of its rest length. `None` to disable, the default.
+    ///
+    /// A few iterations of the implicit solver leave soft cloth stretched under its own
+    /// weight. After the dynamics of every substep, the springs are shortened back to the
+    /// limit in Gauss-Seidel sweeps over the spring colors, the springs of one color in
+    /// parallel with the `parallel` feature. Compressed springs are left alone so that the
+    /// cloth still folds.
+    pub fn set_strain_limit(&mut self, strain_limit: Option<Number>) {
+        self.strain_limit = strain_limit.map(|limit| limit.max(0.0));
+        self.wake_up();
+    }
+
+    #[inline]
+    pub fn strain_limit(&self) -> Option<Number> {
+        self.strain_limit
+    }
+
     pub fn cloth(&self) -> &Cloth {
         &self.cloth
     }
 
+    /// The cloth, for edits of the particle positions that keep the constraint set. Call
+    /// [`Self::end_step`] after to refresh the bounds.
+    #[inline]
+    pub(crate) fn cloth_mut(&mut self) -> &mut Cloth {
+        &mut self.cloth
+    }
+
+    /// Bounding box of the cloth, updated at the end of every step.
+    #[inline]
+    pub fn cloth_aabb(&self) -> Aabb {
+        self.cloth_aabb
+    }
+
+    /// The time advanced by [`Self::step`].
     pub fn time_step(&self) -> Number {
         self.time_step
     }
 
-    pub fn add_collider(&mut self, collider: impl Into<Collider>, transform: Isometry3) {
-        self.colliders.push(TransformedCollider {
-            collider: collider.into(),
-            transform,
-        });
+    /// Split each [`Self::step`] into `substeps` steps of `time_step / substeps`, which is
+    /// more stable for stiff settings than the same number of solver iterations.
+    ///
+    /// The system matrix depends on the time step, so it is refactorized.
+    pub fn set_substeps(&mut self, substeps: usize) {
+        assert!(substeps > 0, "at least one substep is needed");
+        if substeps == self.substeps {
+            return;
+        }
+        let old_time_step = self.substep_time_step();
+        self.substeps = substeps;
+        let time_step = self.substep_time_step();
+        self.h2 = time_step * time_step;
+        // keep the velocities, which are implied by the previous positions
+        let positions = &self.cloth.particle_positions;
+        self.cloth.prev_particle_positions.axpy(
+            1.0 - time_step / old_time_step,
+            positions,
+            time_step / old_time_step,
+        );
+        self.compute_impulse_term();
+        self.rebuild_system();
     }
 
-    pub fn step(&mut self) {
-        self.pre_compute_terms();
+    #[inline]
+    pub fn substeps(&self) -> usize {
+        self.substeps
+    }
+
+    /// The time advanced by each substep, and by [`Self::step_once_manual`].
+    #[inline]
+    pub fn substep_time_step(&self) -> Number {
+        self.time_step / self.substeps as Number
+    }
+
+    /// Simulated time, advanced by the time step on each step.
+    #[inline]
+    pub fn time(&self) -> Number {
+        self.time
+    }
+
+    /// Set a callback adding custom external forces on every step, replacing the previous one.
+    pub fn set_force_hook(&mut self, hook: impl Fn(&Cloth, &mut DVector, Number) + 'static) {
+        self.force_hook = Some(Box::new(hook));
+        self.wake_up();
+    }
+
+    pub fn clear_force_hook(&mut self) {
+        self.force_hook = None;
+        self.wake_up();
+    }
+
+    /// Velocity of a particle over the last step.
+    pub fn particle_velocity(&self, index: usize) -> Vector3 {
+        let x = self.cloth.particle_positions.fixed_rows::<3>(index * 3);
+        let prev_x = self
+            .cloth
+            .prev_particle_positions
+            .fixed_rows::<3>(index * 3);
+        (x - prev_x) / self.substep_time_step()
+    }
+
+    /// Velocities of all particles, packed as `[x0, y0, z0, x1, ...]` like the positions.
+    pub fn particle_velocities(&self) -> DVector {
+        (&self.cloth.particle_positions - &self.cloth.prev_particle_positions)
+            / self.substep_time_step()
+    }
+
+    /// `0.5 * m * v^2` summed over the particles, with the velocities of the last step.
+    pub fn kinetic_energy(&self) -> Number {
+        let time_step = self.substep_time_step();
+        self.cloth
+            .particle_masses
+            .iter()
+            .enumerate()
+            .map(|(i, &mass)| {
+                let x = self.cloth.particle_positions.fixed_rows::<3>(i * 3);
+                let prev_x = self.cloth.prev_particle_positions.fixed_rows::<3>(i * 3);
+                0.5 * mass * (x - prev_x).norm_squared() / (time_step * time_step)
+            })
+            .sum()
+    }
+
+    /// Set the velocity of a particle for the next step. The velocity is implicit in the
+    /// previous position, which is moved accordingly.
+    pub fn set_particle_velocity(&mut self, index: usize, velocity: Vector3) {
+        let prev_x = self.cloth.particle_positions.fixed_rows::<3>(index * 3)
+            - velocity * self.substep_time_step();
         self.cloth
             .prev_particle_positions
-            .copy_from(&self.cloth.particle_positions);
+            .fixed_rows_mut::<3>(index * 3)
+            .copy_from(&prev_x);
+        self.wake_up();
+    }
 
-        for _ in 0..self.num_iterations {
-            self.local_step();
-            self.global_step();
+    /// Set the velocities of all particles, packed like [`Self::particle_velocities`].
+    pub fn set_particle_velocities(&mut self, velocities: &DVector) {
+        assert_eq!(velocities.len(), self.cloth.particle_positions.len());
+        self.cloth.prev_particle_positions =
+            &self.cloth.particle_positions - velocities * self.substep_time_step();
+        self.wake_up();
+    }
+
+    /// Give every particle the same velocity, e.g. to launch the cloth.
+    pub fn set_uniform_velocity(&mut self, velocity: Vector3) {
+        for i in 0..self.cloth.num_particles() {
+            self.set_particle_velocity(i, velocity);
         }
+    }
 
-        self.solve_collision();
+    /// Add a collider, which wakes the cloth if it overlaps it.
+    pub fn add_collider(
+        &mut self,
+        collider: impl Into<Collider>,
+        transform: Isometry3,
+    ) -> ColliderHandle {
+        let handle = self.colliders.add(collider.into(), transform);
+        let aabb = self.colliders.get(handle).expect("just added").aabb();
+        self.wake_up_near(&aabb);
+        handle
     }
 
-    fn solve_collision(&mut self) {
-        for collider in &self.colliders {
-            for i in 0..self.cloth.num_particles() {
-                let mut x = self.cloth.particle_positions.fixed_rows_mut::<3>(i * 3);
-                let point = point![x[0], x[1], x[2]];
-                if let Some(new_point) = collider.compute_collision_with_point(point) {
-                    x.copy_from(&new_point.coords);
+    /// Remove a collider and return it, or `None` if it was already removed. The handles of
+    /// the other colliders stay valid.
+    pub fn remove_collider(&mut self, handle: ColliderHandle) -> Option<Collider> {
+        let removed = self.colliders.remove(handle)?;
+        self.wake_up_near(&removed.aabb());
+        Some(removed.collider)
+    }
+
+    /// Move a collider. The motion since the previous step is taken as the collider velocity
+    /// when computing friction, spread evenly over the substeps. The cloth is woken if the
+    /// collider overlaps it before or after the move.
+    ///
+    /// Panics if the collider was removed.
+    pub fn set_collider_transform(&mut self, handle: ColliderHandle, transform: Isometry3) {
+        let collider = self
+            .colliders
+            .get_mut(handle)
+            .expect("the collider was removed");
+        if collider.transform == transform {
+            return;
+        }
+        let old_aabb = collider.aabb();
+        collider.transform = transform;
+        let aabb = old_aabb.merged(&collider.aabb());
+        self.wake_up_near(&aabb);
+    }
+
+    /// Panics if the collider was removed.
+    #[inline]
+    pub fn collider_transform(&self, handle: ColliderHandle) -> Isometry3 {
+        self.colliders
+            .get(handle)
+            .expect("the collider was removed")
+            .transform
+    }
+
+    /// The collider of a handle, `None` if it was removed.
+    #[inline]
+    pub fn collider(&self, handle: ColliderHandle) -> Option<&TransformedCollider> {
+        self.colliders.get(handle)
+    }
+
+    /// Record a [`ContactEvent`] for every collision. The events accumulate until drained.
+    pub fn set_contact_reporting(&mut self, enabled: bool) {
+        self.report_contacts = enabled;
+        if !enabled {
+            self.contact_events.clear();
+        }
+    }
+
+    /// Measure each step, see [`StepDiagnostics`]. Off by default, as it costs a pass over the
+    /// particles.
+    pub fn set_diagnostics(&mut self, enabled: bool) {
+        if enabled != self.diagnostics.is_some() {
+            self.diagnostics = enabled.then(StepDiagnostics::default);
+        }
+    }
+
+    /// The measures of the last step, `None` unless enabled by [`Self::set_diagnostics`].
+    #[inline]
+    pub fn diagnostics(&self) -> Option<StepDiagnostics> {
+        self.diagnostics
+    }
+
+    /// Put the cloth to sleep once every particle stayed slower than the threshold for a
+    /// number of steps, so that a settled cloth costs nothing until woken. `None`, the
+    /// default, keeps the cloth awake.
+    ///
+    /// A sleeping cloth is woken by the changes made through the solver, e.g. of the forces,
+    /// velocities, attachments or constraints, and by the colliders added, removed or moved
+    /// near it. Forces that change on their own, like a time dependent acceleration field or
+    /// force hook, do not wake it.
+    pub fn set_sleep(&mut self, sleep: Option<SleepSettings>) {
+        self.sleep = sleep;
+        self.wake_up();
+    }
+
+    #[inline]
+    pub fn sleep(&self) -> Option<SleepSettings> {
+        self.sleep
+    }
+
+    /// Whether the steps are skipped, see [`Self::set_sleep`].
+    #[inline]
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    /// Resume stepping the cloth, and restart counting the steps at rest.
+    pub fn wake_up(&mut self) {
+        self.sleeping = false;
+        self.steps_at_rest = 0;
+    }
+
+    /// Wake the cloth if it overlaps `aabb`, e.g. the space swept by a collider.
+    pub(crate) fn wake_up_near(&mut self, aabb: &Aabb) {
+        if self.sleeping && self.cloth_aabb.intersects(aabb) {
+            self.wake_up();
+        }
+    }
+
+    /// Wake the cloth if a particle was pushed from outside the solver, e.g. by another cloth,
+    /// farther than it may move in a step at rest. Resting on another cloth pushes the
+    /// particles a little on every step, which should not keep the cloth awake.
+    pub(crate) fn wake_up_if_pushed(&mut self, distance: Number) {
+        let rest_distance = self.sleep.map_or(0.0, |settings| {
+            settings.velocity_threshold * self.substep_time_step()
+        });
+        if self.sleeping && distance > rest_distance {
+            self.wake_up();
+        }
+    }
+
+    /// Take the contact events recorded since the last call.
+    pub fn drain_contact_events(&mut self) -> std::vec::Drain<'_, ContactEvent> {
+        self.contact_events.drain(..)
+    }
+
+    #[inline]
+    pub fn colliders(&self) -> &[TransformedCollider] {
+        self.colliders.colliders()
+    }
+
+    /// The handle of each of [`Self::colliders`].
+    #[inline]
+    pub fn collider_handles(&self) -> &[ColliderHandle] {
+        self.colliders.handles()
+    }
+
+    /// Add an attachment to the running simulation and return its index.
+    ///
+    /// The constraint set changes, so the system matrix is refactorized.
+    pub fn add_attachment(&mut self, attachment: Attachment) -> Result<usize, SolverBuildError> {
+        let index = self.cloth.attachments.len();
+        check_attachment(self.cloth.num_particles(), index, &attachment)?;
+        self.cloth.attachments.push(attachment);
+        self.rebuild_system();
+        self.wake_up();
+        Ok(index)
+    }
+
+    /// Add several attachments with a single refactorization and return their range. Nothing
+    /// is added if one of them is invalid.
+    pub fn add_attachments(
+        &mut self,
+        attachments: impl IntoIterator<Item = Attachment>,
+    ) -> Result<Range<usize>, SolverBuildError> {
+        let start = self.cloth.attachments.len();
+        let attachments: Vec<Attachment> = attachments.into_iter().collect();
+        for (offset, attachment) in attachments.iter().enumerate() {
+            check_attachment(self.cloth.num_particles(), start + offset, attachment)?;
+        }
+        self.cloth.add_attachments(attachments);
+        if self.cloth.attachments.len() > start {
+            self.rebuild_system();
+            self.wake_up();
+        }
+        Ok(start..self.cloth.attachments.len())
+    }
+
+    /// Remove an attachment from the running simulation. Attachments after `index` are shifted down.
+    pub fn remove_attachment(&mut self, index: usize) -> Attachment {
+        let attachment = self.cloth.attachments.remove(index);
+        self.frame_attachments
+            .retain(|&(attachment_index, _)| attachment_index != index);
+        for (attachment_index, _) in &mut self.frame_attachments {
+            if *attachment_index > index {
+                *attachment_index -= 1;
+            }
+        }
+        self.rebuild_system();
+        self.wake_up();
+        attachment
+    }
+
+    pub fn add_frame(&mut self, transform: Isometry3) -> FrameHandle {
+        self.frames.push(transform);
+        FrameHandle(self.frames.len() - 1)
+    }
+
+    /// Move a frame, and the targets of the attachments bound to it.
+    pub fn set_frame_transform(&mut self, frame: FrameHandle, transform: Isometry3) {
+        self.frames[frame.0] = transform;
+        let mut moved = false;
+        for (attachment_index, frame_attachment) in &self.frame_attachments {
+            if frame_attachment.frame == frame {
+                self.cloth.attachments[*attachment_index].target_position =
+                    (transform * Point3::from(frame_attachment.local_offset)).coords;
+                moved = true;
+            }
+        }
+        if moved {
+            self.wake_up();
+        }
+    }
+
+    #[inline]
+    pub fn frame_transform(&self, frame: FrameHandle) -> Isometry3 {
+        self.frames[frame.0]
+    }
+
+    /// Add an attachment following a frame and return its index among the attachments, for
+    /// [`Self::remove_attachment`].
+    ///
+    /// The constraint set changes, so the system matrix is refactorized.
+    pub fn add_frame_attachment(
+        &mut self,
+        frame_attachment: FrameAttachment,
+    ) -> Result<usize, SolverBuildError> {
+        let target_position =
+            self.frames[frame_attachment.frame.0] * Point3::from(frame_attachment.local_offset);
+        let index = self.add_attachment(Attachment {
+            particle_index: frame_attachment.particle_index,
+            target_position: target_position.coords,
+            stiffness: frame_attachment.stiffness,
+        })?;
+        self.frame_attachments.push((index, frame_attachment));
+        Ok(index)
+    }
+
+    /// Attach a particle to a frame where it currently is, so that it follows the frame from
+    /// there. Returns the attachment index.
+    pub fn attach_to_frame(
+        &mut self,
+        particle_index: usize,
+        frame: FrameHandle,
+        stiffness: Number,
+    ) -> Result<usize, SolverBuildError> {
+        check_particle_index(
+            self.cloth.num_particles(),
+            "attachment",
+            self.cloth.attachments.len(),
+            particle_index,
+        )?;
+        let position = self.cloth.get_particle_position(particle_index);
+        let local_offset = self.frames[frame.0].inverse_transform_point(&position.into());
+        self.add_frame_attachment(FrameAttachment {
+            particle_index,
+            frame,
+            local_offset: local_offset.coords,
+            stiffness,
+        })
+    }
+
+    /// Move the target of an attachment. The target only takes part in the local step,
+    /// so no refactorization is needed.
+    pub fn set_attachment_target(&mut self, index: usize, target_position: Vector3) {
+        self.cloth.attachments[index].target_position = target_position;
+        self.wake_up();
+    }
+
+    /// Set the rest length of a spring. The rest lengths only take part in the local step,
+    /// so no refactorization is needed.
+    pub fn set_spring_rest_length(&mut self, index: usize, rest_length: Number) {
+        self.cloth.springs[index].rest_length = rest_length;
+        self.wake_up();
+    }
+
+    /// Scale the rest length of every spring, see [`Cloth::scale_rest_lengths`].
+    pub fn scale_rest_lengths(&mut self, factor: Number) {
+        self.cloth.scale_rest_lengths(factor);
+        self.wake_up();
+    }
+
+    /// Sew particle pairs together with [`Cloth::add_seams`], e.g. two panels merged with
+    /// [`Cloth::append`]. Returns the range of the new seams.
+    ///
+    /// The constraint set changes, so the system matrix is refactorized. Nothing is added if
+    /// one of the pairs is invalid.
+    pub fn add_seams(
+        &mut self,
+        particle_pairs: impl IntoIterator<Item = (usize, usize)>,
+        stiffness: Number,
+    ) -> Result<Range<usize>, SolverBuildError> {
+        let start = self.cloth.seams.len();
+        let particle_pairs: Vec<(usize, usize)> = particle_pairs.into_iter().collect();
+        for (offset, &pair) in particle_pairs.iter().enumerate() {
+            check_seam(self.cloth.num_particles(), start + offset, pair, stiffness)?;
+        }
+        self.cloth.add_seams(particle_pairs, stiffness);
+        self.rebuild_system();
+        self.wake_up();
+        Ok(start..self.cloth.seams.len())
+    }
+
+    /// Shorten the seams by `speed` per second of simulated time until they are closed.
+    /// Closing the seams gradually lets the cloth slide into place instead of snapping
+    /// together and tangling.
+    ///
+    /// The rest lengths only take part in the local step, so no refactorization is needed.
+    pub fn set_seam_tightening_speed(&mut self, speed: Number) {
+        self.seam_tightening_speed = speed.max(0.0);
+        self.wake_up();
+    }
+
+    #[inline]
+    pub fn seam_tightening_speed(&self) -> Number {
+        self.seam_tightening_speed
+    }
+
+    /// Set the volume a volume constraint pulls the cloth towards, e.g. to inflate it. The
+    /// target only takes part in the local step, so no refactorization is needed.
+    pub fn set_target_volume(&mut self, index: usize, target_volume: Number) {
+        self.cloth.volume_constraints[index].target_volume = target_volume;
+        self.wake_up();
+    }
+
+    /// Change the stiffness and rest length of a spring of the running simulation, e.g. from
+    /// a GUI slider.
+    ///
+    /// A new stiffness changes the system matrix, which is refactorized once on the next step
+    /// however many constraints were updated. The rest length only takes part in the local
+    /// step.
+    pub fn update_spring(
+        &mut self,
+        index: usize,
+        stiffness: Number,
+        rest_length: Number,
+    ) -> Result<(), SolverBuildError> {
+        check_stiffness("spring", index, stiffness)?;
+        if !(rest_length >= 0.0 && rest_length.is_finite()) {
+            return Err(SolverBuildError::InvalidRestLength {
+                spring_index: index,
+                rest_length,
+            });
+        }
+        let spring = &mut self.cloth.springs[index];
+        if spring.stiffness != stiffness {
+            spring.stiffness = stiffness;
+            self.system_dirty = true;
+        }
+        spring.rest_length = rest_length;
+        self.wake_up();
+        Ok(())
+    }
+
+    /// Change the stiffness of an attachment of the running simulation. The system matrix is
+    /// refactorized on the next step, like with [`Self::update_spring`].
+    pub fn update_attachment_stiffness(
+        &mut self,
+        index: usize,
+        stiffness: Number,
+    ) -> Result<(), SolverBuildError> {
+        check_stiffness("attachment", index, stiffness)?;
+        let attachment = &mut self.cloth.attachments[index];
+        if attachment.stiffness != stiffness {
+            attachment.stiffness = stiffness;
+            self.system_dirty = true;
+            self.wake_up();
+        }
+        Ok(())
+    }
+
+    /// Remove the springs for which `predicate` returns true and return them.
+    ///
+    /// The system matrix is refactorized if any spring was removed.
+    pub fn remove_springs(&mut self, mut predicate: impl FnMut(&Spring) -> bool) -> Vec<Spring> {
+        let (removed, kept): (Vec<_>, Vec<_>) = self
+            .cloth
+            .springs
+            .drain(..)
+            .partition(|spring| predicate(spring));
+        self.cloth.springs = kept;
+        if !removed.is_empty() {
+            self.rebuild_system();
+            self.wake_up();
+        }
+        removed
+    }
+
+    /// Refine the cloth with [`Cloth::refine`] against the colliders of the solver, and
+    /// rebuild the system if any edge was split. Returns the number of edges split.
+    ///
+    /// The new particles are appended, so the indices of the existing particles, attachments
+    /// and springs stay valid.
+    pub fn refine(&mut self, criteria: &RefinementCriteria) -> usize {
+        profile_span!("refine");
+        let num_split = self.cloth.refine(criteria, self.colliders.colliders());
+        if num_split > 0 {
+            self.particles_changed();
+        }
+        num_split
+    }
+
+    /// Collapse the flat refined regions away from the colliders of the solver with
+    /// [`Cloth::coarsen`], and rebuild the system if any particle was removed. Returns the
+    /// number of particles removed.
+    ///
+    /// Only refined particles are removed, but the refined particles after them shift down.
+    pub fn coarsen(&mut self, criteria: &RefinementCriteria) -> usize {
+        profile_span!("coarsen");
+        let num_collapsed = self.cloth.coarsen(criteria, self.colliders.colliders());
+        if num_collapsed > 0 {
+            // attached particles are never collapsed, but may have shifted
+            for (attachment_index, frame_attachment) in &mut self.frame_attachments {
+                frame_attachment.particle_index =
+                    self.cloth.attachments[*attachment_index].particle_index;
+            }
+            self.particles_changed();
+        }
+        num_collapsed
+    }
+
+    /// Resize the buffers of the particles and rebuild the system after particles were added
+    /// or removed.
+    fn particles_changed(&mut self) {
+        let num_particles = self.cloth.num_particles();
+        self.impulse_term = DVector::zeros(num_particles * 3);
+        self.inertial_impluse_term = DVector::zeros(num_particles * 3);
+        self.external_forces = DVector::zeros(num_particles * 3);
+        self.inertial_positions = DVector::zeros(num_particles * 3);
+        self.rhs = DVector::zeros(num_particles * 3);
+        self.compute_impulse_term();
+        self.rebuild_system();
+        self.cloth_aabb = self.cloth.compute_aabb();
+        self.wake_up();
+    }
+
+    #[inline]
+    pub fn pick_particle(
+        &self,
+        ray_origin: Vector3,
+        ray_direction: Vector3,
+        max_distance: Number,
+    ) -> Option<usize> {
+        self.cloth
+            .pick_particle(ray_origin, ray_direction, max_distance)
+    }
+
+    /// Advance the simulation by the time step, in [`Self::substeps`] steps.
+    pub fn step(&mut self) {
+        profile_span!("step");
+        if self.skip_sleeping_step(self.time_step) {
+            return;
+        }
+        let start = self.iteration_budget.map(|_| Instant::now());
+        let collider_motion_fraction = 1.0 / self.substeps as Number;
+        for _ in 0..self.substeps {
+            self.substep(None, collider_motion_fraction);
+        }
+        end_collider_step(self.colliders.colliders_mut());
+        self.end_step();
+        self.update_sleep();
+        self.adapt_iterations(start);
+    }
+
+    /// Advance the simulation by a single substep, for applications that run their own time
+    /// loop. The collider motion since the previous call is taken as happening during this
+    /// substep.
+    pub fn step_once_manual(&mut self) {
+        profile_span!("step");
+        if self.skip_sleeping_step(self.substep_time_step()) {
+            return;
+        }
+        let start = self.iteration_budget.map(|_| Instant::now());
+        self.substep(None, 1.0);
+        end_collider_step(self.colliders.colliders_mut());
+        self.end_step();
+        self.update_sleep();
+        self.adapt_iterations(start);
+    }
+
+    /// Step against the colliders of the solver and `shared_colliders`, which are owned by
+    /// the caller. The caller is responsible for calling [`end_collider_step`] on them.
+    pub(crate) fn step_with_shared_colliders(&mut self, shared_colliders: &ColliderSet) {
+        profile_span!("step");
+        if self.skip_sleeping_step(self.time_step) {
+            return;
+        }
+        let start = self.iteration_budget.map(|_| Instant::now());
+        let collider_motion_fraction = 1.0 / self.substeps as Number;
+        for _ in 0..self.substeps {
+            self.substep(Some(shared_colliders), collider_motion_fraction);
+        }
+        end_collider_step(self.colliders.colliders_mut());
+        self.end_step();
+        self.update_sleep();
+        self.adapt_iterations(start);
+    }
+
+    /// Advance the time of a sleeping cloth without stepping it. Returns false if awake.
+    fn skip_sleeping_step(&mut self, time_step: Number) -> bool {
+        if !self.sleeping {
+            return false;
+        }
+        self.time += time_step;
+        // so that the colliders moved during the sleep do not seem fast on waking
+        end_collider_step(self.colliders.colliders_mut());
+        if let Some(diagnostics) = &mut self.diagnostics {
+            *diagnostics = StepDiagnostics::default();
+        }
+        true
+    }
+
+    /// Count the steps every particle stayed under the velocity threshold, and fall asleep
+    /// after enough of them. Seams still tightening keep the cloth awake.
+    fn update_sleep(&mut self) {
+        let settings = match self.sleep {
+            Some(settings) => settings,
+            None => return,
+        };
+        let max_distance = settings.velocity_threshold * self.substep_time_step();
+        let positions = &self.cloth.particle_positions;
+        let prev_positions = &self.cloth.prev_particle_positions;
+        let at_rest = (0..self.cloth.num_particles()).all(|i| {
+            let offset = positions.fixed_rows::<3>(i * 3) - prev_positions.fixed_rows::<3>(i * 3);
+            offset.norm_squared() <= max_distance * max_distance
+        });
+        let tightening = self.seam_tightening_speed > 0.0
+            && self.cloth.seams.iter().any(|seam| seam.rest_length > 0.0);
+        if !at_rest || tightening {
+            self.steps_at_rest = 0;
+            return;
+        }
+        self.steps_at_rest += 1;
+        if self.steps_at_rest >= settings.steps_to_sleep {
+            self.sleeping = true;
+            // the residual motion would come back as a jolt on waking
+            self.cloth
+                .prev_particle_positions
+                .copy_from(&self.cloth.particle_positions);
+        }
+    }
+
+    /// Change the number of iterations by one when the step cost, averaged over the last
+    /// steps to ignore spikes, leaves the budget or would stay within it with one more.
+    fn adapt_iterations(&mut self, start: Option<Instant>) {
+        let (budget, start) = match (self.iteration_budget, start) {
+            (Some(budget), Some(start)) => (budget, start),
+            _ => return,
+        };
+        let step_cost = start.elapsed();
+        let smoothed = match self.smoothed_step_cost {
+            Some(smoothed) => smoothed.mul_f32(0.8) + step_cost.mul_f32(0.2),
+            None => step_cost,
+        };
+        let num_iterations = self.num_iterations;
+        if smoothed > budget.step_budget && num_iterations > budget.min_iterations {
+            self.num_iterations -= 1;
+        } else if num_iterations < budget.max_iterations
+            && smoothed.mul_f32((num_iterations + 1) as f32 / num_iterations.max(1) as f32)
+                < budget.step_budget
+        {
+            self.num_iterations += 1;
+        }
+        // the average was measured with the previous count, scale it to the new one
+        self.smoothed_step_cost = Some(if self.num_iterations == num_iterations {
+            smoothed
+        } else {
+            smoothed.mul_f32(self.num_iterations.max(1) as f32 / num_iterations.max(1) as f32)
+        });
+    }
+
+    fn substep(
+        &mut self,
+        shared_colliders: Option<&ColliderSet>,
+        collider_motion_fraction: Number,
+    ) {
+        profile_span!("substep");
+        if self.system_dirty {
+            self.rebuild_system();
+        }
+        self.step_dynamics();
+        if let Some(strain_limit) = self.strain_limit {
+            self.limit_strain(strain_limit);
+        }
+        self.solve_collision(shared_colliders, collider_motion_fraction);
+    }
+
+    /// Move the particles of the springs stretched beyond `1 + strain_limit` times their rest
+    /// length back to that length, weighted by the inverse masses. The springs of one color
+    /// share no particle, so their corrections are independent.
+    fn limit_strain(&mut self, strain_limit: Number) {
+        profile_span!("limit_strain");
+        let cloth = &mut self.cloth;
+        let coloring = self.spring_coloring.get_or_insert_with(|| {
+            ConstraintColoring::from_springs(cloth.num_particles(), &cloth.springs)
+        });
+        let max_stretch = 1.0 + strain_limit;
+        for color in coloring.colors() {
+            self.strain_corrections.clear();
+            self.strain_corrections
+                .resize(color.len(), Vector3::zeros());
+            let correction = |spring_index: usize| -> Vector3 {
+                let spring = &cloth.springs[spring_index];
+                let p0 = cloth.get_particle_position(spring.particle_index_0);
+                let p1 = cloth.get_particle_position(spring.particle_index_1);
+                let delta = p1 - p0;
+                let length = delta.norm();
+                let max_length = spring.rest_length * max_stretch;
+                if length <= max_length || length == 0.0 {
+                    return Vector3::zeros();
+                }
+                delta * ((length - max_length) / length)
+            };
+            #[cfg(feature = "parallel")]
+            {
+                use rayon::prelude::*;
+                self.strain_corrections
+                    .par_iter_mut()
+                    .zip(color.par_iter())
+                    .for_each(|(c, &spring_index)| *c = correction(spring_index));
+            }
+            #[cfg(not(feature = "parallel"))]
+            for (c, &spring_index) in self.strain_corrections.iter_mut().zip(color) {
+                *c = correction(spring_index);
+            }
+            for (c, &spring_index) in self.strain_corrections.iter().zip(color) {
+                if *c == Vector3::zeros() {
+                    continue;
+                }
+                let spring = &cloth.springs[spring_index];
+                let (i0, i1) = (spring.particle_index_0, spring.particle_index_1);
+                let w0 = 1.0 / cloth.particle_masses[i0];
+                let w1 = 1.0 / cloth.particle_masses[i1];
+                let c = c / (w0 + w1);
+                let positions = &mut cloth.particle_positions;
+                positions.fixed_rows_mut::<3>(i0 * 3).add_assign(&(c * w0));
+                positions.fixed_rows_mut::<3>(i1 * 3).add_assign(&(-c * w1));
+            }
+        }
+    }
+
+    pub(crate) fn end_step(&mut self) {
+        self.cloth.update_surface_bvh();
+        self.cloth_aabb = self.cloth.compute_aabb();
+        if self.diagnostics.is_some() {
+            let kinetic_energy = self.kinetic_energy();
+            if let Some(diagnostics) = &mut self.diagnostics {
+                diagnostics.kinetic_energy = kinetic_energy;
+            }
+        }
+    }
+
+    fn solve_collision(
+        &mut self,
+        shared_colliders: Option<&ColliderSet>,
+        collider_motion_fraction: Number,
+    ) {
+        profile_span!("collision");
+        let params = ContactParams {
+            friction: self.friction,
+            velocity_correction: self.contact_velocity_correction,
+            collider_motion_fraction,
+        };
+        let own = Some((&self.colliders, false));
+        let shared = shared_colliders.map(|colliders| (colliders, true));
+        for (colliders, is_shared) in own.into_iter().chain(shared) {
+            let contacts = if self.report_contacts {
+                Some(&mut self.contact_events)
+            } else {
+                None
+            };
+            solve_collision(
+                &mut self.cloth,
+                colliders,
+                params,
+                &mut self.collision_scratch,
+                contacts.map(|events| (events, is_shared)),
+            );
+        }
+    }
+
+    fn step_dynamics(&mut self) {
+        if self.seam_tightening_speed > 0.0 {
+            self.cloth
+                .tighten_seams(self.seam_tightening_speed * self.substep_time_step());
+        }
+        self.pre_compute_terms();
+        self.cloth
+            .prev_particle_positions
+            .copy_from(&self.cloth.particle_positions);
+
+        let num_iterations = match self.integrator {
+            Integrator::ProjectiveDynamics => {
+                for _ in 0..self.num_iterations {
+                    self.local_step();
+                    self.global_step();
                 }
+                self.num_iterations
             }
+            Integrator::SymplecticEuler => {
+                self.explicit_step();
+                0
+            }
+        };
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.num_iterations = num_iterations;
+            // the global step leaves the previous iterate in the right hand side buffer
+            diagnostics.residual = if num_iterations > 0 {
+                // summed in place, a difference of the vectors would allocate every step
+                let squared: Number = self
+                    .cloth
+                    .particle_positions
+                    .iter()
+                    .zip(self.rhs.iter())
+                    .map(|(x, previous)| (x - previous) * (x - previous))
+                    .sum();
+                (squared / self.cloth.num_particles() as Number).sqrt()
+            } else {
+                0.0
+            };
         }
+        self.time += self.substep_time_step();
+    }
+
+    fn rebuild_system(&mut self) {
+        profile_span!("rebuild_system");
+        // masses were validated when the solver was built, so the system stays positive definite
+        let (h2_matrix_j, matrix_m, cholesky) =
+            compute_system(&self.cloth, self.h2).expect("failed to factorize the system matrix");
+        self.h2_matrix_j = h2_matrix_j;
+        self.matrix_m = matrix_m;
+        self.cholesky = cholesky;
+        self.matrix_l = match self.integrator {
+            Integrator::ProjectiveDynamics => None,
+            Integrator::SymplecticEuler => Some(compute_matrix_l(&self.cloth)),
+        };
+        self.system_dirty = false;
+        self.vector_d = DVector::zeros(self.cloth.num_constraints() * 3);
+        self.spring_coloring = None;
     }
 
     fn pre_compute_terms(&mut self) {
+        profile_span!("pre_compute_terms");
         let damping = self.damping;
         let positions = &self.cloth.particle_positions;
         let prev_positions = &self.cloth.prev_particle_positions;
+        // y = (1 + damping) * x - damping * x_prev
+        self.inertial_positions.copy_from(positions);
+        self.inertial_positions
+            .axpy(-damping, prev_positions, 1.0 + damping);
+        if self.damping_mode == DampingMode::Deformation && damping < 1.0 {
+            // y += (1 - damping) * rigid displacement, so that only the deformation is damped
+            let (center, linear, angular) =
+                rigid_motion(&self.cloth.particle_masses, positions, prev_positions);
+            for i in 0..self.cloth.num_particles() {
+                let offset = positions.fixed_rows::<3>(i * 3) - center;
+                self.inertial_positions
+                    .fixed_rows_mut::<3>(i * 3)
+                    .add_assign(&((linear + angular.cross(&offset)) * (1.0 - damping)));
+            }
+        }
         // inertial_impluse_term = M * y + h^2 * f_ext
-        self.inertial_impluse_term = &self.matrix_m
-            * ((1.0 + damping) * positions - damping * prev_positions)
-            + &self.impulse_term;
+        self.inertial_impluse_term.copy_from(&self.impulse_term);
+        self.inertial_impluse_term
+            .gemv(1.0, &self.matrix_m, &self.inertial_positions, 1.0);
+
+        if self.acceleration_field.as_constant().is_none() {
+            for (i, &mass) in self.cloth.particle_masses.iter().enumerate() {
+                let position = positions.fixed_rows::<3>(i * 3).into_owned();
+                let acceleration = self
+                    .acceleration_field
+                    .acceleration_at(&position, self.time);
+                self.inertial_impluse_term
+                    .fixed_rows_mut::<3>(i * 3)
+                    .add_assign(&(mass * acceleration * self.h2));
+            }
+        }
+
+        if self.wind.drag > 0.0 {
+            let time_step = self.substep_time_step();
+            for (i, &mass) in self.cloth.particle_masses.iter().enumerate() {
+                let position = positions.fixed_rows::<3>(i * 3).into_owned();
+                let velocity = (position - prev_positions.fixed_rows::<3>(i * 3)) / time_step;
+                let force = self.wind.force_at(mass, &position, &velocity, self.time);
+                self.inertial_impluse_term
+                    .fixed_rows_mut::<3>(i * 3)
+                    .add_assign(&(force * self.h2));
+            }
+        }
+
+        if self.aerodynamics.is_enabled() {
+            let time_step = self.substep_time_step();
+            for &triangle in self.cloth.triangles() {
+                let [p0, p1, p2] = triangle.map(|i| positions.fixed_rows::<3>(i * 3).into_owned());
+                let cross = (p1 - p0).cross(&(p2 - p0));
+                let double_area = cross.norm();
+                if double_area <= Number::EPSILON {
+                    continue;
+                }
+                let velocity = triangle
+                    .iter()
+                    .map(|&i| {
+                        positions.fixed_rows::<3>(i * 3) - prev_positions.fixed_rows::<3>(i * 3)
+                    })
+                    .sum::<Vector3>()
+                    / (3.0 * time_step);
+                let centroid = (p0 + p1 + p2) / 3.0;
+                let air_velocity = self.wind.velocity_at(&centroid, self.time);
+                let force = self.aerodynamics.force(
+                    0.5 * double_area,
+                    &(cross / double_area),
+                    &(air_velocity - velocity),
+                );
+                for i in triangle {
+                    self.inertial_impluse_term
+                        .fixed_rows_mut::<3>(i * 3)
+                        .add_assign(&(force * (self.h2 / 3.0)));
+                }
+            }
+        }
+
+        if let Some(hook) = &self.force_hook {
+            self.external_forces.fill(0.0);
+            hook(&self.cloth, &mut self.external_forces, self.time);
+            self.inertial_impluse_term
+                .axpy(self.h2, &self.external_forces, 1.0);
+        }
     }
 
     fn local_step(&mut self) {
-        compute_vector_d(&self.cloth, &mut self.vector_d);
+        profile_span!("local_step");
+        compute_vector_d(
+            &self.cloth,
+            &mut self.vector_d,
+            &mut self.volume_projections,
+        );
     }
 
     fn global_step(&mut self) {
-        let b = &self.h2_matrix_j * &self.vector_d + &self.inertial_impluse_term;
-        self.cloth.particle_positions = self.cholesky.solve(&b);
+        profile_span!("global_step");
+        // b = h^2 * J * d + inertial_impluse_term
+        self.rhs.copy_from(&self.inertial_impluse_term);
+        self.rhs.gemv(1.0, &self.h2_matrix_j, &self.vector_d, 1.0);
+        self.cholesky.solve_mut(&mut self.rhs);
+        std::mem::swap(&mut self.cloth.particle_positions, &mut self.rhs);
+    }
+
+    /// `x' = y + h^2 * M^-1 * (f_ext + f)`, where the constraint forces `f = J * d - L * x`
+    /// are the negative gradient of the projective dynamics energy at the current positions.
+    fn explicit_step(&mut self) {
+        profile_span!("explicit_step");
+        self.local_step();
+        let matrix_l = self
+            .matrix_l
+            .as_ref()
+            .expect("the stiffness matrix is kept for the explicit integrator");
+        // b = inertial_impluse_term + h^2 * J * d - h^2 * L * x
+        self.rhs.copy_from(&self.inertial_impluse_term);
+        self.rhs.gemv(1.0, &self.h2_matrix_j, &self.vector_d, 1.0);
+        self.rhs
+            .gemv(-self.h2, matrix_l, &self.cloth.particle_positions, 1.0);
+        // M is diagonal
+        for (i, &mass) in self.cloth.particle_masses.iter().enumerate() {
+            self.rhs.fixed_rows_mut::<3>(i * 3).unscale_mut(mass);
+        }
+        std::mem::swap(&mut self.cloth.particle_positions, &mut self.rhs);
+    }
+}
+
+fn solve_collision(
+    cloth: &mut Cloth,
+    collider_set: &ColliderSet,
+    params: ContactParams,
+    scratch: &mut CollisionScratch,
+    mut contacts: Option<(&mut Vec<ContactEvent>, bool)>,
+) {
+    let colliders = collider_set.colliders();
+    if colliders.is_empty() {
+        return;
+    }
+    // broadphase over the collider bounds, rebuilt each step since colliders may move
+    scratch.collider_aabbs.clear();
+    scratch
+        .collider_aabbs
+        .extend(colliders.iter().map(|c| c.aabb()));
+    scratch.broadphase.rebuild(&scratch.collider_aabbs);
+    if !scratch.broadphase.aabb().intersects(&cloth.compute_aabb()) {
+        return;
+    }
+    let broadphase = &scratch.broadphase;
+    let candidates = &mut scratch.candidates;
+    for i in 0..cloth.num_particles() {
+        let position: Vector3 = cloth.particle_positions.fixed_rows::<3>(i * 3).into_owned();
+        candidates.clear();
+        broadphase.query_aabb(&Aabb::new(position, position), |collider_index| {
+            candidates.push(collider_index)
+        });
+        if candidates.is_empty() {
+            continue;
+        }
+        // keep the order in which the colliders were added
+        candidates.sort_unstable();
+        let prev_position: Vector3 = cloth
+            .prev_particle_positions
+            .fixed_rows::<3>(i * 3)
+            .into_owned();
+        let mut new_position = position;
+        let mut new_prev_position = prev_position;
+        for &collider_index in candidates.iter() {
+            let collider = &colliders[collider_index];
+            if let Some(contact) = collide_point(collider, params, &prev_position, &new_position) {
+                new_position = contact.position;
+                if params.velocity_correction > 0.0 {
+                    // normal velocity relative to the collider surface
+                    let relative_motion =
+                        new_position - new_prev_position - contact.surface_displacement;
+                    let normal_motion = contact.normal.dot(&relative_motion);
+                    new_prev_position +=
+                        contact.normal * (normal_motion * params.velocity_correction);
+                }
+                if let Some((events, shared)) = &mut contacts {
+                    events.push(ContactEvent {
+                        particle_index: i,
+                        collider: collider_set.handles()[collider_index],
+                        shared: *shared,
+                        depth: contact.depth,
+                        normal: contact.normal,
+                    });
+                }
+            }
+        }
+        cloth
+            .particle_positions
+            .fixed_rows_mut::<3>(i * 3)
+            .copy_from(&new_position);
+        cloth
+            .prev_particle_positions
+            .fixed_rows_mut::<3>(i * 3)
+            .copy_from(&new_prev_position);
+    }
+}
+
+/// Remember the current collider transforms, so that the motion during the next step can be
+/// derived.
+pub(crate) fn end_collider_step(colliders: &mut [TransformedCollider]) {
+    for collider in colliders {
+        collider.prev_transform = collider.transform;
     }
 }
 
-fn compute_vector_d(cloth: &Cloth, vector_d: &mut DVector) {
+#[derive(Clone, Copy)]
+struct ContactParams {
+    friction: Number,
+    velocity_correction: Number,
+    /// The part of the collider motion since the previous step that happened during this one.
+    collider_motion_fraction: Number,
+}
+
+struct PointContact {
+    /// The resolved position of the point.
+    position: Vector3,
+    normal: Vector3,
+    depth: Number,
+    /// How far the collider surface under the point moved during the step.
+    surface_displacement: Vector3,
+}
+
+/// Push a particle out of a collider and apply friction. Returns `None` if they do not collide.
+fn collide_point(
+    collider: &TransformedCollider,
+    params: ContactParams,
+    prev_position: &Vector3,
+    position: &Vector3,
+) -> Option<PointContact> {
+    let point = point![position.x, position.y, position.z];
+    let new_point = collider.compute_collision_with_point(point)?;
+    let mut new_position = new_point.coords;
+    let push = new_point - point;
+    let push_length = push.magnitude();
+    let normal = if push_length > Number::EPSILON {
+        push / push_length
+    } else {
+        Vector3::zeros()
+    };
+    let surface_displacement =
+        collider.point_displacement(new_point) * params.collider_motion_fraction;
+    if params.friction > 0.0 && push_length > Number::EPSILON {
+        // remove part of the motion tangential to the contact normal
+        // motion relative to the collider surface
+        let displacement = new_position - prev_position - surface_displacement;
+        let tangential = displacement - normal * normal.dot(&displacement);
+        new_position -= tangential * params.friction;
+    }
+    Some(PointContact {
+        position: new_position,
+        normal,
+        depth: push_length,
+        surface_displacement,
+    })
+}
+
+/// The best fit rigid motion of the particles over the last step, as the center of mass and
+/// the linear and angular displacement about it, which have the momentum of the particles.
+fn rigid_motion(
+    masses: &[Number],
+    positions: &DVector,
+    prev_positions: &DVector,
+) -> (Vector3, Vector3, Vector3) {
+    let total_mass: Number = masses.iter().sum();
+    if total_mass <= 0.0 {
+        return (Vector3::zeros(), Vector3::zeros(), Vector3::zeros());
+    }
+    let mut center = Vector3::zeros();
+    let mut linear = Vector3::zeros();
+    for (i, &mass) in masses.iter().enumerate() {
+        let position = positions.fixed_rows::<3>(i * 3);
+        center += position * mass;
+        linear += (position - prev_positions.fixed_rows::<3>(i * 3)) * mass;
+    }
+    center /= total_mass;
+    linear /= total_mass;
+
+    let mut momentum = Vector3::zeros();
+    let mut inertia = Matrix3::zeros();
+    for (i, &mass) in masses.iter().enumerate() {
+        let position = positions.fixed_rows::<3>(i * 3);
+        let offset = position - center;
+        let displacement = position - prev_positions.fixed_rows::<3>(i * 3);
+        momentum += offset.cross(&displacement) * mass;
+        inertia +=
+            (Matrix3::identity() * offset.norm_squared() - offset * offset.transpose()) * mass;
+    }
+    // a cloth on a line has no inertia about it, any spin about it is deformation
+    let angular = inertia
+        .try_inverse()
+        .map_or_else(Vector3::zeros, |inverse| inverse * momentum);
+    (center, linear, angular)
+}
+
+fn check_particle_index(
+    num_particles: usize,
+    constraint: &'static str,
+    constraint_index: usize,
+    particle_index: usize,
+) -> Result<(), SolverBuildError> {
+    if particle_index < num_particles {
+        Ok(())
+    } else {
+        Err(SolverBuildError::ParticleIndexOutOfRange {
+            constraint,
+            constraint_index,
+            particle_index,
+        })
+    }
+}
+
+fn check_attachment(
+    num_particles: usize,
+    index: usize,
+    attachment: &Attachment,
+) -> Result<(), SolverBuildError> {
+    check_particle_index(
+        num_particles,
+        "attachment",
+        index,
+        attachment.particle_index,
+    )?;
+    check_stiffness("attachment", index, attachment.stiffness)
+}
+
+fn check_seam(
+    num_particles: usize,
+    seam_index: usize,
+    (particle_index_0, particle_index_1): (usize, usize),
+    stiffness: Number,
+) -> Result<(), SolverBuildError> {
+    check_particle_index(num_particles, "seam", seam_index, particle_index_0)?;
+    check_particle_index(num_particles, "seam", seam_index, particle_index_1)?;
+    check_stiffness("seam", seam_index, stiffness)?;
+    if particle_index_0 == particle_index_1 {
+        return Err(SolverBuildError::DegenerateSeam { seam_index });
+    }
+    Ok(())
+}
+
+fn check_stiffness(
+    constraint: &'static str,
+    constraint_index: usize,
+    stiffness: Number,
+) -> Result<(), SolverBuildError> {
+    if stiffness >= 0.0 && stiffness.is_finite() {
+        Ok(())
+    } else {
+        Err(SolverBuildError::InvalidStiffness {
+            constraint,
+            constraint_index,
+            stiffness,
+        })
+    }
+}
+
+fn validate_cloth(cloth: &Cloth) -> Result<(), SolverBuildError> {
+    let num_particles = cloth.num_particles();
+    if num_particles == 0 {
+        return Err(SolverBuildError::EmptyCloth);
+    }
+    for (particle_index, &mass) in cloth.particle_masses.iter().enumerate() {
+        if !(mass > 0.0 && mass.is_finite()) {
+            return Err(SolverBuildError::InvalidMass {
+                particle_index,
+                mass,
+            });
+        }
+        if !cloth
+            .get_particle_position(particle_index)
+            .iter()
+            .all(|x| x.is_finite())
+        {
+            return Err(SolverBuildError::InvalidPosition { particle_index });
+        }
+    }
+
+    let check_index = |constraint: &'static str, constraint_index: usize, particle_index: usize| {
+        check_particle_index(num_particles, constraint, constraint_index, particle_index)
+    };
+    let check_triangle =
+        |constraint: &'static str, constraint_index: usize, triangle: [usize; 3]| {
+            for particle_index in triangle {
+                check_index(constraint, constraint_index, particle_index)?;
+            }
+            let [a, b, c] = triangle;
+            if a == b || b == c || c == a {
+                return Err(SolverBuildError::DegenerateTriangle {
+                    constraint,
+                    constraint_index,
+                    triangle,
+                });
+            }
+            Ok(())
+        };
+
+    for (spring_index, spring) in cloth.springs.iter().enumerate() {
+        check_index("spring", spring_index, spring.particle_index_0)?;
+        check_index("spring", spring_index, spring.particle_index_1)?;
+        check_stiffness("spring", spring_index, spring.stiffness)?;
+        if spring.particle_index_0 == spring.particle_index_1 {
+            return Err(SolverBuildError::DegenerateSpring { spring_index });
+        }
+        if !(spring.rest_length >= 0.0 && spring.rest_length.is_finite()) {
+            return Err(SolverBuildError::InvalidRestLength {
+                spring_index,
+                rest_length: spring.rest_length,
+            });
+        }
+    }
+    for (index, attachment) in cloth.attachments.iter().enumerate() {
+        check_attachment(num_particles, index, attachment)?;
+    }
+    for (index, lra) in cloth.long_range_attachments.iter().enumerate() {
+        check_index("long range attachment", index, lra.particle_index)?;
+        check_index("long range attachment", index, lra.anchor_index)?;
+        check_stiffness("long range attachment", index, lra.stiffness)?;
+    }
+    for (seam_index, seam) in cloth.seams.iter().enumerate() {
+        check_seam(
+            num_particles,
+            seam_index,
+            (seam.particle_index_0, seam.particle_index_1),
+            seam.stiffness,
+        )?;
+    }
+    for (index, &triangle) in cloth.triangles().iter().enumerate() {
+        check_triangle("triangle", index, triangle)?;
+    }
+    for (index, area) in cloth.area_constraints.iter().enumerate() {
+        check_triangle("area constraint", index, area.particle_indices)?;
+        check_stiffness("area constraint", index, area.stiffness)?;
+    }
+    for (index, volume) in cloth.volume_constraints.iter().enumerate() {
+        for &particle_index in volume.particle_indices() {
+            check_index("volume constraint", index, particle_index)?;
+        }
+        for triangle in volume.triangles() {
+            check_triangle("volume constraint", index, triangle)?;
+        }
+        check_stiffness("volume constraint", index, volume.stiffness)?;
+        if !volume.is_closed() {
+            return Err(SolverBuildError::OpenVolumeSurface {
+                volume_index: index,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Compute `h^2 * J`, `M` and the cholesky factorization of `M + h^2 * L`.
+fn compute_system(
+    cloth: &Cloth,
+    h2: Number,
+) -> Result<(DMatrix, DMatrix, Cholesky<Number, Dyn>), SolverBuildError> {
+    let (matrix_j, matrix_m, system_matrix) = {
+        profile_span!("assemble_system");
+        let matrix_l = compute_matrix_l(cloth); // size = (3 * numParticles) x (3 * numParticles)
+        let matrix_j = compute_matrix_j(cloth);
+        let matrix_m = compute_matrix_m(cloth);
+        let system_matrix = &matrix_m + h2 * &matrix_l;
+        (h2 * matrix_j, matrix_m, system_matrix)
+    };
+    profile_span!("factorize_system");
+    let cholesky = nalgebra::linalg::Cholesky::new(system_matrix)
+        .ok_or(SolverBuildError::NotPositiveDefinite)?;
+    Ok((matrix_j, matrix_m, cholesky))
+}
+
+fn compute_vector_d(cloth: &Cloth, vector_d: &mut DVector, volume_projections: &mut Vec<Vector3>) {
     debug_assert!(vector_d.len() == cloth.num_constraints() * 3);
+    let num_local_constraints = cloth.attachments.len()
+        + cloth.springs.len()
+        + cloth.long_range_attachments.len()
+        + cloth.seams.len();
+    let (local_d, rest_d) = vector_d
+        .as_mut_slice()
+        .split_at_mut(num_local_constraints * 3);
+    let (area_d, volume_d) =
+        rest_d.split_at_mut(cloth.area_constraints.len() * AreaConstraint::NUM_SLOTS * 3);
 
-    let mut constraint_index = 0;
+    // every local constraint writes its own slot of d, so they can be projected independently
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        local_d
+            .par_chunks_mut(3)
+            .enumerate()
+            .for_each(|(constraint_index, d)| {
+                d.copy_from_slice(project_constraint(cloth, constraint_index).as_slice());
+            });
+    }
+    #[cfg(not(feature = "parallel"))]
+    for (constraint_index, d) in local_d.chunks_mut(3).enumerate() {
+        d.copy_from_slice(project_constraint(cloth, constraint_index).as_slice());
+    }
 
-    for attachment in &cloth.attachments {
-        let d = attachment.target_position;
-        vector_d
-            .fixed_rows_mut::<3>(constraint_index * 3)
-            .copy_from(&d);
-        constraint_index += 1;
+    // an area constraint projects both of its slots at once
+    let project_area = |area: &AreaConstraint, d: &mut [Number]| {
+        let [column0, column1] = area.project(&cloth.particle_positions);
+        d[..3].copy_from_slice(column0.as_slice());
+        d[3..].copy_from_slice(column1.as_slice());
+    };
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        area_d
+            .par_chunks_mut(AreaConstraint::NUM_SLOTS * 3)
+            .zip(cloth.area_constraints.par_iter())
+            .for_each(|(d, area)| project_area(area, d));
+    }
+    #[cfg(not(feature = "parallel"))]
+    for (d, area) in area_d
+        .chunks_mut(AreaConstraint::NUM_SLOTS * 3)
+        .zip(&cloth.area_constraints)
+    {
+        project_area(area, d);
     }
 
-    for spring in &cloth.springs {
+    // a volume constraint projects all of its particles at once, one slot per particle
+    let mut volume_slots = volume_d.chunks_mut(3);
+    for volume in &cloth.volume_constraints {
+        volume_projections.resize(volume.particle_indices().len(), Vector3::zeros());
+        volume.project(&cloth.particle_positions, volume_projections);
+        for (d, projection) in (&mut volume_slots).zip(volume_projections.iter()) {
+            d.copy_from_slice(projection.as_slice());
+        }
+    }
+}
+
+/// The projection `d` of a constraint. Constraints are ordered as attachments, springs, long
+/// range attachments, seams, then the slots of the area constraints and the particles of the
+/// volume constraints, which are projected by [`compute_vector_d`].
+fn project_constraint(cloth: &Cloth, constraint_index: usize) -> Vector3 {
+    let mut index = constraint_index;
+    if index < cloth.attachments.len() {
+        return cloth.attachments[index].target_position;
+    }
+    index -= cloth.attachments.len();
+
+    if index < cloth.springs.len() {
+        let spring = &cloth.springs[index];
         let p0 = cloth
             .particle_positions
             .fixed_rows::<3>(spring.particle_index_0 * 3);
@@ -144,12 +2025,40 @@ fn compute_vector_d(cloth: &Cloth, vector_d: &mut DVector) {
             .fixed_rows::<3>(spring.particle_index_1 * 3);
         let delta = p0 - p1;
         //compute the projection of delta onto the spring direction
-        let d = delta.normalize() * spring.rest_length;
-        vector_d
-            .fixed_rows_mut::<3>(constraint_index * 3)
-            .copy_from(&d);
-        constraint_index += 1;
+        return delta.normalize() * spring.rest_length;
     }
+    index -= cloth.springs.len();
+
+    if index < cloth.long_range_attachments.len() {
+        let lra = &cloth.long_range_attachments[index];
+        let p = cloth
+            .particle_positions
+            .fixed_rows::<3>(lra.particle_index * 3);
+        let anchor = cloth
+            .particle_positions
+            .fixed_rows::<3>(lra.anchor_index * 3);
+        let delta = p - anchor;
+        let distance = delta.magnitude();
+        // project onto the sphere around the anchor only when the particle is outside of it
+        return if distance > lra.max_distance {
+            anchor + delta * (lra.max_distance / distance)
+        } else {
+            p.into_owned()
+        };
+    }
+    index -= cloth.long_range_attachments.len();
+
+    let seam = &cloth.seams[index];
+    let p0 = cloth
+        .particle_positions
+        .fixed_rows::<3>(seam.particle_index_0 * 3);
+    let p1 = cloth
+        .particle_positions
+        .fixed_rows::<3>(seam.particle_index_1 * 3);
+    // a closed seam has coincident particles, without a direction to project onto
+    (p0 - p1)
+        .try_normalize(Number::EPSILON)
+        .map_or(Vector3::zeros(), |direction| direction * seam.rest_length)
 }
 
 /// calculate the matrix L in projective dynamics.
@@ -189,6 +2098,55 @@ fn compute_matrix_l(cloth: &Cloth) -> DMatrix {
             .fixed_view_mut::<3, 3>(3 * j, 3 * i)
             .add_assign(-k * i3);
     }
+
+    for lra in &cloth.long_range_attachments {
+        let k = lra.stiffness;
+        let i = lra.particle_index;
+        matrix_l
+            .fixed_view_mut::<3, 3>(3 * i, 3 * i)
+            .add_assign(&(k * i3));
+    }
+
+    for seam in &cloth.seams {
+        let k = seam.stiffness;
+        let i = seam.particle_index_0;
+        let j = seam.particle_index_1;
+        matrix_l
+            .fixed_view_mut::<3, 3>(3 * i, 3 * i)
+            .add_assign(&(k * i3));
+        matrix_l
+            .fixed_view_mut::<3, 3>(3 * j, 3 * j)
+            .add_assign(&(k * i3));
+        matrix_l
+            .fixed_view_mut::<3, 3>(3 * i, 3 * j)
+            .add_assign(-k * i3);
+        matrix_l
+            .fixed_view_mut::<3, 3>(3 * j, 3 * i)
+            .add_assign(-k * i3);
+    }
+
+    for area in &cloth.area_constraints {
+        let k = area.weight();
+        for slot in 0..AreaConstraint::NUM_SLOTS {
+            let coefficients = area.slot_coefficients(slot);
+            for (a, &i) in area.particle_indices.iter().enumerate() {
+                for (b, &j) in area.particle_indices.iter().enumerate() {
+                    matrix_l
+                        .fixed_view_mut::<3, 3>(3 * i, 3 * j)
+                        .add_assign(&(k * coefficients[a] * coefficients[b] * i3));
+                }
+            }
+        }
+    }
+
+    for volume in &cloth.volume_constraints {
+        let k = volume.stiffness;
+        for &i in volume.particle_indices() {
+            matrix_l
+                .fixed_view_mut::<3, 3>(3 * i, 3 * i)
+                .add_assign(&(k * i3));
+        }
+    }
     matrix_l
 }
 
@@ -217,6 +2175,51 @@ fn compute_matrix_j(cloth: &Cloth) -> DMatrix {
             .copy_from(&(-k * i3));
         constraint_index += 1;
     }
+
+    for lra in cloth.long_range_attachments.iter() {
+        let i = lra.particle_index;
+        let k = lra.stiffness;
+        matrix_j
+            .fixed_view_mut::<3, 3>(3 * i, 3 * constraint_index)
+            .copy_from(&(k * i3));
+        constraint_index += 1;
+    }
+
+    for seam in cloth.seams.iter() {
+        let i = seam.particle_index_0;
+        let j = seam.particle_index_1;
+        let k = seam.stiffness;
+        matrix_j
+            .fixed_view_mut::<3, 3>(3 * i, 3 * constraint_index)
+            .copy_from(&(k * i3));
+        matrix_j
+            .fixed_view_mut::<3, 3>(3 * j, 3 * constraint_index)
+            .copy_from(&(-k * i3));
+        constraint_index += 1;
+    }
+
+    for area in cloth.area_constraints.iter() {
+        let k = area.weight();
+        for slot in 0..AreaConstraint::NUM_SLOTS {
+            let coefficients = area.slot_coefficients(slot);
+            for (&i, coefficient) in area.particle_indices.iter().zip(coefficients) {
+                matrix_j
+                    .fixed_view_mut::<3, 3>(3 * i, 3 * constraint_index)
+                    .copy_from(&(k * coefficient * i3));
+            }
+            constraint_index += 1;
+        }
+    }
+
+    for volume in cloth.volume_constraints.iter() {
+        let k = volume.stiffness;
+        for &i in volume.particle_indices() {
+            matrix_j
+                .fixed_view_mut::<3, 3>(3 * i, 3 * constraint_index)
+                .copy_from(&(k * i3));
+            constraint_index += 1;
+        }
+    }
     matrix_j
 }
 
@@ -0,0 +1,209 @@
+use crate::math::{Number, Vector3};
+
+/// Air moving at a constant velocity, dragging the particles along.
+///
+/// The force on a particle is `drag * mass * (velocity - particle_velocity)`, so `drag` is
+/// independent of the cloth resolution.
+#[derive(Clone, Copy, Debug)]
+pub struct Wind {
+    pub velocity: Vector3,
+    pub drag: Number,
+    /// Gusts varying over space and time on top of `velocity`.
+    pub turbulence: Turbulence,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            velocity: Vector3::zeros(),
+            drag: 0.0,
+            turbulence: Turbulence::default(),
+        }
+    }
+}
+
+impl Wind {
+    /// Force from the mean wind velocity, ignoring the turbulence.
+    #[inline]
+    pub fn force(&self, particle_mass: Number, particle_velocity: &Vector3) -> Vector3 {
+        (self.velocity - particle_velocity) * (self.drag * particle_mass)
+    }
+
+    /// Velocity of the air at `position` and `time`, including the turbulence.
+    #[inline]
+    pub fn velocity_at(&self, position: &Vector3, time: Number) -> Vector3 {
+        self.velocity + self.turbulence.velocity_at(position, time, &self.velocity)
+    }
+
+    /// Force on a particle at `position`, including the turbulence.
+    #[inline]
+    pub fn force_at(
+        &self,
+        particle_mass: Number,
+        position: &Vector3,
+        particle_velocity: &Vector3,
+        time: Number,
+    ) -> Vector3 {
+        (self.velocity_at(position, time) - particle_velocity) * (self.drag * particle_mass)
+    }
+}
+
+/// Lift and drag on each triangle of the cloth, from its motion relative to the air. Unlike
+/// the drag of [`Wind`], which acts on each particle alike, the force depends on how the
+/// triangles face the air, so a falling cloth glides, flutters and tumbles.
+///
+/// The force on a triangle of area `A` with the air flowing at `v` relative to it, at an angle
+/// `theta` to its normal `n`, is `0.5 * density * A * cos(theta) * |v|^2` times
+/// `drag_coefficient` along `v` plus `lift_coefficient` along the part of `n` orthogonal to
+/// `v`. It is split evenly between the three particles.
+#[derive(Clone, Copy, Debug)]
+pub struct Aerodynamics {
+    pub density: Number,
+    pub drag_coefficient: Number,
+    pub lift_coefficient: Number,
+}
+
+impl Default for Aerodynamics {
+    fn default() -> Self {
+        Self {
+            density: 1.2,
+            drag_coefficient: 0.0,
+            lift_coefficient: 0.0,
+        }
+    }
+}
+
+impl Aerodynamics {
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.density > 0.0 && (self.drag_coefficient != 0.0 || self.lift_coefficient != 0.0)
+    }
+
+    /// Force on a triangle of `area` and unit `normal`, either side, with the air moving at
+    /// `relative_velocity` with respect to it.
+    pub fn force(&self, area: Number, normal: &Vector3, relative_velocity: &Vector3) -> Vector3 {
+        let speed = relative_velocity.norm();
+        if speed <= Number::EPSILON {
+            return Vector3::zeros();
+        }
+        let direction = relative_velocity / speed;
+        // face the normal downstream, so that the air pushes along it
+        let cos_theta = normal.dot(&direction);
+        let normal = if cos_theta < 0.0 { -normal } else { *normal };
+        let cos_theta = cos_theta.abs();
+        let pressure = 0.5 * self.density * area * cos_theta * speed * speed;
+        // `normal - cos_theta * direction` has length `sin(theta)`, so the lift vanishes when
+        // the triangle faces the air or is edge on
+        let lift = (normal - direction * cos_theta) * self.lift_coefficient;
+        (direction * self.drag_coefficient + lift) * pressure
+    }
+}
+
+/// Procedural gusts from gradient noise. The gust pattern is carried along by the mean wind
+/// and slowly changes shape over time.
+#[derive(Clone, Copy, Debug)]
+pub struct Turbulence {
+    /// Largest velocity added to the wind, zero disables the turbulence.
+    pub amplitude: Number,
+    /// Spatial frequency of the largest gusts, in cycles per unit length.
+    pub frequency: Number,
+    /// How fast the gust pattern changes shape, in cycles per second.
+    pub evolution: Number,
+    /// Number of noise layers, each with twice the frequency and half the amplitude of the
+    /// previous one.
+    pub octaves: u32,
+    pub seed: u32,
+}
+
+impl Default for Turbulence {
+    fn default() -> Self {
+        Self {
+            amplitude: 0.0,
+            frequency: 0.5,
+            evolution: 0.5,
+            octaves: 3,
+            seed: 0,
+        }
+    }
+}
+
+impl Turbulence {
+    /// The velocity added at `position` and `time` to air moving at `carrier_velocity`.
+    pub fn velocity_at(
+        &self,
+        position: &Vector3,
+        time: Number,
+        carrier_velocity: &Vector3,
+    ) -> Vector3 {
+        if self.amplitude == 0.0 {
+            return Vector3::zeros();
+        }
+        // the pattern is frozen in the moving air, and drifts in noise space to evolve
+        let origin = position - carrier_velocity * time;
+        let drift = Vector3::new(0.31, 0.17, 0.43) * (time * self.evolution);
+        let mut velocity = Vector3::zeros();
+        let mut frequency = self.frequency;
+        let mut weight = 1.0;
+        let mut total_weight = 0.0;
+        for octave in 0..self.octaves.max(1) {
+            let p = origin * frequency + drift;
+            let seed = self.seed.wrapping_add(octave.wrapping_mul(0x68e3_1da4));
+            // independent noise for each component
+            for axis in 0..3 {
+                let axis_seed = seed.wrapping_add((axis as u32).wrapping_mul(0x9e37_79b9));
+                velocity[axis] += weight * gradient_noise(&p, axis_seed);
+            }
+            total_weight += weight;
+            frequency *= 2.0;
+            weight *= 0.5;
+        }
+        velocity * (self.amplitude / total_weight)
+    }
+}
+
+/// Gradient noise in about `[-1, 1]`, zero at the lattice points.
+fn gradient_noise(p: &Vector3, seed: u32) -> Number {
+    let cell = p.map(Number::floor);
+    let f = p - cell;
+    let [x, y, z] = [cell.x as i32, cell.y as i32, cell.z as i32];
+    let fade = |t: Number| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    let [u, v, w] = [fade(f.x), fade(f.y), fade(f.z)];
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        let gradient = lattice_gradient(lattice_hash(x + dx, y + dy, z + dz, seed));
+        gradient.dot(&(f - Vector3::new(dx as Number, dy as Number, dz as Number)))
+    };
+    let lerp = |a: Number, b: Number, t: Number| a + (b - a) * t;
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+    lerp(lerp(x00, x10, v), lerp(x01, x11, v), w)
+}
+
+fn lattice_hash(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+    let mut h = seed
+        ^ (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^ (h >> 15)
+}
+
+/// One of the 12 edge directions of a cube, as in improved Perlin noise.
+fn lattice_gradient(hash: u32) -> Vector3 {
+    match hash % 12 {
+        0 => Vector3::new(1.0, 1.0, 0.0),
+        1 => Vector3::new(-1.0, 1.0, 0.0),
+        2 => Vector3::new(1.0, -1.0, 0.0),
+        3 => Vector3::new(-1.0, -1.0, 0.0),
+        4 => Vector3::new(1.0, 0.0, 1.0),
+        5 => Vector3::new(-1.0, 0.0, 1.0),
+        6 => Vector3::new(1.0, 0.0, -1.0),
+        7 => Vector3::new(-1.0, 0.0, -1.0),
+        8 => Vector3::new(0.0, 1.0, 1.0),
+        9 => Vector3::new(0.0, -1.0, 1.0),
+        10 => Vector3::new(0.0, 1.0, -1.0),
+        _ => Vector3::new(0.0, -1.0, -1.0),
+    }
+}
@@ -0,0 +1,298 @@
+use simulation::{closest_point_on_triangle, Aabb, Collider, SpatialHash, TransformedCollider};
+
+use crate::{
+    cloth::Cloth,
+    math::{Isometry3, Number, Vector3},
+    solver::{end_collider_step, ColliderHandle, ColliderSet, FastMassSpringSolver},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ClothHandle(usize);
+
+/// Several independent cloths, each with its own solver, stepped together against a set of
+/// shared colliders.
+///
+/// The cloths only interact with each other when a cloth thickness is set, see
+/// [`World::set_cloth_thickness`]. Colliders added to a single solver only affect that cloth.
+#[derive(Default)]
+pub struct World {
+    solvers: Vec<FastMassSpringSolver>,
+    colliders: ColliderSet,
+    cloth_thickness: Number,
+    cloth_collision_scratch: ClothCollisionScratch,
+}
+
+/// Buffers reused by the cloth-cloth collision pass.
+#[derive(Default)]
+struct ClothCollisionScratch {
+    spatial_hash: Option<SpatialHash>,
+    /// The cloth and triangle index of each item of the spatial hash.
+    triangles: Vec<(usize, usize)>,
+    triangle_aabbs: Vec<Aabb>,
+    /// The particles of the cloth being resolved that were pushed, and where to.
+    pushed: Vec<(usize, Vector3)>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_cloth(&mut self, solver: FastMassSpringSolver) -> ClothHandle {
+        self.solvers.push(solver);
+        ClothHandle(self.solvers.len() - 1)
+    }
+
+    #[inline]
+    pub fn solver(&self, handle: ClothHandle) -> &FastMassSpringSolver {
+        &self.solvers[handle.0]
+    }
+
+    #[inline]
+    pub fn solver_mut(&mut self, handle: ClothHandle) -> &mut FastMassSpringSolver {
+        &mut self.solvers[handle.0]
+    }
+
+    #[inline]
+    pub fn solvers(&self) -> &[FastMassSpringSolver] {
+        &self.solvers
+    }
+
+    #[inline]
+    pub fn solvers_mut(&mut self) -> &mut [FastMassSpringSolver] {
+        &mut self.solvers
+    }
+
+    /// Add a collider shared by all cloths, which wakes the sleeping cloths it overlaps.
+    pub fn add_collider(
+        &mut self,
+        collider: impl Into<Collider>,
+        transform: Isometry3,
+    ) -> ColliderHandle {
+        let handle = self.colliders.add(collider.into(), transform);
+        let aabb = self.colliders.get(handle).expect("just added").aabb();
+        self.wake_up_near(&aabb);
+        handle
+    }
+
+    /// Remove a shared collider and return it, or `None` if it was already removed.
+    pub fn remove_collider(&mut self, handle: ColliderHandle) -> Option<Collider> {
+        let removed = self.colliders.remove(handle)?;
+        self.wake_up_near(&removed.aabb());
+        Some(removed.collider)
+    }
+
+    /// Move a shared collider, waking the sleeping cloths it overlaps before or after the
+    /// move.
+    ///
+    /// Panics if the collider was removed.
+    pub fn set_collider_transform(&mut self, handle: ColliderHandle, transform: Isometry3) {
+        let collider = self
+            .colliders
+            .get_mut(handle)
+            .expect("the collider was removed");
+        if collider.transform == transform {
+            return;
+        }
+        let old_aabb = collider.aabb();
+        collider.transform = transform;
+        let aabb = old_aabb.merged(&collider.aabb());
+        self.wake_up_near(&aabb);
+    }
+
+    fn wake_up_near(&mut self, aabb: &Aabb) {
+        for solver in &mut self.solvers {
+            solver.wake_up_near(aabb);
+        }
+    }
+
+    /// Panics if the collider was removed.
+    #[inline]
+    pub fn collider_transform(&self, handle: ColliderHandle) -> Isometry3 {
+        self.colliders
+            .get(handle)
+            .expect("the collider was removed")
+            .transform
+    }
+
+    /// The shared colliders.
+    #[inline]
+    pub fn colliders(&self) -> &[TransformedCollider] {
+        self.colliders.colliders()
+    }
+
+    /// The handle of each of [`Self::colliders`].
+    #[inline]
+    pub fn collider_handles(&self) -> &[ColliderHandle] {
+        self.colliders.handles()
+    }
+
+    /// Keep the particles of each cloth at least `thickness` away from the triangles of the
+    /// other cloths, so that stacked or layered cloths do not pass through each other. Zero
+    /// disables the collisions between cloths, which is the default.
+    ///
+    /// The collisions are resolved once per [`World::step`], after the cloths are stepped, so
+    /// the thickness should exceed the distance a particle travels in a step.
+    pub fn set_cloth_thickness(&mut self, thickness: Number) {
+        self.cloth_thickness = thickness.max(0.0);
+    }
+
+    #[inline]
+    pub fn cloth_thickness(&self) -> Number {
+        self.cloth_thickness
+    }
+
+    /// Advance every cloth by its time step. The solvers are expected to share the same
+    /// time step.
+    pub fn step(&mut self) {
+        profile_span!("world_step");
+        for solver in &mut self.solvers {
+            solver.step_with_shared_colliders(&self.colliders);
+        }
+        end_collider_step(self.colliders.colliders_mut());
+        if self.cloth_thickness > 0.0 && self.solvers.len() > 1 {
+            solve_cloth_collisions(
+                &mut self.solvers,
+                self.cloth_thickness,
+                &mut self.cloth_collision_scratch,
+            );
+        }
+    }
+}
+
+/// Push the particles of every cloth out of the triangles of the other cloths, through a
+/// spatial hash shared by the triangles of all cloths.
+fn solve_cloth_collisions(
+    solvers: &mut [FastMassSpringSolver],
+    thickness: Number,
+    scratch: &mut ClothCollisionScratch,
+) {
+    profile_span!("cloth_collision");
+    scratch.triangles.clear();
+    scratch.triangle_aabbs.clear();
+    let mut sum_extents = 0.0;
+    for (cloth_index, solver) in solvers.iter().enumerate() {
+        let cloth = solver.cloth();
+        for (triangle_index, triangle) in cloth.triangles().iter().enumerate() {
+            let [a, b, c] = triangle.map(|i| cloth.get_particle_position(i));
+            let aabb = Aabb::from_points([&a, &b, &c]).expanded(thickness);
+            sum_extents += aabb.extents().max();
+            scratch.triangles.push((cloth_index, triangle_index));
+            scratch.triangle_aabbs.push(aabb);
+        }
+    }
+    if scratch.triangles.is_empty() {
+        return;
+    }
+    // cells about the size of a triangle, so that a particle meets few triangles per cell
+    let cell_size = sum_extents / scratch.triangles.len() as Number;
+    let spatial_hash = scratch
+        .spatial_hash
+        .get_or_insert_with(|| SpatialHash::new(cell_size));
+    spatial_hash.clear(cell_size);
+    for (item, aabb) in scratch.triangle_aabbs.iter().enumerate() {
+        spatial_hash.insert(item, aabb);
+    }
+
+    for cloth_index in 0..solvers.len() {
+        let cloth_aabb = solvers[cloth_index].cloth_aabb().expanded(thickness);
+        let overlaps_other = solvers.iter().enumerate().any(|(other, solver)| {
+            other != cloth_index && solver.cloth_aabb().intersects(&cloth_aabb)
+        });
+        if !overlaps_other {
+            continue;
+        }
+
+        scratch.pushed.clear();
+        let mut max_push_squared: Number = 0.0;
+        let cloth = solvers[cloth_index].cloth();
+        for particle_index in 0..cloth.num_particles() {
+            let prev_position: Vector3 = cloth
+                .prev_particle_positions
+                .fixed_rows::<3>(particle_index * 3)
+                .into_owned();
+            let mut position = cloth.get_particle_position(particle_index);
+            let original_position = position;
+            let mut pushed = false;
+            for &item in spatial_hash.query_point(&position) {
+                let (other_index, triangle_index) = scratch.triangles[item];
+                if other_index == cloth_index {
+                    continue;
+                }
+                let other = solvers[other_index].cloth();
+                if let Some(resolved) = push_out_of_triangle(
+                    other,
+                    triangle_index,
+                    &prev_position,
+                    &position,
+                    thickness,
+                ) {
+                    position = resolved;
+                    pushed = true;
+                }
+            }
+            if pushed {
+                max_push_squared =
+                    max_push_squared.max((position - original_position).norm_squared());
+                scratch.pushed.push((particle_index, position));
+            }
+        }
+
+        if scratch.pushed.is_empty() {
+            continue;
+        }
+        let solver = &mut solvers[cloth_index];
+        let positions = &mut solver.cloth_mut().particle_positions;
+        for (particle_index, position) in &scratch.pushed {
+            positions
+                .fixed_rows_mut::<3>(particle_index * 3)
+                .copy_from(position);
+        }
+        solver.wake_up_if_pushed(max_push_squared.sqrt());
+        solver.end_step();
+    }
+}
+
+/// Push a particle to `thickness` away from a triangle of another cloth, on the side it came
+/// from. Returns `None` if it is already far enough.
+fn push_out_of_triangle(
+    other: &Cloth,
+    triangle_index: usize,
+    prev_position: &Vector3,
+    position: &Vector3,
+    thickness: Number,
+) -> Option<Vector3> {
+    let triangle = other.triangles()[triangle_index];
+    let [a, b, c] = triangle.map(|i| other.get_particle_position(i));
+    let (closest, _) = closest_point_on_triangle(position, &a, &b, &c);
+    let delta = position - closest;
+    let distance = delta.magnitude();
+    if distance >= thickness {
+        return None;
+    }
+    let normal = (b - a).cross(&(c - a)).try_normalize(Number::EPSILON)?;
+
+    // the side the particle was on at the start of the step, which it may have crossed since
+    let [prev_a, prev_b, prev_c] = triangle.map(|i| {
+        other
+            .prev_particle_positions
+            .fixed_rows::<3>(i * 3)
+            .into_owned()
+    });
+    let prev_normal = (prev_b - prev_a)
+        .cross(&(prev_c - prev_a))
+        .try_normalize(Number::EPSILON)
+        .unwrap_or(normal);
+    let mut side = (prev_position - prev_a).dot(&prev_normal);
+    if side.abs() <= Number::EPSILON {
+        side = (position - a).dot(&normal);
+    }
+    let outward = if side < 0.0 { -normal } else { normal };
+
+    let direction = if distance > Number::EPSILON && delta.dot(&outward) > 0.0 {
+        delta / distance
+    } else {
+        outward
+    };
+    Some(closest + direction * thickness)
+}
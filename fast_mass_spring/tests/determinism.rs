@@ -0,0 +1,164 @@
+//! Stepping a scene from scratch must give bitwise identical positions on every run and
+//! across versions, see the determinism notes of `FastMassSpringSolver`. The positions are
+//! compared against recorded hashes: a change that alters the results on purpose, e.g. a new
+//! iteration order, must update them.
+
+use fast_mass_spring::{
+    cloth::{Cloth, ClothBuilder, ClothFromMeshBuilder},
+    refine::RefinementCriteria,
+    solver::{DampingMode, FastMassSpringSolver},
+    wind::{Aerodynamics, Turbulence, Wind},
+    world::World,
+};
+use simulation::{
+    math::{Isometry3, UnitQuaternion, Vector3},
+    GridPlaneBuilder, SphereCollider,
+};
+
+const NUM_STEPS: usize = 40;
+const TIME_STEP: f32 = 1.0 / 60.0;
+
+/// FNV-1a over the bits of the positions. Unlike the std hasher, it is fixed across Rust
+/// versions and does not treat `0.0` and `-0.0` alike.
+fn hash_positions(positions: &[f32]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for position in positions {
+        for byte in position.to_bits().to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+fn horizontal(height: f32) -> Isometry3 {
+    Isometry3 {
+        rotation: UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f32::consts::FRAC_PI_2),
+        translation: Vector3::new(0.0, height, 0.0).into(),
+    }
+}
+
+fn grid_cloth(height: f32) -> Cloth {
+    let mut cloth = ClothBuilder {
+        width: 2.0,
+        height: 1.5,
+        width_segments: 8,
+        height_segments: 6,
+        structural_spring_stiffness: 80.0,
+        shear_spring_stiffness: 2.0,
+        mass: 1.0,
+        transform: horizontal(height),
+        stiffness_map: None,
+    }
+    .build();
+    cloth.add_area_constraints(20.0);
+    cloth
+}
+
+fn new_solver(cloth: Cloth) -> FastMassSpringSolver {
+    let mut solver = FastMassSpringSolver::new(cloth, TIME_STEP).expect("valid cloth");
+    solver.set_gravity(Vector3::new(0.0, -9.8, 0.0));
+    solver.set_num_iterations(5);
+    solver
+}
+
+/// A cloth dropped on a sphere in turbulent wind, refined and coarsened as it drapes.
+fn drop_on_sphere() -> u64 {
+    let mut solver = new_solver(grid_cloth(1.0));
+    solver.set_substeps(2);
+    solver.set_damping(0.99);
+    solver.set_damping_mode(DampingMode::Deformation);
+    solver.set_friction(0.3);
+    solver.set_wind(Wind {
+        velocity: Vector3::new(1.0, 0.0, 0.5),
+        drag: 0.5,
+        turbulence: Turbulence {
+            amplitude: 1.0,
+            ..Default::default()
+        },
+    });
+    solver.set_aerodynamics(Aerodynamics {
+        drag_coefficient: 1.0,
+        lift_coefficient: 0.5,
+        ..Default::default()
+    });
+    solver.add_collider(SphereCollider::new(0.5), Isometry3::identity());
+    let criteria = RefinementCriteria {
+        contact_distance: 0.1,
+        max_particles: 100,
+        ..Default::default()
+    };
+    let num_particles = solver.cloth().num_particles();
+    for step in 0..NUM_STEPS {
+        if step % 10 == 9 {
+            solver.refine(&criteria);
+        }
+        if step % 10 == 4 {
+            solver.coarsen(&criteria);
+        }
+        solver.step();
+    }
+    assert!(
+        solver.cloth().num_particles() > num_particles,
+        "the cloth was not refined"
+    );
+    hash_positions(solver.cloth().particle_positions.as_slice())
+}
+
+/// A cloth built from a mesh, with bending springs, hanging by two corners.
+fn hang_from_mesh() -> u64 {
+    let mesh = GridPlaneBuilder::new(1.0, 1.0, 6, 6).build();
+    let mut cloth = ClothFromMeshBuilder {
+        mesh: &mesh,
+        mass: 1.0,
+        spring_stiffness: 60.0,
+        bend_stiffness: 1.0,
+        stiffness_multipliers: None,
+    }
+    .build();
+    let top = (0..cloth.num_particles())
+        .map(|i| cloth.get_particle_position(i).y)
+        .fold(f32::MIN, f32::max);
+    cloth.attach_where(
+        |position| position.y >= top && position.x.abs() >= 0.49,
+        200.0,
+    );
+    let mut solver = new_solver(cloth);
+    for _ in 0..NUM_STEPS {
+        solver.step();
+    }
+    hash_positions(solver.cloth().particle_positions.as_slice())
+}
+
+/// Two cloths of a world falling on each other and on a shared sphere.
+fn stacked_in_world() -> u64 {
+    let mut world = World::new();
+    world.add_cloth(new_solver(grid_cloth(0.8)));
+    world.add_cloth(new_solver(grid_cloth(1.2)));
+    world.add_collider(SphereCollider::new(0.5), Isometry3::identity());
+    world.set_cloth_thickness(0.02);
+    for _ in 0..NUM_STEPS {
+        world.step();
+    }
+    let positions: Vec<f32> = world
+        .solvers()
+        .iter()
+        .flat_map(|solver| solver.cloth().particle_positions.iter().copied())
+        .collect();
+    hash_positions(&positions)
+}
+
+#[test]
+fn drop_on_sphere_is_deterministic() {
+    assert_eq!(drop_on_sphere(), 5777228830029487785);
+}
+
+#[test]
+fn hang_from_mesh_is_deterministic() {
+    assert_eq!(hang_from_mesh(), 1090943423700406146);
+}
+
+#[test]
+fn stacked_in_world_is_deterministic() {
+    assert_eq!(stacked_in_world(), 18363976226871566423);
+}
@@ -0,0 +1,171 @@
+//! Refining and coarsening a cloth must keep its constraints on its triangles.
+
+use std::collections::HashSet;
+
+use fast_mass_spring::{
+    cloth::{Attachment, Cloth, ClothBuilder, ClothFromMeshBuilder},
+    refine::RefinementCriteria,
+    solver::FastMassSpringSolver,
+};
+use simulation::{math::Isometry3, IcosphereBuilder, SphereCollider, TransformedCollider};
+
+const TIME_STEP: f32 = 1.0 / 60.0;
+
+/// A closed sphere with area constraints on every triangle and a volume constraint.
+fn balloon() -> Cloth {
+    let mesh = IcosphereBuilder::new(0.5, 1).build();
+    let mut cloth = ClothFromMeshBuilder {
+        mesh: &mesh,
+        mass: 1.0,
+        spring_stiffness: 60.0,
+        bend_stiffness: 1.0,
+        stiffness_multipliers: None,
+    }
+    .build();
+    cloth.add_area_constraints(20.0);
+    cloth.add_volume_constraint(10.0);
+    cloth
+}
+
+fn sorted(mut triangle: [usize; 3]) -> [usize; 3] {
+    triangle.sort_unstable();
+    triangle
+}
+
+fn total_rest_area(cloth: &Cloth) -> f32 {
+    cloth
+        .area_constraints
+        .iter()
+        .map(|area| area.rest_area())
+        .sum()
+}
+
+/// Every area constraint is on a triangle and the volume constraint covers the whole surface.
+fn assert_constraints_match_triangles(cloth: &Cloth) {
+    let triangles: HashSet<[usize; 3]> = cloth.triangles().iter().copied().map(sorted).collect();
+    assert_eq!(cloth.area_constraints.len(), cloth.triangles().len());
+    for area in &cloth.area_constraints {
+        assert!(triangles.contains(&sorted(area.particle_indices)));
+    }
+    let volume = &cloth.volume_constraints[0];
+    assert!(volume.is_closed());
+    assert_eq!(volume.particle_indices().len(), cloth.num_particles());
+    let volume_triangles: HashSet<[usize; 3]> = volume.triangles().map(sorted).collect();
+    assert_eq!(volume_triangles, triangles);
+}
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() <= 1e-4 * expected.abs(),
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn refine_splits_area_and_volume_constraints() {
+    let mut cloth = balloon();
+    let num_particles = cloth.num_particles();
+    let rest_area = total_rest_area(&cloth);
+    let target_volume = cloth.volume_constraints[0].target_volume;
+    let criteria = RefinementCriteria {
+        max_dihedral_angle: 0.1,
+        min_edge_length: 0.0,
+        ..Default::default()
+    };
+
+    let num_split = cloth.refine(&criteria, &[]);
+    assert!(num_split > 0);
+    assert_eq!(cloth.num_particles(), num_particles + num_split);
+    assert_constraints_match_triangles(&cloth);
+    // the midpoints are on the old triangles, so neither the surface nor its volume change
+    assert_close(total_rest_area(&cloth), rest_area);
+    assert_close(
+        cloth.volume_constraints[0].volume(&cloth.particle_positions),
+        target_volume,
+    );
+    FastMassSpringSolver::new(cloth, TIME_STEP).expect("valid constraints after refining");
+}
+
+#[test]
+fn coarsen_undoes_refine() {
+    let mut cloth = balloon();
+    let num_particles = cloth.num_particles();
+    let num_springs = cloth.springs.len();
+    let rest_area = total_rest_area(&cloth);
+    let criteria = RefinementCriteria {
+        max_dihedral_angle: 0.1,
+        min_edge_length: 0.0,
+        coarsen_dihedral_angle: std::f32::consts::PI,
+        ..Default::default()
+    };
+    cloth.refine(&criteria, &[]);
+    cloth.refine(&criteria, &[]);
+
+    while cloth.coarsen(&criteria, &[]) > 0 {}
+    assert!(cloth.refined_particles().is_empty());
+    assert_eq!(cloth.num_particles(), num_particles);
+    assert_eq!(cloth.springs.len(), num_springs);
+    assert_constraints_match_triangles(&cloth);
+    assert_close(total_rest_area(&cloth), rest_area);
+    FastMassSpringSolver::new(cloth, TIME_STEP).expect("valid constraints after coarsening");
+}
+
+#[test]
+fn refined_particles_get_long_range_attachments() {
+    let builder = ClothBuilder {
+        width: 2.0,
+        height: 2.0,
+        width_segments: 8,
+        height_segments: 8,
+        structural_spring_stiffness: 80.0,
+        shear_spring_stiffness: 2.0,
+        mass: 1.0,
+        transform: Isometry3::identity(),
+        stiffness_map: None,
+    };
+    let corners = [
+        builder.top_left_vertex_index(),
+        builder.top_right_vertex_index(),
+    ];
+    let mut cloth = builder.build();
+    let attachments: Vec<_> = corners
+        .into_iter()
+        .map(|particle_index| Attachment {
+            particle_index,
+            target_position: cloth.get_particle_position(particle_index),
+            stiffness: 100.0,
+        })
+        .collect();
+    cloth.add_attachments(attachments);
+    cloth.add_long_range_attachments(50.0);
+    let center = cloth.compute_aabb().center();
+    let colliders = [TransformedCollider::new(
+        SphereCollider::new(0.2).into(),
+        Isometry3::translation(center.x, center.y, center.z + 0.3),
+    )];
+    let criteria = RefinementCriteria {
+        max_dihedral_angle: std::f32::consts::PI,
+        contact_distance: 0.3,
+        ..Default::default()
+    };
+
+    let num_split = cloth.refine(&criteria, &colliders);
+    assert!(num_split > 0);
+    let limited: HashSet<usize> = cloth
+        .long_range_attachments
+        .iter()
+        .map(|lra| lra.particle_index)
+        .collect();
+    for refined in cloth.refined_particles() {
+        assert!(limited.contains(&refined.particle_index));
+    }
+    // the cloth is at rest, so the geodesic bounds hold
+    for lra in &cloth.long_range_attachments {
+        let distance = (cloth.get_particle_position(lra.particle_index)
+            - cloth.get_particle_position(lra.anchor_index))
+        .magnitude();
+        assert!(distance <= lra.max_distance + 1e-5);
+    }
+}
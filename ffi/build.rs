@@ -0,0 +1,18 @@
+use std::{env, path::PathBuf};
+
+/// Generates the header into `OUT_DIR` so that builds never touch the source tree; the
+/// committed `include/physics_rs.h` is refreshed with `generate_header.sh`.
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+        .expect("failed to read cbindgen.toml");
+    cbindgen::Builder::new()
+        .with_src(crate_dir.join("src/lib.rs"))
+        .with_config(config)
+        .generate()
+        .expect("failed to generate the C header")
+        .write_to_file(out_dir.join("physics_rs.h"));
+}
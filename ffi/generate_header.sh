@@ -0,0 +1,24 @@
+#!/bin/sh
+# Regenerate include/physics_rs.h from src/lib.rs, or with --check fail if it is out of date.
+# Needs the cbindgen CLI: cargo install cbindgen
+set -eu
+cd "$(dirname "$0")"
+
+case "${1:-}" in
+"")
+    cbindgen --config cbindgen.toml --output include/physics_rs.h src/lib.rs
+    ;;
+--check)
+    generated=$(mktemp)
+    trap 'rm -f "$generated"' EXIT
+    cbindgen --config cbindgen.toml --output "$generated" src/lib.rs
+    if ! diff -u include/physics_rs.h "$generated"; then
+        echo "include/physics_rs.h is out of date, run ffi/generate_header.sh" >&2
+        exit 1
+    fi
+    ;;
+*)
+    echo "usage: $0 [--check]" >&2
+    exit 2
+    ;;
+esac
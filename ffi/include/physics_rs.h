@@ -0,0 +1,243 @@
+#ifndef PHYSICS_RS_H
+#define PHYSICS_RS_H
+
+/* Generated by cbindgen from ffi/src/lib.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum PhysicsStatus {
+  PHYSICS_STATUS_OK,
+  PHYSICS_STATUS_NULL_POINTER,
+  PHYSICS_STATUS_INVALID_ARGUMENT,
+  /**
+   * The solver could not be built from the cloth, e.g. because of invalid masses.
+   */
+  PHYSICS_STATUS_BUILD_FAILED,
+  PHYSICS_STATUS_INDEX_OUT_OF_RANGE,
+  /**
+   * The solver panicked. It must not be used again, except to destroy it.
+   */
+  PHYSICS_STATUS_PANIC,
+} PhysicsStatus;
+
+/**
+ * A solver and the cloth it simulates.
+ */
+typedef struct PhysicsSolver PhysicsSolver;
+
+typedef struct PhysicsVec3 {
+  float x;
+  float y;
+  float z;
+} PhysicsVec3;
+
+/**
+ * A square grid cloth in the xy plane.
+ */
+typedef struct PhysicsGridClothDesc {
+  float size;
+  /**
+   * Number of particles along each side, at least 2.
+   */
+  uintptr_t resolution;
+  float structural_spring_stiffness;
+  float shear_spring_stiffness;
+  float mass;
+  struct PhysicsVec3 center;
+} PhysicsGridClothDesc;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Build a grid cloth and its solver, written to `out_solver`.
+ *
+ * # Safety
+ *
+ * `desc` must point to a valid description and `out_solver` to writable memory.
+ */
+enum PhysicsStatus physics_solver_create_grid(const struct PhysicsGridClothDesc *desc,
+                                              float time_step,
+                                              struct PhysicsSolver **out_solver);
+
+/**
+ * Build a cloth from a triangle mesh, with a spring on every edge, and its solver.
+ *
+ * # Safety
+ *
+ * `vertices` must point to `3 * num_vertices` floats, `indices` to `num_indices` indices and
+ * `out_solver` to writable memory.
+ */
+enum PhysicsStatus physics_solver_create_from_mesh(const float *vertices,
+                                                   uintptr_t num_vertices,
+                                                   const uint32_t *indices,
+                                                   uintptr_t num_indices,
+                                                   float mass,
+                                                   float spring_stiffness,
+                                                   float time_step,
+                                                   struct PhysicsSolver **out_solver);
+
+/**
+ * Destroy a solver. Null is ignored.
+ *
+ * # Safety
+ *
+ * `solver` must be null or a live solver, which must not be used afterwards.
+ */
+void physics_solver_destroy(struct PhysicsSolver *solver);
+
+/**
+ * Advance the simulation by `num_steps` time steps.
+ *
+ * # Safety
+ *
+ * `solver` must be a live solver.
+ */
+enum PhysicsStatus physics_solver_step(struct PhysicsSolver *solver, uint32_t num_steps);
+
+/**
+ * The number of particles, zero for a null solver.
+ *
+ * # Safety
+ *
+ * `solver` must be null or a live solver.
+ */
+uintptr_t physics_solver_num_particles(const struct PhysicsSolver *solver);
+
+/**
+ * The particle positions, packed as `3 * num_particles` floats. The pointer is only valid
+ * until the next call mutating the solver: the solver swaps its position buffers every step.
+ *
+ * # Safety
+ *
+ * `solver` must be null or a live solver.
+ */
+const float *physics_solver_positions(const struct PhysicsSolver *solver);
+
+/**
+ * Copy the particle positions into `out_positions`, which holds `len` floats, at least
+ * `3 * num_particles`.
+ *
+ * # Safety
+ *
+ * `solver` must be a live solver and `out_positions` must point to `len` writable floats.
+ */
+enum PhysicsStatus physics_solver_copy_positions(const struct PhysicsSolver *solver,
+                                                 float *out_positions,
+                                                 uintptr_t len);
+
+/**
+ * # Safety
+ *
+ * `solver` must be a live solver.
+ */
+enum PhysicsStatus physics_solver_set_gravity(struct PhysicsSolver *solver,
+                                              struct PhysicsVec3 gravity);
+
+/**
+ * # Safety
+ *
+ * `solver` must be a live solver.
+ */
+enum PhysicsStatus physics_solver_set_damping(struct PhysicsSolver *solver, float damping);
+
+/**
+ * # Safety
+ *
+ * `solver` must be a live solver.
+ */
+enum PhysicsStatus physics_solver_set_num_iterations(struct PhysicsSolver *solver,
+                                                     uint32_t num_iterations);
+
+/**
+ * # Safety
+ *
+ * `solver` must be a live solver.
+ */
+enum PhysicsStatus physics_solver_set_substeps(struct PhysicsSolver *solver, uint32_t substeps);
+
+/**
+ * Add a sphere collider and write its index to `out_index`, which may be null. An inverted
+ * sphere keeps the cloth inside.
+ *
+ * # Safety
+ *
+ * `solver` must be a live solver and `out_index` null or writable.
+ */
+enum PhysicsStatus physics_solver_add_sphere_collider(struct PhysicsSolver *solver,
+                                                      struct PhysicsVec3 center,
+                                                      float radius,
+                                                      bool inverted,
+                                                      uint32_t *out_index);
+
+/**
+ * Add a triangle mesh collider at `position` and write its index to `out_index`, which may
+ * be null.
+ *
+ * # Safety
+ *
+ * `solver` must be a live solver, `vertices` must point to `3 * num_vertices` floats,
+ * `indices` to `num_indices` indices and `out_index` must be null or writable.
+ */
+enum PhysicsStatus physics_solver_add_mesh_collider(struct PhysicsSolver *solver,
+                                                    const float *vertices,
+                                                    uintptr_t num_vertices,
+                                                    const uint32_t *indices,
+                                                    uintptr_t num_indices,
+                                                    struct PhysicsVec3 position,
+                                                    float thickness,
+                                                    uint32_t *out_index);
+
+/**
+ * Move a collider, see `FastMassSpringSolver::set_collider_transform`.
+ *
+ * # Safety
+ *
+ * `solver` must be a live solver.
+ */
+enum PhysicsStatus physics_solver_set_collider_position(struct PhysicsSolver *solver,
+                                                        uint32_t index,
+                                                        struct PhysicsVec3 position);
+
+/**
+ * Attach a particle to `target` and write the attachment index to `out_index`, which may be
+ * null.
+ *
+ * # Safety
+ *
+ * `solver` must be a live solver and `out_index` null or writable.
+ */
+enum PhysicsStatus physics_solver_add_attachment(struct PhysicsSolver *solver,
+                                                 uint32_t particle_index,
+                                                 struct PhysicsVec3 target,
+                                                 float stiffness,
+                                                 uint32_t *out_index);
+
+/**
+ * # Safety
+ *
+ * `solver` must be a live solver.
+ */
+enum PhysicsStatus physics_solver_set_attachment_target(struct PhysicsSolver *solver,
+                                                        uint32_t index,
+                                                        struct PhysicsVec3 target);
+
+/**
+ * Remove an attachment. The attachments after it are shifted down.
+ *
+ * # Safety
+ *
+ * `solver` must be a live solver.
+ */
+enum PhysicsStatus physics_solver_remove_attachment(struct PhysicsSolver *solver, uint32_t index);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PHYSICS_RS_H */
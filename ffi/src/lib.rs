@@ -0,0 +1,528 @@
+//! C ABI of the cloth solver, for embedding it in engines. The header is committed at
+//! `include/physics_rs.h`; run `generate_header.sh` after changing the API.
+//!
+//! Every function taking a solver pointer expects a pointer returned by one of the create
+//! functions and not yet destroyed. Panics never unwind into the caller: a function returning
+//! a status returns `Panic` instead, the others their null value.
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use fast_mass_spring::{
+    cloth::{Attachment, ClothBuilder, ClothFromMeshBuilder},
+    solver::{ColliderHandle, FastMassSpringSolver},
+};
+use nalgebra::{Isometry3, Vector3};
+use simulation::{Collider, Mesh, SphereCollider, TriMeshCollider};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhysicsStatus {
+    Ok,
+    NullPointer,
+    InvalidArgument,
+    /// The solver could not be built from the cloth, e.g. because of invalid masses.
+    BuildFailed,
+    IndexOutOfRange,
+    /// The solver panicked. It must not be used again, except to destroy it.
+    Panic,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsVec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl From<PhysicsVec3> for Vector3<f32> {
+    fn from(v: PhysicsVec3) -> Self {
+        Vector3::new(v.x, v.y, v.z)
+    }
+}
+
+/// A square grid cloth in the xy plane.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PhysicsGridClothDesc {
+    pub size: f32,
+    /// Number of particles along each side, at least 2.
+    pub resolution: usize,
+    pub structural_spring_stiffness: f32,
+    pub shear_spring_stiffness: f32,
+    pub mass: f32,
+    pub center: PhysicsVec3,
+}
+
+/// A solver and the cloth it simulates.
+pub struct PhysicsSolver {
+    solver: FastMassSpringSolver,
+    colliders: Vec<ColliderHandle>,
+}
+
+impl PhysicsSolver {
+    fn add_collider(&mut self, collider: Collider, position: PhysicsVec3) -> u32 {
+        let handle = self.solver.add_collider(collider, translation(position));
+        self.colliders.push(handle);
+        (self.colliders.len() - 1) as u32
+    }
+}
+
+/// Run the body of an entry point, catching a panic since unwinding across `extern "C"` is
+/// undefined behavior.
+fn guard(f: impl FnOnce() -> PhysicsStatus) -> PhysicsStatus {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(PhysicsStatus::Panic)
+}
+
+fn translation(v: PhysicsVec3) -> Isometry3<f32> {
+    Isometry3::translation(v.x, v.y, v.z)
+}
+
+/// Build a grid cloth and its solver, written to `out_solver`.
+///
+/// # Safety
+///
+/// `desc` must point to a valid description and `out_solver` to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_create_grid(
+    desc: *const PhysicsGridClothDesc,
+    time_step: f32,
+    out_solver: *mut *mut PhysicsSolver,
+) -> PhysicsStatus {
+    guard(|| {
+        if desc.is_null() || out_solver.is_null() {
+            return PhysicsStatus::NullPointer;
+        }
+        let desc = &*desc;
+        if desc.resolution < 2 {
+            return PhysicsStatus::InvalidArgument;
+        }
+        let cloth = ClothBuilder {
+            width: desc.size,
+            height: desc.size,
+            width_segments: desc.resolution - 1,
+            height_segments: desc.resolution - 1,
+            structural_spring_stiffness: desc.structural_spring_stiffness,
+            shear_spring_stiffness: desc.shear_spring_stiffness,
+            mass: desc.mass,
+            transform: translation(desc.center),
+            stiffness_map: None,
+        }
+        .build();
+        match FastMassSpringSolver::new(cloth, time_step) {
+            Ok(solver) => {
+                *out_solver = Box::into_raw(Box::new(PhysicsSolver {
+                    solver,
+                    colliders: vec![],
+                }));
+                PhysicsStatus::Ok
+            }
+            Err(_) => PhysicsStatus::BuildFailed,
+        }
+    })
+}
+
+/// Build a cloth from a triangle mesh, with a spring on every edge, and its solver.
+///
+/// # Safety
+///
+/// `vertices` must point to `3 * num_vertices` floats, `indices` to `num_indices` indices and
+/// `out_solver` to writable memory.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn physics_solver_create_from_mesh(
+    vertices: *const f32,
+    num_vertices: usize,
+    indices: *const u32,
+    num_indices: usize,
+    mass: f32,
+    spring_stiffness: f32,
+    time_step: f32,
+    out_solver: *mut *mut PhysicsSolver,
+) -> PhysicsStatus {
+    guard(|| {
+        if out_solver.is_null() {
+            return PhysicsStatus::NullPointer;
+        }
+        let Some(mesh) = read_mesh(vertices, num_vertices, indices, num_indices) else {
+            return PhysicsStatus::InvalidArgument;
+        };
+        let cloth = ClothFromMeshBuilder {
+            mesh: &mesh,
+            mass,
+            spring_stiffness,
+            bend_stiffness: 0.0,
+            stiffness_multipliers: None,
+        }
+        .build();
+        match FastMassSpringSolver::new(cloth, time_step) {
+            Ok(solver) => {
+                *out_solver = Box::into_raw(Box::new(PhysicsSolver {
+                    solver,
+                    colliders: vec![],
+                }));
+                PhysicsStatus::Ok
+            }
+            Err(_) => PhysicsStatus::BuildFailed,
+        }
+    })
+}
+
+/// Destroy a solver. Null is ignored.
+///
+/// # Safety
+///
+/// `solver` must be null or a live solver, which must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_destroy(solver: *mut PhysicsSolver) {
+    if !solver.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(solver))));
+    }
+}
+
+/// Advance the simulation by `num_steps` time steps.
+///
+/// # Safety
+///
+/// `solver` must be a live solver.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_step(
+    solver: *mut PhysicsSolver,
+    num_steps: u32,
+) -> PhysicsStatus {
+    guard(|| {
+        let Some(solver) = solver.as_mut() else {
+            return PhysicsStatus::NullPointer;
+        };
+        for _ in 0..num_steps {
+            solver.solver.step();
+        }
+        PhysicsStatus::Ok
+    })
+}
+
+/// The number of particles, zero for a null solver.
+///
+/// # Safety
+///
+/// `solver` must be null or a live solver.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_num_particles(solver: *const PhysicsSolver) -> usize {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        solver
+            .as_ref()
+            .map_or(0, |solver| solver.solver.cloth().num_particles())
+    }))
+    .unwrap_or(0)
+}
+
+/// The particle positions, packed as `3 * num_particles` floats. The pointer is only valid
+/// until the next call mutating the solver: the solver swaps its position buffers every step.
+///
+/// # Safety
+///
+/// `solver` must be null or a live solver.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_positions(solver: *const PhysicsSolver) -> *const f32 {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        solver.as_ref().map_or(ptr::null(), |solver| {
+            solver.solver.cloth().particle_positions.as_ptr()
+        })
+    }))
+    .unwrap_or(ptr::null())
+}
+
+/// Copy the particle positions into `out_positions`, which holds `len` floats, at least
+/// `3 * num_particles`.
+///
+/// # Safety
+///
+/// `solver` must be a live solver and `out_positions` must point to `len` writable floats.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_copy_positions(
+    solver: *const PhysicsSolver,
+    out_positions: *mut f32,
+    len: usize,
+) -> PhysicsStatus {
+    guard(|| {
+        let Some(solver) = solver.as_ref() else {
+            return PhysicsStatus::NullPointer;
+        };
+        if out_positions.is_null() {
+            return PhysicsStatus::NullPointer;
+        }
+        let positions = solver.solver.cloth().particle_positions.as_slice();
+        if len < positions.len() {
+            return PhysicsStatus::InvalidArgument;
+        }
+        slice::from_raw_parts_mut(out_positions, positions.len()).copy_from_slice(positions);
+        PhysicsStatus::Ok
+    })
+}
+
+/// # Safety
+///
+/// `solver` must be a live solver.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_set_gravity(
+    solver: *mut PhysicsSolver,
+    gravity: PhysicsVec3,
+) -> PhysicsStatus {
+    guard(|| {
+        let Some(solver) = solver.as_mut() else {
+            return PhysicsStatus::NullPointer;
+        };
+        solver.solver.set_gravity(Vector3::from(gravity));
+        PhysicsStatus::Ok
+    })
+}
+
+/// # Safety
+///
+/// `solver` must be a live solver.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_set_damping(
+    solver: *mut PhysicsSolver,
+    damping: f32,
+) -> PhysicsStatus {
+    guard(|| {
+        let Some(solver) = solver.as_mut() else {
+            return PhysicsStatus::NullPointer;
+        };
+        solver.solver.set_damping(damping);
+        PhysicsStatus::Ok
+    })
+}
+
+/// # Safety
+///
+/// `solver` must be a live solver.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_set_num_iterations(
+    solver: *mut PhysicsSolver,
+    num_iterations: u32,
+) -> PhysicsStatus {
+    guard(|| {
+        let Some(solver) = solver.as_mut() else {
+            return PhysicsStatus::NullPointer;
+        };
+        solver.solver.set_num_iterations(num_iterations as usize);
+        PhysicsStatus::Ok
+    })
+}
+
+/// # Safety
+///
+/// `solver` must be a live solver.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_set_substeps(
+    solver: *mut PhysicsSolver,
+    substeps: u32,
+) -> PhysicsStatus {
+    guard(|| {
+        let Some(solver) = solver.as_mut() else {
+            return PhysicsStatus::NullPointer;
+        };
+        if substeps == 0 {
+            return PhysicsStatus::InvalidArgument;
+        }
+        solver.solver.set_substeps(substeps as usize);
+        PhysicsStatus::Ok
+    })
+}
+
+/// Add a sphere collider and write its index to `out_index`, which may be null. An inverted
+/// sphere keeps the cloth inside.
+///
+/// # Safety
+///
+/// `solver` must be a live solver and `out_index` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_add_sphere_collider(
+    solver: *mut PhysicsSolver,
+    center: PhysicsVec3,
+    radius: f32,
+    inverted: bool,
+    out_index: *mut u32,
+) -> PhysicsStatus {
+    guard(|| {
+        let Some(solver) = solver.as_mut() else {
+            return PhysicsStatus::NullPointer;
+        };
+        if !(radius >= 0.0 && radius.is_finite()) {
+            return PhysicsStatus::InvalidArgument;
+        }
+        let index = solver.add_collider(SphereCollider { radius, inverted }.into(), center);
+        write_index(out_index, index);
+        PhysicsStatus::Ok
+    })
+}
+
+/// Add a triangle mesh collider at `position` and write its index to `out_index`, which may
+/// be null.
+///
+/// # Safety
+///
+/// `solver` must be a live solver, `vertices` must point to `3 * num_vertices` floats,
+/// `indices` to `num_indices` indices and `out_index` must be null or writable.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn physics_solver_add_mesh_collider(
+    solver: *mut PhysicsSolver,
+    vertices: *const f32,
+    num_vertices: usize,
+    indices: *const u32,
+    num_indices: usize,
+    position: PhysicsVec3,
+    thickness: f32,
+    out_index: *mut u32,
+) -> PhysicsStatus {
+    guard(|| {
+        let Some(solver) = solver.as_mut() else {
+            return PhysicsStatus::NullPointer;
+        };
+        let Some(mesh) = read_mesh(vertices, num_vertices, indices, num_indices) else {
+            return PhysicsStatus::InvalidArgument;
+        };
+        let index = solver.add_collider(TriMeshCollider::new(&mesh, thickness).into(), position);
+        write_index(out_index, index);
+        PhysicsStatus::Ok
+    })
+}
+
+/// Move a collider, see `FastMassSpringSolver::set_collider_transform`.
+///
+/// # Safety
+///
+/// `solver` must be a live solver.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_set_collider_position(
+    solver: *mut PhysicsSolver,
+    index: u32,
+    position: PhysicsVec3,
+) -> PhysicsStatus {
+    guard(|| {
+        let Some(solver) = solver.as_mut() else {
+            return PhysicsStatus::NullPointer;
+        };
+        let Some(&handle) = solver.colliders.get(index as usize) else {
+            return PhysicsStatus::IndexOutOfRange;
+        };
+        solver
+            .solver
+            .set_collider_transform(handle, translation(position));
+        PhysicsStatus::Ok
+    })
+}
+
+/// Attach a particle to `target` and write the attachment index to `out_index`, which may be
+/// null.
+///
+/// # Safety
+///
+/// `solver` must be a live solver and `out_index` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_add_attachment(
+    solver: *mut PhysicsSolver,
+    particle_index: u32,
+    target: PhysicsVec3,
+    stiffness: f32,
+    out_index: *mut u32,
+) -> PhysicsStatus {
+    guard(|| {
+        let Some(solver) = solver.as_mut() else {
+            return PhysicsStatus::NullPointer;
+        };
+        let particle_index = particle_index as usize;
+        if particle_index >= solver.solver.cloth().num_particles() {
+            return PhysicsStatus::IndexOutOfRange;
+        }
+        if !(stiffness >= 0.0 && stiffness.is_finite()) {
+            return PhysicsStatus::InvalidArgument;
+        }
+        let Ok(index) = solver.solver.add_attachment(Attachment {
+            particle_index,
+            target_position: Vector3::from(target),
+            stiffness,
+        }) else {
+            return PhysicsStatus::InvalidArgument;
+        };
+        write_index(out_index, index as u32);
+        PhysicsStatus::Ok
+    })
+}
+
+/// # Safety
+///
+/// `solver` must be a live solver.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_set_attachment_target(
+    solver: *mut PhysicsSolver,
+    index: u32,
+    target: PhysicsVec3,
+) -> PhysicsStatus {
+    guard(|| {
+        let Some(solver) = solver.as_mut() else {
+            return PhysicsStatus::NullPointer;
+        };
+        if index as usize >= solver.solver.cloth().attachments.len() {
+            return PhysicsStatus::IndexOutOfRange;
+        }
+        solver
+            .solver
+            .set_attachment_target(index as usize, Vector3::from(target));
+        PhysicsStatus::Ok
+    })
+}
+
+/// Remove an attachment. The attachments after it are shifted down.
+///
+/// # Safety
+///
+/// `solver` must be a live solver.
+#[no_mangle]
+pub unsafe extern "C" fn physics_solver_remove_attachment(
+    solver: *mut PhysicsSolver,
+    index: u32,
+) -> PhysicsStatus {
+    guard(|| {
+        let Some(solver) = solver.as_mut() else {
+            return PhysicsStatus::NullPointer;
+        };
+        if index as usize >= solver.solver.cloth().attachments.len() {
+            return PhysicsStatus::IndexOutOfRange;
+        }
+        solver.solver.remove_attachment(index as usize);
+        PhysicsStatus::Ok
+    })
+}
+
+unsafe fn write_index(out_index: *mut u32, index: u32) {
+    if !out_index.is_null() {
+        *out_index = index;
+    }
+}
+
+/// Copy a triangle mesh, `None` if a pointer is null or an index out of range.
+unsafe fn read_mesh(
+    vertices: *const f32,
+    num_vertices: usize,
+    indices: *const u32,
+    num_indices: usize,
+) -> Option<Mesh> {
+    if vertices.is_null() || indices.is_null() || !num_indices.is_multiple_of(3) {
+        return None;
+    }
+    let vertices: Vec<Vector3<f32>> = slice::from_raw_parts(vertices, num_vertices * 3)
+        .chunks_exact(3)
+        .map(Vector3::from_column_slice)
+        .collect();
+    let indices = slice::from_raw_parts(indices, num_indices).to_vec();
+    if indices.iter().any(|&i| i as usize >= vertices.len()) {
+        return None;
+    }
+    Some(Mesh::new(vertices, indices))
+}
@@ -0,0 +1,14 @@
+"""Hang a cloth by its top row and compare the sag for several spring stiffnesses."""
+
+import physics_rs
+
+for stiffness in [5.0, 10.0, 20.0, 40.0]:
+    builder = physics_rs.ClothBuilder(resolution=16, structural_spring_stiffness=stiffness)
+    cloth = builder.build()
+    cloth.attach_particles(builder.top_row_indices(), 100.0)
+    solver = physics_rs.Solver(cloth, 1.0 / 60.0)
+    solver.set_gravity((0.0, -9.8, 0.0))
+    solver.set_damping(0.98)
+    solver.step(300)
+    positions = solver.positions()
+    print(f"stiffness {stiffness:5.1f}: lowest point y = {positions[:, 1].min():.3f}")
@@ -0,0 +1,588 @@
+//! Python bindings of the cloth solver, built as the `physics_rs` module.
+//!
+//! Particle positions and velocities are exchanged as `(num_particles, 3)` float32 numpy
+//! arrays. They are copies: the solver swaps its position buffers every iteration, so no
+//! stable view into them can be handed out.
+
+use fast_mass_spring::{
+    cloth::{self, Attachment},
+    solver::{self, ColliderHandle},
+};
+use nalgebra::{Isometry3, Vector3};
+use numpy::{PyArray1, PyArray2, PyReadonlyArray2};
+use pyo3::{
+    exceptions::{PyIndexError, PyRuntimeError, PyValueError},
+    prelude::*,
+};
+use simulation::{Mesh, SphereCollider, TriMeshCollider};
+
+/// Grid cloth builder, see `fast_mass_spring::cloth::ClothBuilder`. The grid lies in the xy
+/// plane, centered on `center`.
+///
+/// `size` and `resolution` set a square grid with `resolution` particles along each side,
+/// `width`, `height`, `width_segments` and `height_segments` override them per axis.
+#[pyclass]
+#[derive(Clone)]
+struct ClothBuilder {
+    #[pyo3(get, set)]
+    width: f32,
+    #[pyo3(get, set)]
+    height: f32,
+    /// Segments along the width, at least 1.
+    #[pyo3(get)]
+    width_segments: usize,
+    /// Segments along the height, at least 1.
+    #[pyo3(get)]
+    height_segments: usize,
+    #[pyo3(get, set)]
+    structural_spring_stiffness: f32,
+    #[pyo3(get, set)]
+    shear_spring_stiffness: f32,
+    #[pyo3(get, set)]
+    mass: f32,
+    #[pyo3(get, set)]
+    center: (f32, f32, f32),
+    /// Rows of stiffness multipliers over the grid, see
+    /// `fast_mass_spring::cloth::StiffnessMap::Grid`. Each row has the same length.
+    #[pyo3(get, set)]
+    stiffness_map: Option<Vec<Vec<f32>>>,
+}
+
+impl ClothBuilder {
+    fn to_builder(&self) -> cloth::ClothBuilder {
+        let (x, y, z) = self.center;
+        cloth::ClothBuilder {
+            width: self.width,
+            height: self.height,
+            width_segments: self.width_segments,
+            height_segments: self.height_segments,
+            structural_spring_stiffness: self.structural_spring_stiffness,
+            shear_spring_stiffness: self.shear_spring_stiffness,
+            mass: self.mass,
+            transform: Isometry3::translation(x, y, z),
+            stiffness_map: None,
+        }
+    }
+
+    fn to_stiffness_map(&self) -> PyResult<Option<cloth::StiffnessMap>> {
+        let Some(rows) = &self.stiffness_map else {
+            return Ok(None);
+        };
+        let width = rows.first().map_or(0, Vec::len);
+        if width == 0 || rows.iter().any(|row| row.len() != width) {
+            return Err(PyValueError::new_err(
+                "the stiffness map rows must be non empty and of the same length",
+            ));
+        }
+        Ok(Some(cloth::StiffnessMap::Grid {
+            width,
+            height: rows.len(),
+            values: rows.concat(),
+        }))
+    }
+}
+
+#[pymethods]
+impl ClothBuilder {
+    #[new]
+    #[pyo3(signature = (
+        size = 3.0,
+        resolution = 20,
+        structural_spring_stiffness = 10.0,
+        shear_spring_stiffness = 0.6,
+        mass = 1.0,
+        center = (0.0, 0.0, 0.0),
+        stiffness_map = None,
+        width = None,
+        height = None,
+        width_segments = None,
+        height_segments = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        size: f32,
+        resolution: usize,
+        structural_spring_stiffness: f32,
+        shear_spring_stiffness: f32,
+        mass: f32,
+        center: (f32, f32, f32),
+        stiffness_map: Option<Vec<Vec<f32>>>,
+        width: Option<f32>,
+        height: Option<f32>,
+        width_segments: Option<usize>,
+        height_segments: Option<usize>,
+    ) -> PyResult<Self> {
+        check_resolution(resolution)?;
+        let width_segments = width_segments.unwrap_or(resolution - 1);
+        let height_segments = height_segments.unwrap_or(resolution - 1);
+        check_segments(width_segments)?;
+        check_segments(height_segments)?;
+        Ok(Self {
+            width: width.unwrap_or(size),
+            height: height.unwrap_or(size),
+            width_segments,
+            height_segments,
+            structural_spring_stiffness,
+            shear_spring_stiffness,
+            mass,
+            center,
+            stiffness_map,
+        })
+    }
+
+    /// The side of a square grid, `None` if the width and height differ.
+    #[getter]
+    fn size(&self) -> Option<f32> {
+        (self.width == self.height).then_some(self.width)
+    }
+
+    /// Set both the width and the height.
+    #[setter]
+    fn set_size(&mut self, size: f32) {
+        self.width = size;
+        self.height = size;
+    }
+
+    /// Particles along each side of a square grid, `None` if the segments of the axes differ.
+    #[getter]
+    fn resolution(&self) -> Option<usize> {
+        (self.width_segments == self.height_segments).then_some(self.width_segments + 1)
+    }
+
+    /// Set the particles along both axes, at least 2.
+    #[setter]
+    fn set_resolution(&mut self, resolution: usize) -> PyResult<()> {
+        check_resolution(resolution)?;
+        self.width_segments = resolution - 1;
+        self.height_segments = resolution - 1;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_width_segments(&mut self, width_segments: usize) -> PyResult<()> {
+        check_segments(width_segments)?;
+        self.width_segments = width_segments;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_height_segments(&mut self, height_segments: usize) -> PyResult<()> {
+        check_segments(height_segments)?;
+        self.height_segments = height_segments;
+        Ok(())
+    }
+
+    fn build(&self) -> PyResult<Cloth> {
+        let builder = cloth::ClothBuilder {
+            stiffness_map: self.to_stiffness_map()?,
+            ..self.to_builder()
+        };
+        Ok(Cloth {
+            cloth: Some(builder.build()),
+        })
+    }
+
+    fn top_row_indices(&self) -> Vec<usize> {
+        self.to_builder().top_row_indices().collect()
+    }
+
+    fn bottom_row_indices(&self) -> Vec<usize> {
+        self.to_builder().bottom_row_indices().collect()
+    }
+
+    fn left_column_indices(&self) -> Vec<usize> {
+        self.to_builder().left_column_indices().collect()
+    }
+
+    fn right_column_indices(&self) -> Vec<usize> {
+        self.to_builder().right_column_indices().collect()
+    }
+
+    fn border_indices(&self) -> Vec<usize> {
+        self.to_builder().border_indices().collect()
+    }
+}
+
+/// A cloth being set up. It is moved into the solver built from it.
+#[pyclass(unsendable)]
+struct Cloth {
+    cloth: Option<cloth::Cloth>,
+}
+
+impl Cloth {
+    fn get(&self) -> PyResult<&cloth::Cloth> {
+        self.cloth.as_ref().ok_or_else(moved_error)
+    }
+
+    fn get_mut(&mut self) -> PyResult<&mut cloth::Cloth> {
+        self.cloth.as_mut().ok_or_else(moved_error)
+    }
+}
+
+fn moved_error() -> PyErr {
+    PyRuntimeError::new_err("the cloth was moved into a solver")
+}
+
+#[pymethods]
+impl Cloth {
+    /// Build a cloth from the triangles of a mesh, with a spring on every edge, and a bending
+    /// spring across every interior edge if `bend_stiffness` is positive. The stiffness of
+    /// each spring is scaled by the mean of the `stiffness_multipliers` of its vertices.
+    #[staticmethod]
+    #[pyo3(signature = (
+        vertices,
+        indices,
+        mass,
+        spring_stiffness,
+        bend_stiffness = 0.0,
+        stiffness_multipliers = None,
+    ))]
+    fn from_mesh(
+        vertices: PyReadonlyArray2<f32>,
+        indices: Vec<u32>,
+        mass: f32,
+        spring_stiffness: f32,
+        bend_stiffness: f32,
+        stiffness_multipliers: Option<Vec<f32>>,
+    ) -> PyResult<Self> {
+        let vertices = read_vectors(&vertices)?;
+        if indices.len() % 3 != 0 {
+            return Err(PyValueError::new_err(
+                "the number of indices must be a multiple of 3",
+            ));
+        }
+        if indices.iter().any(|&i| i as usize >= vertices.len()) {
+            return Err(PyIndexError::new_err("vertex index out of range"));
+        }
+        if let Some(multipliers) = &stiffness_multipliers {
+            if multipliers.len() != vertices.len() {
+                return Err(PyValueError::new_err(
+                    "one stiffness multiplier per vertex is needed",
+                ));
+            }
+        }
+        let mesh = Mesh::new(vertices, indices);
+        let cloth = cloth::ClothFromMeshBuilder {
+            mesh: &mesh,
+            mass,
+            spring_stiffness,
+            bend_stiffness,
+            stiffness_multipliers: stiffness_multipliers.as_deref(),
+        }
+        .build();
+        Ok(Self { cloth: Some(cloth) })
+    }
+
+    fn num_particles(&self) -> PyResult<usize> {
+        Ok(self.get()?.num_particles())
+    }
+
+    fn positions<'py>(&self, py: Python<'py>) -> PyResult<&'py PyArray2<f32>> {
+        vectors_to_array(py, self.get()?.particle_positions.as_slice())
+    }
+
+    /// Attach the particles at their current position.
+    fn attach_particles(&mut self, particle_indices: Vec<usize>, stiffness: f32) -> PyResult<()> {
+        let cloth = self.get_mut()?;
+        check_particle_indices(cloth, &particle_indices)?;
+        cloth.attach_particles(particle_indices, stiffness);
+        Ok(())
+    }
+
+    /// Attach the particles for which `predicate(x, y, z)` returns true. Returns the number of
+    /// attached particles.
+    fn attach_where(&mut self, predicate: &PyAny, stiffness: f32) -> PyResult<usize> {
+        let cloth = self.get_mut()?;
+        let mut particle_indices = vec![];
+        for i in 0..cloth.num_particles() {
+            let p = cloth.get_particle_position(i);
+            if predicate.call1((p.x, p.y, p.z))?.is_true()? {
+                particle_indices.push(i);
+            }
+        }
+        let count = particle_indices.len();
+        cloth.attach_particles(particle_indices, stiffness);
+        Ok(count)
+    }
+
+    fn add_long_range_attachments(&mut self, stiffness: f32) -> PyResult<usize> {
+        Ok(self.get_mut()?.add_long_range_attachments(stiffness))
+    }
+
+    /// Keep the volume enclosed by the triangles at its current value. The mesh should be
+    /// closed and face outwards. Returns the index of the constraint.
+    fn add_volume_constraint(&mut self, stiffness: f32) -> PyResult<usize> {
+        Ok(self.get_mut()?.add_volume_constraint(stiffness))
+    }
+
+    fn scale_rest_lengths(&mut self, factor: f32) -> PyResult<()> {
+        self.get_mut()?.scale_rest_lengths(factor);
+        Ok(())
+    }
+}
+
+#[pyclass(unsendable)]
+struct Solver {
+    solver: solver::FastMassSpringSolver,
+    colliders: Vec<ColliderHandle>,
+}
+
+impl Solver {
+    fn collider(&self, index: usize) -> PyResult<ColliderHandle> {
+        self.colliders
+            .get(index)
+            .copied()
+            .ok_or_else(|| PyIndexError::new_err("collider index out of range"))
+    }
+
+    fn add_collider(&mut self, collider: simulation::Collider, position: (f32, f32, f32)) -> usize {
+        let (x, y, z) = position;
+        let handle = self
+            .solver
+            .add_collider(collider, Isometry3::translation(x, y, z));
+        self.colliders.push(handle);
+        self.colliders.len() - 1
+    }
+}
+
+#[pymethods]
+impl Solver {
+    /// Build a solver, taking the cloth over.
+    #[new]
+    fn new(cloth: &mut Cloth, time_step: f32) -> PyResult<Self> {
+        let inner = cloth.cloth.take().ok_or_else(moved_error)?;
+        let solver = solver::FastMassSpringSolver::new(inner, time_step)
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Ok(Self {
+            solver,
+            colliders: vec![],
+        })
+    }
+
+    /// Advance the simulation by `num_steps` time steps.
+    #[pyo3(signature = (num_steps = 1))]
+    fn step(&mut self, num_steps: usize) {
+        for _ in 0..num_steps {
+            self.solver.step();
+        }
+    }
+
+    fn set_gravity(&mut self, gravity: (f32, f32, f32)) {
+        let (x, y, z) = gravity;
+        self.solver.set_gravity(Vector3::new(x, y, z));
+    }
+
+    fn set_damping(&mut self, damping: f32) {
+        self.solver.set_damping(damping);
+    }
+
+    fn set_friction(&mut self, friction: f32) {
+        self.solver.set_friction(friction);
+    }
+
+    fn set_num_iterations(&mut self, num_iterations: usize) {
+        self.solver.set_num_iterations(num_iterations);
+    }
+
+    fn set_substeps(&mut self, substeps: usize) -> PyResult<()> {
+        if substeps == 0 {
+            return Err(PyValueError::new_err("at least one substep is needed"));
+        }
+        self.solver.set_substeps(substeps);
+        Ok(())
+    }
+
+    fn set_target_volume(&mut self, index: usize, target_volume: f32) -> PyResult<()> {
+        check_volume_index(self.solver.cloth(), index)?;
+        self.solver.set_target_volume(index, target_volume);
+        Ok(())
+    }
+
+    /// The volume currently enclosed by the surface of a volume constraint.
+    fn volume(&self, index: usize) -> PyResult<f32> {
+        let cloth = self.solver.cloth();
+        check_volume_index(cloth, index)?;
+        Ok(cloth.volume_constraints[index].volume(&cloth.particle_positions))
+    }
+
+    #[getter]
+    fn time(&self) -> f32 {
+        self.solver.time()
+    }
+
+    #[getter]
+    fn time_step(&self) -> f32 {
+        self.solver.time_step()
+    }
+
+    fn num_particles(&self) -> usize {
+        self.solver.cloth().num_particles()
+    }
+
+    fn positions<'py>(&self, py: Python<'py>) -> PyResult<&'py PyArray2<f32>> {
+        vectors_to_array(py, self.solver.cloth().particle_positions.as_slice())
+    }
+
+    fn velocities<'py>(&self, py: Python<'py>) -> PyResult<&'py PyArray2<f32>> {
+        vectors_to_array(py, self.solver.particle_velocities().as_slice())
+    }
+
+    fn set_velocities(&mut self, velocities: PyReadonlyArray2<f32>) -> PyResult<()> {
+        let velocities = read_vectors(&velocities)?;
+        if velocities.len() != self.num_particles() {
+            return Err(PyValueError::new_err("expected one velocity per particle"));
+        }
+        let velocities = nalgebra::DVector::from_iterator(
+            velocities.len() * 3,
+            velocities.iter().flat_map(|v| [v.x, v.y, v.z]),
+        );
+        self.solver.set_particle_velocities(&velocities);
+        Ok(())
+    }
+
+    /// Per spring strain, `length / rest_length - 1`.
+    fn spring_strains<'py>(&self, py: Python<'py>) -> &'py PyArray1<f32> {
+        let cloth = self.solver.cloth();
+        let strains: Vec<f32> = cloth
+            .springs
+            .iter()
+            .map(|spring| cloth.spring_strain(spring))
+            .collect();
+        PyArray1::from_vec(py, strains)
+    }
+
+    /// Add a sphere collider and return its index. An inverted sphere keeps the cloth inside.
+    #[pyo3(signature = (center, radius, inverted = false))]
+    fn add_sphere_collider(
+        &mut self,
+        center: (f32, f32, f32),
+        radius: f32,
+        inverted: bool,
+    ) -> usize {
+        self.add_collider(SphereCollider { radius, inverted }.into(), center)
+    }
+
+    /// Add a triangle mesh collider at `position` and return its index.
+    #[pyo3(signature = (vertices, indices, position = (0.0, 0.0, 0.0), thickness = 0.01))]
+    fn add_mesh_collider(
+        &mut self,
+        vertices: PyReadonlyArray2<f32>,
+        indices: Vec<u32>,
+        position: (f32, f32, f32),
+        thickness: f32,
+    ) -> PyResult<usize> {
+        let vertices = read_vectors(&vertices)?;
+        if indices.len() % 3 != 0 || indices.iter().any(|&i| i as usize >= vertices.len()) {
+            return Err(PyValueError::new_err("invalid triangle indices"));
+        }
+        let mesh = Mesh::new(vertices, indices);
+        Ok(self.add_collider(TriMeshCollider::new(&mesh, thickness).into(), position))
+    }
+
+    fn set_collider_position(&mut self, index: usize, position: (f32, f32, f32)) -> PyResult<()> {
+        let handle = self.collider(index)?;
+        let (x, y, z) = position;
+        self.solver
+            .set_collider_transform(handle, Isometry3::translation(x, y, z));
+        Ok(())
+    }
+
+    /// Attach a particle to `target` and return the attachment index.
+    fn add_attachment(
+        &mut self,
+        particle_index: usize,
+        target: (f32, f32, f32),
+        stiffness: f32,
+    ) -> PyResult<usize> {
+        check_particle_indices(self.solver.cloth(), &[particle_index])?;
+        let (x, y, z) = target;
+        self.solver
+            .add_attachment(Attachment {
+                particle_index,
+                target_position: Vector3::new(x, y, z),
+                stiffness,
+            })
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    fn set_attachment_target(&mut self, index: usize, target: (f32, f32, f32)) -> PyResult<()> {
+        if index >= self.solver.cloth().attachments.len() {
+            return Err(PyIndexError::new_err("attachment index out of range"));
+        }
+        let (x, y, z) = target;
+        self.solver
+            .set_attachment_target(index, Vector3::new(x, y, z));
+        Ok(())
+    }
+
+    fn remove_attachment(&mut self, index: usize) -> PyResult<()> {
+        if index >= self.solver.cloth().attachments.len() {
+            return Err(PyIndexError::new_err("attachment index out of range"));
+        }
+        self.solver.remove_attachment(index);
+        Ok(())
+    }
+}
+
+fn check_resolution(resolution: usize) -> PyResult<()> {
+    if resolution < 2 {
+        return Err(PyValueError::new_err("resolution must be at least 2"));
+    }
+    Ok(())
+}
+
+fn check_segments(segments: usize) -> PyResult<()> {
+    if segments < 1 {
+        return Err(PyValueError::new_err(
+            "a grid needs at least 1 segment per axis",
+        ));
+    }
+    Ok(())
+}
+
+fn check_particle_indices(cloth: &cloth::Cloth, particle_indices: &[usize]) -> PyResult<()> {
+    let num_particles = cloth.num_particles();
+    match particle_indices.iter().find(|&&i| i >= num_particles) {
+        Some(i) => Err(PyIndexError::new_err(format!(
+            "particle index {} out of range",
+            i
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn check_volume_index(cloth: &cloth::Cloth, index: usize) -> PyResult<()> {
+    if index < cloth.volume_constraints.len() {
+        Ok(())
+    } else {
+        Err(PyIndexError::new_err(format!(
+            "volume constraint index {} out of range",
+            index
+        )))
+    }
+}
+
+/// Copy packed `[x0, y0, z0, x1, ...]` values into a `(n, 3)` array.
+fn vectors_to_array<'py>(py: Python<'py>, values: &[f32]) -> PyResult<&'py PyArray2<f32>> {
+    PyArray1::from_slice(py, values).reshape([values.len() / 3, 3])
+}
+
+fn read_vectors(array: &PyReadonlyArray2<f32>) -> PyResult<Vec<Vector3<f32>>> {
+    let array = array.as_array();
+    if array.ncols() != 3 {
+        return Err(PyValueError::new_err("expected an array of shape (n, 3)"));
+    }
+    Ok(array
+        .rows()
+        .into_iter()
+        .map(|row| Vector3::new(row[0], row[1], row[2]))
+        .collect())
+}
+
+#[pymodule]
+fn physics_rs(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<ClothBuilder>()?;
+    m.add_class::<Cloth>()?;
+    m.add_class::<Solver>()?;
+    Ok(())
+}
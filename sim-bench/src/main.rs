@@ -0,0 +1,136 @@
+use std::{path::PathBuf, process, time::Instant};
+
+use clap::Parser;
+use fast_mass_spring::solver::FastMassSpringSolver;
+use report::{Report, RunResult, StepTimes};
+use scenes::{Scene, SolverConfig, CONFIGS};
+mod report;
+mod scenes;
+
+/// Step standardized cloth scenes with several solver configurations and report the step
+/// time and convergence of each, so that solver changes are compared by numbers.
+#[derive(Parser)]
+struct Args {
+    /// Only run these scenes, all by default.
+    #[arg(long, value_enum)]
+    scene: Vec<Scene>,
+    /// Only run the configurations whose name contains this, e.g. "pd".
+    #[arg(long)]
+    config: Option<String>,
+    /// Steps of each run.
+    #[arg(long, default_value_t = 240)]
+    steps: usize,
+    /// Write the report as JSON, to be passed as `--baseline` to a later run.
+    #[arg(long, value_name = "PATH")]
+    json: Option<PathBuf>,
+    /// Compare with a report written by `--json`, and exit with an error on a regression.
+    #[arg(long, value_name = "PATH")]
+    baseline: Option<PathBuf>,
+    /// Largest growth of a mean step time over the baseline, in percent, that is not a
+    /// regression.
+    #[arg(long, default_value_t = 10.0)]
+    max_regression: f64,
+}
+
+fn main() {
+    let args = Args::parse();
+    let scenes = if args.scene.is_empty() {
+        Scene::ALL.to_vec()
+    } else {
+        args.scene.clone()
+    };
+    let configs: Vec<&SolverConfig> = CONFIGS
+        .iter()
+        .filter(|config| {
+            args.config
+                .as_ref()
+                .is_none_or(|name| config.name.contains(name.as_str()))
+        })
+        .collect();
+    if configs.is_empty() {
+        exit_with_error(format!(
+            "no configuration matches, expected one of: {}",
+            CONFIGS.map(|config| config.name).join(", ")
+        ));
+    }
+
+    let mut results = vec![];
+    for &scene in &scenes {
+        for &config in &configs {
+            eprintln!("running {} with {}", scene.name(), config.name);
+            results.push(run(scene, config, args.steps));
+        }
+    }
+    let report = Report {
+        parallel: cfg!(feature = "parallel"),
+        num_steps: args.steps,
+        results,
+    };
+    report.print();
+
+    if let Some(path) = &args.json {
+        if let Err(err) = report.save(path) {
+            exit_with_error(format!("{}: {}", path.display(), err));
+        }
+    }
+    if let Some(path) = &args.baseline {
+        let baseline = Report::load(path)
+            .unwrap_or_else(|err| exit_with_error(format!("{}: {}", path.display(), err)));
+        println!();
+        let regressions = report.compare(&baseline, args.max_regression);
+        if !regressions.is_empty() {
+            exit_with_error(format!("{} runs regressed", regressions.len()));
+        }
+    }
+}
+
+fn run(scene: Scene, config: &SolverConfig, num_steps: usize) -> RunResult {
+    let start = Instant::now();
+    let mut solver = build(scene, config);
+    let build_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    let mut step_times = Vec::with_capacity(num_steps);
+    for _ in 0..num_steps {
+        let start = Instant::now();
+        solver.step();
+        step_times.push(start.elapsed());
+    }
+    let cloth = solver.cloth();
+    RunResult {
+        scene: scene.name().to_string(),
+        config: config.name.to_string(),
+        num_particles: cloth.num_particles(),
+        build_ms,
+        step_ms: StepTimes::from_durations(&step_times),
+        mean_residual: mean_residual(scene, config, num_steps),
+        final_kinetic_energy: solver.kinetic_energy(),
+        stable: cloth.particle_positions.iter().all(|x| x.is_finite()),
+    }
+}
+
+/// The residual averaged over a second, untimed run of the same steps. The diagnostics cost
+/// time of their own, so they are off in the timed run; the solver is deterministic, so both
+/// runs take the same steps.
+fn mean_residual(scene: Scene, config: &SolverConfig, num_steps: usize) -> f32 {
+    let mut solver = build(scene, config);
+    solver.set_diagnostics(true);
+    let mut sum_residual = 0.0;
+    for _ in 0..num_steps {
+        solver.step();
+        sum_residual += solver
+            .diagnostics()
+            .map_or(0.0, |diagnostics| diagnostics.residual);
+    }
+    sum_residual / num_steps.max(1) as f32
+}
+
+fn build(scene: Scene, config: &SolverConfig) -> FastMassSpringSolver {
+    scene
+        .build(config)
+        .unwrap_or_else(|err| exit_with_error(format!("{}: {}", scene.name(), err)))
+}
+
+fn exit_with_error(message: String) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
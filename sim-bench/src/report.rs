@@ -0,0 +1,194 @@
+use std::{fmt, fs, io, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// The results of a whole run, written as JSON to compare with later runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Report {
+    /// Whether the local step ran on multiple threads.
+    pub parallel: bool,
+    pub num_steps: usize,
+    pub results: Vec<RunResult>,
+}
+
+/// The measures of one scene stepped with one solver configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RunResult {
+    pub scene: String,
+    pub config: String,
+    pub num_particles: usize,
+    /// Time to build the solver, including the factorization of the system.
+    pub build_ms: f64,
+    /// Times of the steps, with the diagnostics off.
+    pub step_ms: StepTimes,
+    /// [`StepDiagnostics::residual`](fast_mass_spring::solver::StepDiagnostics::residual)
+    /// averaged over the steps of a separate, untimed run. Lower means better converged.
+    pub mean_residual: f32,
+    pub final_kinetic_energy: f32,
+    /// False if a position became infinite or NaN, e.g. the explicit integrator blew up.
+    pub stable: bool,
+}
+
+/// Statistics over the times of the steps, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StepTimes {
+    pub mean: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl StepTimes {
+    pub fn from_durations(durations: &[Duration]) -> Self {
+        if durations.is_empty() {
+            return Self::default();
+        }
+        let mut millis: Vec<f64> = durations
+            .iter()
+            .map(|duration| duration.as_secs_f64() * 1000.0)
+            .collect();
+        millis.sort_by(f64::total_cmp);
+        let percentile = |p: f64| millis[((millis.len() - 1) as f64 * p).round() as usize];
+        Self {
+            mean: millis.iter().sum::<f64>() / millis.len() as f64,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            max: millis[millis.len() - 1],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReportError {
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportError::Io(err) => write!(f, "{}", err),
+            ReportError::Json(err) => write!(f, "invalid report: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ReportError {}
+
+impl From<io::Error> for ReportError {
+    fn from(err: io::Error) -> Self {
+        ReportError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ReportError {
+    fn from(err: serde_json::Error) -> Self {
+        ReportError::Json(err)
+    }
+}
+
+impl Report {
+    pub fn load(path: &Path) -> Result<Self, ReportError> {
+        let text = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReportError> {
+        let text = serde_json::to_string_pretty(self)?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Print a table with a row per run.
+    pub fn print(&self) {
+        println!(
+            "{} steps, local step {}",
+            self.num_steps,
+            if self.parallel { "parallel" } else { "serial" }
+        );
+        println!(
+            "{:<18} {:<16} {:>9} {:>9} {:>9} {:>9} {:>9} {:>11} {:>11}",
+            "scene",
+            "config",
+            "particles",
+            "build ms",
+            "mean ms",
+            "p95 ms",
+            "max ms",
+            "residual",
+            "energy"
+        );
+        for result in &self.results {
+            println!(
+                "{:<18} {:<16} {:>9} {:>9.2} {:>9.3} {:>9.3} {:>9.3} {:>11.3e} {:>11.3e}{}",
+                result.scene,
+                result.config,
+                result.num_particles,
+                result.build_ms,
+                result.step_ms.mean,
+                result.step_ms.p95,
+                result.step_ms.max,
+                result.mean_residual,
+                result.final_kinetic_energy,
+                if result.stable { "" } else { "  UNSTABLE" }
+            );
+        }
+    }
+
+    /// Print the change of each run relative to the same scene and configuration in
+    /// `baseline`, and return the runs whose mean step time grew by more than
+    /// `max_regression` percent or that became unstable.
+    pub fn compare(&self, baseline: &Report, max_regression: f64) -> Vec<&RunResult> {
+        if baseline.parallel != self.parallel {
+            println!("the baseline was run with a different local step, the times differ");
+        }
+        println!(
+            "{:<18} {:<16} {:>12} {:>12}",
+            "scene", "config", "mean ms", "residual"
+        );
+        let mut regressions = vec![];
+        for result in &self.results {
+            let previous = baseline.results.iter().find(|previous| {
+                previous.scene == result.scene && previous.config == result.config
+            });
+            let previous = match previous {
+                Some(previous) => previous,
+                None => {
+                    println!(
+                        "{:<18} {:<16} not in the baseline",
+                        result.scene, result.config
+                    );
+                    continue;
+                }
+            };
+            let time_change = relative_change(previous.step_ms.mean, result.step_ms.mean);
+            let residual_change =
+                relative_change(previous.mean_residual as f64, result.mean_residual as f64);
+            let regressed = time_change > max_regression || (previous.stable && !result.stable);
+            println!(
+                "{:<18} {:<16} {:>+11.1}% {:>+11.1}%{}",
+                result.scene,
+                result.config,
+                time_change,
+                residual_change,
+                if regressed { "  REGRESSED" } else { "" }
+            );
+            if regressed {
+                regressions.push(result);
+            }
+        }
+        regressions
+    }
+}
+
+/// Change from `before` to `after`, in percent of `before`.
+fn relative_change(before: f64, after: f64) -> f64 {
+    if before > 0.0 {
+        (after - before) / before * 100.0
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,149 @@
+use clap::ValueEnum;
+use fast_mass_spring::{
+    cloth::{Attachment, Cloth, ClothBuilder},
+    solver::{FastMassSpringSolver, Integrator, SolverBuildError},
+};
+use simulation::{
+    math::{Isometry3, UnitQuaternion, Vector3},
+    SphereCollider,
+};
+
+pub const TIME_STEP: f32 = 1.0 / 60.0;
+
+/// The standardized scenes, close to the defaults of the demos of the same name so that the
+/// numbers relate to how the demos feel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Scene {
+    /// A vertical cloth hanging by its two top corners.
+    Hang,
+    /// A horizontal cloth dropped on a sphere.
+    DropOnSphere,
+    /// The hanging cloth at twice the resolution, to show how the step time grows with the
+    /// size of the system. The system matrix is dense, so much larger grids do not fit in
+    /// memory.
+    LargeResolution,
+}
+
+impl Scene {
+    pub const ALL: [Scene; 3] = [Scene::Hang, Scene::DropOnSphere, Scene::LargeResolution];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scene::Hang => "hang",
+            Scene::DropOnSphere => "drop-on-sphere",
+            Scene::LargeResolution => "large-resolution",
+        }
+    }
+
+    /// Number of particles along each side of the cloth.
+    fn resolution(self) -> usize {
+        match self {
+            Scene::Hang | Scene::DropOnSphere => 20,
+            Scene::LargeResolution => 40,
+        }
+    }
+
+    /// Build the cloth and the solver of the scene with the parameters of `config`.
+    pub fn build(self, config: &SolverConfig) -> Result<FastMassSpringSolver, SolverBuildError> {
+        let cloth = match self {
+            Scene::Hang | Scene::LargeResolution => hanging_cloth(self.resolution()),
+            Scene::DropOnSphere => horizontal_cloth(self.resolution()),
+        };
+        let mut solver = FastMassSpringSolver::new(cloth, TIME_STEP)?;
+        solver.set_gravity(Vector3::new(0.0, -9.8, 0.0));
+        solver.set_integrator(config.integrator);
+        solver.set_num_iterations(config.num_iterations);
+        solver.set_substeps(config.substeps);
+        if self == Scene::DropOnSphere {
+            solver.set_contact_velocity_correction(1.0);
+            solver.add_collider(SphereCollider::new(1.0), Isometry3::identity());
+        }
+        Ok(solver)
+    }
+}
+
+/// A named set of solver parameters to run every scene with.
+#[derive(Debug, Clone, Copy)]
+pub struct SolverConfig {
+    pub name: &'static str,
+    pub integrator: Integrator,
+    pub num_iterations: usize,
+    pub substeps: usize,
+}
+
+/// The configurations compared by default: the implicit solver with more iterations or
+/// substeps, and the explicit integrator with enough substeps to stay stable.
+pub const CONFIGS: [SolverConfig; 5] = [
+    SolverConfig {
+        name: "pd-2",
+        integrator: Integrator::ProjectiveDynamics,
+        num_iterations: 2,
+        substeps: 1,
+    },
+    SolverConfig {
+        name: "pd-5",
+        integrator: Integrator::ProjectiveDynamics,
+        num_iterations: 5,
+        substeps: 1,
+    },
+    SolverConfig {
+        name: "pd-10",
+        integrator: Integrator::ProjectiveDynamics,
+        num_iterations: 10,
+        substeps: 1,
+    },
+    SolverConfig {
+        name: "pd-2-sub4",
+        integrator: Integrator::ProjectiveDynamics,
+        num_iterations: 2,
+        substeps: 4,
+    },
+    SolverConfig {
+        name: "explicit-sub16",
+        integrator: Integrator::SymplecticEuler,
+        num_iterations: 1,
+        substeps: 16,
+    },
+];
+
+fn builder(resolution: usize, transform: Isometry3) -> ClothBuilder {
+    let size = 3.0;
+    ClothBuilder {
+        width: size,
+        height: size,
+        width_segments: resolution - 1,
+        height_segments: resolution - 1,
+        structural_spring_stiffness: 10.0,
+        shear_spring_stiffness: 0.6,
+        mass: 1.0,
+        transform,
+        stiffness_map: None,
+    }
+}
+
+fn hanging_cloth(resolution: usize) -> Cloth {
+    let builder = builder(resolution, Isometry3::identity());
+    let corners = [
+        builder.top_left_vertex_index(),
+        builder.top_right_vertex_index(),
+    ];
+    let mut cloth = builder.build();
+    let attachments: Vec<_> = corners
+        .into_iter()
+        .map(|particle_index| Attachment {
+            particle_index,
+            target_position: cloth.get_particle_position(particle_index),
+            stiffness: 100.0,
+        })
+        .collect();
+    cloth.add_attachments(attachments);
+    cloth
+}
+
+fn horizontal_cloth(resolution: usize) -> Cloth {
+    let transform = Isometry3 {
+        rotation: UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f32::consts::FRAC_PI_2),
+        translation: Vector3::new(0.0, 1.2, 0.0).into(),
+    };
+    builder(resolution, transform).build()
+}
@@ -0,0 +1,123 @@
+use crate::math::{Isometry3, Number, Vector3};
+
+/// Axis aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Aabb {
+    #[inline]
+    pub fn new(min: Vector3, max: Vector3) -> Self {
+        Self { min, max }
+    }
+
+    /// An inverted box containing nothing. Growing it by any point gives a box around that point.
+    #[inline]
+    pub fn empty() -> Self {
+        Self {
+            min: Vector3::repeat(Number::INFINITY),
+            max: Vector3::repeat(Number::NEG_INFINITY),
+        }
+    }
+
+    /// A box containing every finite point, for colliders that push points anywhere.
+    #[inline]
+    pub fn everything() -> Self {
+        Self {
+            min: Vector3::repeat(Number::MIN),
+            max: Vector3::repeat(Number::MAX),
+        }
+    }
+
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vector3>) -> Self {
+        let mut aabb = Self::empty();
+        for point in points {
+            aabb.grow(point);
+        }
+        aabb
+    }
+
+    #[inline]
+    pub fn grow(&mut self, point: &Vector3) {
+        self.min = self.min.inf(point);
+        self.max = self.max.sup(point);
+    }
+
+    #[inline]
+    pub fn merged(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    #[inline]
+    pub fn expanded(&self, margin: Number) -> Aabb {
+        let margin = Vector3::repeat(margin);
+        Aabb {
+            min: self.min - margin,
+            max: self.max + margin,
+        }
+    }
+
+    /// The box around this box after it is moved by `transform`.
+    pub fn transformed(&self, transform: &Isometry3) -> Aabb {
+        if self.is_empty() {
+            return *self;
+        }
+        let mut aabb = Aabb::empty();
+        for i in 0..8 {
+            let corner = Vector3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            );
+            aabb.grow(&transform.transform_point(&corner.into()).coords);
+        }
+        aabb
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    #[inline]
+    pub fn center(&self) -> Vector3 {
+        (self.min + self.max) * 0.5
+    }
+
+    #[inline]
+    pub fn extents(&self) -> Vector3 {
+        self.max - self.min
+    }
+
+    #[inline]
+    pub fn contains_point(&self, point: &Vector3) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+            && point.z >= self.min.z
+            && point.z <= self.max.z
+    }
+
+    /// Squared distance from `point` to the box, zero for points inside.
+    #[inline]
+    pub fn distance_squared_to_point(&self, point: &Vector3) -> Number {
+        let closest = point.sup(&self.min).inf(&self.max);
+        (point - closest).magnitude_squared()
+    }
+
+    #[inline]
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+}
@@ -0,0 +1,241 @@
+use std::cmp::Ordering;
+
+use crate::{
+    math::{Number, Vector3},
+    Aabb, Ray,
+};
+
+const MAX_LEAF_SIZE: usize = 4;
+const MAX_DEPTH: usize = 64;
+
+#[derive(Clone, Copy)]
+enum NodeKind {
+    Leaf { start: usize, count: usize },
+    Internal { left: usize, right: usize },
+}
+
+#[derive(Clone, Copy)]
+struct BvhNode {
+    aabb: Aabb,
+    kind: NodeKind,
+}
+
+/// Bounding volume hierarchy over a set of primitives, each represented by its bounding box.
+///
+/// The primitives are referred to by their index in the slice the hierarchy was built from.
+#[derive(Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    primitive_indices: Vec<usize>,
+    primitive_aabbs: Vec<Aabb>,
+}
+
+impl Bvh {
+    pub fn build(primitive_aabbs: &[Aabb]) -> Self {
+        let mut bvh = Self::default();
+        bvh.rebuild(primitive_aabbs);
+        bvh
+    }
+
+    /// Build the hierarchy again over new primitives, reusing the allocated memory.
+    pub fn rebuild(&mut self, primitive_aabbs: &[Aabb]) {
+        let num_primitives = primitive_aabbs.len();
+        self.nodes.clear();
+        self.primitive_indices.clear();
+        self.primitive_indices.extend(0..num_primitives);
+        self.primitive_aabbs.clear();
+        self.primitive_aabbs.extend_from_slice(primitive_aabbs);
+        if num_primitives > 0 {
+            self.build_node(0, num_primitives, 0);
+        }
+    }
+
+    #[inline]
+    pub fn num_primitives(&self) -> usize {
+        self.primitive_aabbs.len()
+    }
+
+    /// The bounding box of all primitives.
+    pub fn aabb(&self) -> Aabb {
+        self.nodes
+            .first()
+            .map(|node| node.aabb)
+            .unwrap_or_else(Aabb::empty)
+    }
+
+    /// Update the bounding boxes of the primitives and the nodes above them without changing
+    /// the structure of the hierarchy. Cheaper than a rebuild, but the queries slow down if the
+    /// primitives move far from where they were at build time.
+    pub fn refit(&mut self, primitive_aabbs: &[Aabb]) {
+        assert_eq!(primitive_aabbs.len(), self.primitive_aabbs.len());
+        self.primitive_aabbs.copy_from_slice(primitive_aabbs);
+        // children are always stored after their parent
+        for node_index in (0..self.nodes.len()).rev() {
+            let aabb = match self.nodes[node_index].kind {
+                NodeKind::Leaf { start, count } => self.primitive_indices[start..start + count]
+                    .iter()
+                    .fold(Aabb::empty(), |acc, &i| {
+                        acc.merged(&self.primitive_aabbs[i])
+                    }),
+                NodeKind::Internal { left, right } => {
+                    self.nodes[left].aabb.merged(&self.nodes[right].aabb)
+                }
+            };
+            self.nodes[node_index].aabb = aabb;
+        }
+    }
+
+    fn build_node(&mut self, start: usize, end: usize, depth: usize) -> usize {
+        let aabb = self.primitive_indices[start..end]
+            .iter()
+            .fold(Aabb::empty(), |acc, &i| {
+                acc.merged(&self.primitive_aabbs[i])
+            });
+        let node_index = self.nodes.len();
+        self.nodes.push(BvhNode {
+            aabb,
+            kind: NodeKind::Leaf {
+                start,
+                count: end - start,
+            },
+        });
+        if end - start <= MAX_LEAF_SIZE || depth + 1 >= MAX_DEPTH {
+            return node_index;
+        }
+
+        // split at the median of the primitive centers along the longest axis
+        let mut centers = Aabb::empty();
+        for &i in &self.primitive_indices[start..end] {
+            centers.grow(&self.primitive_aabbs[i].center());
+        }
+        let axis = centers.extents().imax();
+        let mid = (start + end) / 2;
+        let primitive_aabbs = &self.primitive_aabbs;
+        self.primitive_indices[start..end].select_nth_unstable_by(mid - start, |&a, &b| {
+            let ca = primitive_aabbs[a].center()[axis];
+            let cb = primitive_aabbs[b].center()[axis];
+            ca.partial_cmp(&cb).unwrap_or(Ordering::Equal)
+        });
+        let left = self.build_node(start, mid, depth + 1);
+        let right = self.build_node(mid, end, depth + 1);
+        self.nodes[node_index].kind = NodeKind::Internal { left, right };
+        node_index
+    }
+
+    /// Visit the index of every primitive whose bounding box is hit by the ray within
+    /// `max_distance`. The primitives are not visited in order of distance.
+    pub fn query_ray(&self, ray: &Ray, max_distance: Number, mut visitor: impl FnMut(usize)) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let hits = |aabb: &Aabb| {
+            aabb.ray_intersection(ray)
+                .is_some_and(|(t_min, _)| t_min <= max_distance)
+        };
+        let mut stack = [0usize; MAX_DEPTH * 2];
+        let mut stack_len = 1;
+        while stack_len > 0 {
+            stack_len -= 1;
+            let node = &self.nodes[stack[stack_len]];
+            if !hits(&node.aabb) {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf { start, count } => {
+                    for &i in &self.primitive_indices[start..start + count] {
+                        if hits(&self.primitive_aabbs[i]) {
+                            visitor(i);
+                        }
+                    }
+                }
+                NodeKind::Internal { left, right } => {
+                    stack[stack_len] = left;
+                    stack[stack_len + 1] = right;
+                    stack_len += 2;
+                }
+            }
+        }
+    }
+
+    /// Find the primitive closest to `point`. `distance_squared` computes the squared distance
+    /// from the point to a primitive; primitives whose bounding box is further away than the
+    /// best one found so far are skipped. Returns the primitive index and its squared distance.
+    pub fn query_closest(
+        &self,
+        point: &Vector3,
+        mut distance_squared: impl FnMut(usize) -> Number,
+    ) -> Option<(usize, Number)> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let mut closest: Option<(usize, Number)> = None;
+        let mut stack = [0usize; MAX_DEPTH * 2];
+        let mut stack_len = 1;
+        while stack_len > 0 {
+            stack_len -= 1;
+            let node = &self.nodes[stack[stack_len]];
+            let best = closest.map_or(Number::INFINITY, |(_, d)| d);
+            if node.aabb.distance_squared_to_point(point) > best {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf { start, count } => {
+                    for &i in &self.primitive_indices[start..start + count] {
+                        let best = closest.map_or(Number::INFINITY, |(_, d)| d);
+                        if self.primitive_aabbs[i].distance_squared_to_point(point) > best {
+                            continue;
+                        }
+                        let d = distance_squared(i);
+                        if d < best {
+                            closest = Some((i, d));
+                        }
+                    }
+                }
+                NodeKind::Internal { left, right } => {
+                    // visit the nearer child first, it is pushed last
+                    let d_left = self.nodes[left].aabb.distance_squared_to_point(point);
+                    let d_right = self.nodes[right].aabb.distance_squared_to_point(point);
+                    let (near, far) = if d_left <= d_right {
+                        (left, right)
+                    } else {
+                        (right, left)
+                    };
+                    stack[stack_len] = far;
+                    stack[stack_len + 1] = near;
+                    stack_len += 2;
+                }
+            }
+        }
+        closest
+    }
+
+    /// Visit the index of every primitive whose bounding box intersects `aabb`.
+    pub fn query_aabb(&self, aabb: &Aabb, mut visitor: impl FnMut(usize)) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut stack = [0usize; MAX_DEPTH * 2];
+        let mut stack_len = 1;
+        while stack_len > 0 {
+            stack_len -= 1;
+            let node = &self.nodes[stack[stack_len]];
+            if !node.aabb.intersects(aabb) {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf { start, count } => {
+                    for &i in &self.primitive_indices[start..start + count] {
+                        if self.primitive_aabbs[i].intersects(aabb) {
+                            visitor(i);
+                        }
+                    }
+                }
+                NodeKind::Internal { left, right } => {
+                    stack[stack_len] = left;
+                    stack[stack_len + 1] = right;
+                    stack_len += 2;
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,148 @@
+use crate::{
+    closest_point_on_triangle, math::Vector3, ray_sphere_intersection, ray_triangle_intersection,
+    triangle_normal_facing, Aabb, Bvh, Mesh, Ray, RayHit,
+};
+
 type Isometry3 = nalgebra::Isometry3<f32>;
 type Point3 = nalgebra::Point3<f32>;
 
 pub struct SphereCollider {
     pub radius: f32,
+    /// Keep the particles inside the sphere instead of outside, e.g. to contain the cloth.
+    pub inverted: bool,
 }
 
+impl SphereCollider {
+    #[inline]
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            inverted: false,
+        }
+    }
+
+    /// A sphere keeping the particles inside.
+    #[inline]
+    pub fn container(radius: f32) -> Self {
+        Self {
+            radius,
+            inverted: true,
+        }
+    }
+}
+
+/// A solid box centered on the origin of its transform.
+pub struct BoxCollider {
+    pub half_extents: Vector3,
+}
+
+impl BoxCollider {
+    #[inline]
+    pub fn new(half_extents: Vector3) -> Self {
+        Self { half_extents }
+    }
+}
+
+/// An infinite floor: the particles are kept on the side of the local `+y` axis of the plane
+/// through the origin of its transform.
+#[derive(Default)]
+pub struct PlaneCollider;
+
+/// Collide with the surface of a triangle mesh. The triangles are organized in a [`Bvh`].
+pub struct TriMeshCollider {
+    vertices: Vec<Vector3>,
+    indices: Vec<u32>,
+    bvh: Bvh,
+    /// Particles are kept at least this far away from the surface.
+    pub thickness: f32,
+    /// Particles deeper below the surface than this are assumed to be on the other side of a thin
+    /// part of the mesh and are left alone.
+    pub max_penetration: f32,
+}
+
+impl TriMeshCollider {
+    pub fn new(mesh: &Mesh, thickness: f32) -> Self {
+        let vertices = mesh.vertices().to_vec();
+        let indices = mesh.indices().to_vec();
+        let triangle_aabbs: Vec<Aabb> = indices
+            .chunks(3)
+            .map(|triangle| Aabb::from_points(triangle.iter().map(|&i| &vertices[i as usize])))
+            .collect();
+        Self {
+            bvh: Bvh::build(&triangle_aabbs),
+            vertices,
+            indices,
+            thickness,
+            max_penetration: 0.1,
+        }
+    }
+
+    #[inline]
+    pub fn vertices(&self) -> &[Vector3] {
+        &self.vertices
+    }
+
+    #[inline]
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Cast a ray in the local space of the mesh and return the closest hit.
+    pub fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        let mut closest: Option<RayHit> = None;
+        self.bvh.query_ray(ray, max_distance, |triangle_index| {
+            let [a, b, c] = self.triangle(triangle_index);
+            if let Some((distance, _)) = ray_triangle_intersection(ray, a, b, c) {
+                let limit = closest.map_or(max_distance, |hit| hit.distance);
+                if distance <= limit {
+                    closest = Some(RayHit {
+                        distance,
+                        point: ray.point_at(distance),
+                        normal: triangle_normal_facing(a, b, c, &ray.direction),
+                        triangle_index: Some(triangle_index),
+                    });
+                }
+            }
+        });
+        closest
+    }
+
+    /// Distance from a point in the local space of the mesh to the surface offset by the
+    /// thickness, searched within `max_distance`. The side of the surface is ignored.
+    fn distance_to_point(&self, local_point: &Vector3, max_distance: f32) -> Option<f32> {
+        let search_radius = self.thickness + max_distance;
+        let query = Aabb::new(*local_point, *local_point).expanded(search_radius);
+        let mut closest: Option<f32> = None;
+        self.bvh.query_aabb(&query, |triangle_index| {
+            let [a, b, c] = self.triangle(triangle_index);
+            let (surface_point, _) = closest_point_on_triangle(local_point, a, b, c);
+            let distance = (local_point - surface_point).magnitude();
+            if closest.is_none_or(|d| distance < d) {
+                closest = Some(distance);
+            }
+        });
+        closest.map(|distance| distance - self.thickness)
+    }
+
+    #[inline]
+    fn triangle(&self, triangle_index: usize) -> [&Vector3; 3] {
+        let i = triangle_index * 3;
+        [
+            &self.vertices[self.indices[i] as usize],
+            &self.vertices[self.indices[i + 1] as usize],
+            &self.vertices[self.indices[i + 2] as usize],
+        ]
+    }
+}
+
+#[non_exhaustive]
 pub enum Collider {
     Sphere(SphereCollider),
+    Box(BoxCollider),
+    Plane(PlaneCollider),
+    TriMesh(TriMeshCollider),
+    #[cfg(feature = "parry")]
+    Parry(crate::ParryCollider),
 }
 
 impl From<SphereCollider> for Collider {
@@ -16,9 +152,32 @@ impl From<SphereCollider> for Collider {
     }
 }
 
+impl From<BoxCollider> for Collider {
+    #[inline]
+    fn from(box_collider: BoxCollider) -> Self {
+        Self::Box(box_collider)
+    }
+}
+
+impl From<PlaneCollider> for Collider {
+    #[inline]
+    fn from(plane: PlaneCollider) -> Self {
+        Self::Plane(plane)
+    }
+}
+
+impl From<TriMeshCollider> for Collider {
+    #[inline]
+    fn from(tri_mesh: TriMeshCollider) -> Self {
+        Self::TriMesh(tri_mesh)
+    }
+}
+
 pub struct TransformedCollider {
     pub collider: Collider,
     pub transform: Isometry3,
+    /// The transform at the previous step, used to derive the velocity of moving colliders.
+    pub prev_transform: Isometry3,
 }
 
 pub trait ComputeCollisionWithPoint {
@@ -38,19 +197,254 @@ impl ComputeCollisionWithPoint for SphereCollider {
         let center: Point3 = collider_transform.translation.vector.into();
         let dir = point - center;
         let distance = dir.magnitude();
-        if distance >= self.radius {
-            None
-        } else {
+        let is_inside = distance < self.radius;
+        if is_inside != self.inverted {
             Some(center + dir / distance * self.radius)
+        } else {
+            None
         }
     }
 }
 
+impl ComputeCollisionWithPoint for BoxCollider {
+    fn compute_collision_with_point(
+        &self,
+        collider_transform: Isometry3,
+        point: Point3,
+    ) -> Option<Point3> {
+        let mut local_point = collider_transform.inverse_transform_point(&point);
+        // push out through the closest face
+        let mut exit: Option<(usize, f32)> = None;
+        for axis in 0..3 {
+            let depth = self.half_extents[axis] - local_point[axis].abs();
+            if depth <= 0.0 {
+                return None;
+            }
+            if exit.is_none_or(|(_, min_depth)| depth < min_depth) {
+                exit = Some((axis, depth));
+            }
+        }
+        let (axis, _) = exit?;
+        local_point[axis] = self.half_extents[axis].copysign(local_point[axis]);
+        Some(collider_transform * local_point)
+    }
+}
+
+impl ComputeCollisionWithPoint for PlaneCollider {
+    fn compute_collision_with_point(
+        &self,
+        collider_transform: Isometry3,
+        point: Point3,
+    ) -> Option<Point3> {
+        let mut local_point = collider_transform.inverse_transform_point(&point);
+        if local_point.y >= 0.0 {
+            return None;
+        }
+        local_point.y = 0.0;
+        Some(collider_transform * local_point)
+    }
+}
+
+impl ComputeCollisionWithPoint for TriMeshCollider {
+    fn compute_collision_with_point(
+        &self,
+        collider_transform: Isometry3,
+        point: Point3,
+    ) -> Option<Point3> {
+        let local_point = collider_transform.inverse_transform_point(&point).coords;
+        let search_radius = self.thickness + self.max_penetration;
+        let query = Aabb::new(local_point, local_point).expanded(search_radius);
+
+        // (distance, closest point, triangle normal)
+        let mut closest: Option<(f32, Vector3, Vector3)> = None;
+        self.bvh.query_aabb(&query, |triangle_index| {
+            let [a, b, c] = self.triangle(triangle_index);
+            let normal = (b - a).cross(&(c - a));
+            let area = normal.magnitude();
+            if area <= f32::EPSILON {
+                return;
+            }
+            let (surface_point, _) = closest_point_on_triangle(&local_point, a, b, c);
+            let distance = (local_point - surface_point).magnitude();
+            if distance < search_radius && closest.is_none_or(|(d, _, _)| distance < d) {
+                closest = Some((distance, surface_point, normal / area));
+            }
+        });
+
+        let (distance, surface_point, normal) = closest?;
+        let offset = local_point - surface_point;
+        let target = if offset.dot(&normal) < 0.0 {
+            // below the surface
+            surface_point + normal * self.thickness
+        } else if distance < self.thickness {
+            if distance > f32::EPSILON {
+                surface_point + offset / distance * self.thickness
+            } else {
+                surface_point + normal * self.thickness
+            }
+        } else {
+            return None;
+        };
+        Some(collider_transform * Point3::from(target))
+    }
+}
+
 impl TransformedCollider {
+    #[inline]
+    pub fn new(collider: Collider, transform: Isometry3) -> Self {
+        Self {
+            collider,
+            transform,
+            prev_transform: transform,
+        }
+    }
+
+    /// How far the collider surface at `point` moved since the previous step.
+    #[inline]
+    pub fn point_displacement(&self, point: Point3) -> Vector3 {
+        let local_point = self.transform.inverse_transform_point(&point);
+        point - self.prev_transform * local_point
+    }
+
+    /// Bounding box of the region where the collider can push points, in world space.
+    pub fn aabb(&self) -> Aabb {
+        match &self.collider {
+            Collider::Sphere(sphere) if sphere.inverted => Aabb::everything(),
+            Collider::Sphere(sphere) => {
+                let center = self.transform.translation.vector;
+                Aabb::new(center, center).expanded(sphere.radius)
+            }
+            Collider::Box(box_collider) => {
+                Aabb::new(-box_collider.half_extents, box_collider.half_extents)
+                    .transformed(&self.transform)
+            }
+            Collider::Plane(_) => Aabb::everything(),
+            Collider::TriMesh(tri_mesh) => tri_mesh
+                .bvh
+                .aabb()
+                .expanded(tri_mesh.thickness + tri_mesh.max_penetration)
+                .transformed(&self.transform),
+            #[cfg(feature = "parry")]
+            Collider::Parry(parry) => parry.aabb(&self.transform),
+        }
+    }
+
+    /// Cast a ray in world space against the collider surface and return the closest hit
+    /// within `max_distance`.
+    pub fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        match &self.collider {
+            Collider::Sphere(sphere) => {
+                let center = self.transform.translation.vector;
+                let distance = ray_sphere_intersection(ray, &center, sphere.radius)?;
+                if distance > max_distance {
+                    return None;
+                }
+                let point = ray.point_at(distance);
+                // facing the side the particles are kept on
+                let outward = (point - center).normalize();
+                Some(RayHit {
+                    distance,
+                    point,
+                    normal: if sphere.inverted { -outward } else { outward },
+                    triangle_index: None,
+                })
+            }
+            Collider::Box(box_collider) => {
+                let local_ray = ray.inverse_transformed(&self.transform);
+                let half_extents = box_collider.half_extents;
+                let (distance, _) =
+                    Aabb::new(-half_extents, half_extents).ray_intersection(&local_ray)?;
+                if distance > max_distance {
+                    return None;
+                }
+                // the face hit is the one the local point is closest to
+                let local_point = local_ray.point_at(distance);
+                let axis = (0..3)
+                    .min_by(|&a, &b| {
+                        let gap = |axis: usize| half_extents[axis] - local_point[axis].abs();
+                        gap(a).total_cmp(&gap(b))
+                    })
+                    .unwrap();
+                let mut normal = Vector3::zeros();
+                normal[axis] = 1.0f32.copysign(local_point[axis]);
+                Some(RayHit {
+                    distance,
+                    point: ray.point_at(distance),
+                    normal: self.transform * normal,
+                    triangle_index: None,
+                })
+            }
+            Collider::Plane(_) => {
+                let local_ray = ray.inverse_transformed(&self.transform);
+                let distance = -local_ray.origin.y / local_ray.direction.y;
+                if !(distance >= 0.0 && distance <= max_distance) {
+                    return None;
+                }
+                Some(RayHit {
+                    distance,
+                    point: ray.point_at(distance),
+                    normal: self.transform * Vector3::y(),
+                    triangle_index: None,
+                })
+            }
+            Collider::TriMesh(tri_mesh) => {
+                let local_ray = ray.inverse_transformed(&self.transform);
+                let hit = tri_mesh.raycast(&local_ray, max_distance)?;
+                Some(RayHit {
+                    point: ray.point_at(hit.distance),
+                    normal: self.transform * hit.normal,
+                    ..hit
+                })
+            }
+            #[cfg(feature = "parry")]
+            Collider::Parry(parry) => parry.raycast(&self.transform, ray, max_distance),
+        }
+    }
+
+    /// Distance from `point` to the region the collider pushes points out of, zero if the
+    /// point is inside. `None` if the point is farther than `max_distance`.
+    ///
+    /// Unlike [`Self::aabb`], also bounds the infinite colliders, e.g. to find the points
+    /// near a floor.
+    pub fn distance_to_point(&self, point: Point3, max_distance: f32) -> Option<f32> {
+        let local_point = self.transform.inverse_transform_point(&point).coords;
+        let distance = match &self.collider {
+            Collider::Sphere(sphere) => {
+                let from_center = local_point.magnitude();
+                if sphere.inverted {
+                    sphere.radius - from_center
+                } else {
+                    from_center - sphere.radius
+                }
+            }
+            Collider::Box(box_collider) => {
+                let outside = local_point.abs() - box_collider.half_extents;
+                outside.map(|x| x.max(0.0)).magnitude()
+            }
+            Collider::Plane(_) => local_point.y,
+            Collider::TriMesh(tri_mesh) => {
+                tri_mesh.distance_to_point(&local_point, max_distance)?
+            }
+            #[cfg(feature = "parry")]
+            Collider::Parry(parry) => parry.distance_to_point(&self.transform, &point),
+        };
+        let distance = distance.max(0.0);
+        (distance <= max_distance).then_some(distance)
+    }
+
     #[inline]
     pub fn compute_collision_with_point(&self, point: Point3) -> Option<Point3> {
         match &self.collider {
             Collider::Sphere(sphere) => sphere.compute_collision_with_point(self.transform, point),
+            Collider::Box(box_collider) => {
+                box_collider.compute_collision_with_point(self.transform, point)
+            }
+            Collider::Plane(plane) => plane.compute_collision_with_point(self.transform, point),
+            Collider::TriMesh(tri_mesh) => {
+                tri_mesh.compute_collision_with_point(self.transform, point)
+            }
+            #[cfg(feature = "parry")]
+            Collider::Parry(parry) => parry.compute_collision_with_point(self.transform, point),
         }
     }
 }
@@ -14,7 +14,7 @@ impl FixedFrames {
         }
     }
 
-    pub fn iter(&mut self, current_time: f32, max_frame_count: usize) -> FixedFramesIterMut {
+    pub fn iter(&mut self, current_time: f32, max_frame_count: usize) -> FixedFramesIterMut<'_> {
         FixedFramesIterMut {
             frames: self,
             current_time,
@@ -1,24 +1,61 @@
-use std::time::Instant;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Number of frame times kept by default, a few seconds at common refresh rates.
+const DEFAULT_HISTORY_LEN: usize = 300;
 
 pub struct FPSCounter {
     frame_count: usize,
     last_time: Instant,
     fps: u32,
+    last_frame: Option<Instant>,
+    /// The most recent frame times, oldest first.
+    frame_times: VecDeque<Duration>,
+    history_len: usize,
+}
+
+/// Statistics over the recent frame times of a [`FPSCounter`]. The percentiles show the
+/// occasional slow frames that the average hides.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameTimeStats {
+    pub min: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
 }
 
 impl Default for FPSCounter {
     fn default() -> Self {
+        Self::with_history_len(DEFAULT_HISTORY_LEN)
+    }
+}
+impl FPSCounter {
+    /// Keep the times of the last `history_len` frames for the statistics.
+    pub fn with_history_len(history_len: usize) -> Self {
+        assert!(history_len > 0, "the history must hold at least one frame");
         Self {
             frame_count: 0,
             last_time: Instant::now(),
             fps: 0,
+            last_frame: None,
+            frame_times: VecDeque::with_capacity(history_len),
+            history_len,
         }
     }
-}
-impl FPSCounter {
+
     pub fn update(&mut self) {
         self.frame_count += 1;
         let now = Instant::now();
+        if let Some(last_frame) = self.last_frame {
+            if self.frame_times.len() == self.history_len {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back(now - last_frame);
+        }
+        self.last_frame = Some(now);
         let elapsed = now - self.last_time;
         if elapsed.as_secs_f32() > 1.0 {
             self.fps = (self.frame_count as f32 / elapsed.as_secs_f32()) as u32;
@@ -30,4 +67,30 @@ impl FPSCounter {
     pub fn fps(&self) -> u32 {
         self.fps
     }
+
+    /// The recent frame times, oldest first.
+    pub fn frame_times(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.frame_times.iter().copied()
+    }
+
+    /// Statistics over the recent frame times, `None` before the second frame.
+    pub fn frame_time_stats(&self) -> Option<FrameTimeStats> {
+        if self.frame_times.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.frame_times.iter().copied().collect();
+        sorted.sort_unstable();
+        // nearest rank percentile
+        let percentile = |p: f32| {
+            let rank = (p / 100.0 * sorted.len() as f32).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        Some(FrameTimeStats {
+            min: sorted[0],
+            p50: percentile(50.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+            max: sorted[sorted.len() - 1],
+        })
+    }
 }
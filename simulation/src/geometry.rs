@@ -0,0 +1,95 @@
+use crate::math::Vector3;
+
+/// Compute the closest point on the triangle `abc` to `p`.
+///
+/// Returns the point together with its barycentric coordinates with respect to `a`, `b` and `c`.
+pub fn closest_point_on_triangle(
+    p: &Vector3,
+    a: &Vector3,
+    b: &Vector3,
+    c: &Vector3,
+) -> (Vector3, Vector3) {
+    // Real-Time Collision Detection, Christer Ericson, 5.1.5
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(&ap);
+    let d2 = ac.dot(&ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return (*a, Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    let bp = p - b;
+    let d3 = ab.dot(&bp);
+    let d4 = ac.dot(&bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return (*b, Vector3::new(0.0, 1.0, 0.0));
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let v = d1 / (d1 - d3);
+        return (a + ab * v, Vector3::new(1.0 - v, v, 0.0));
+    }
+
+    let cp = p - c;
+    let d5 = ab.dot(&cp);
+    let d6 = ac.dot(&cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return (*c, Vector3::new(0.0, 0.0, 1.0));
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let w = d2 / (d2 - d6);
+        return (a + ac * w, Vector3::new(1.0 - w, 0.0, w));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return (b + (c - b) * w, Vector3::new(0.0, 1.0 - w, w));
+    }
+
+    let denom = 1.0 / (va + vb + vc);
+    let v = vb * denom;
+    let w = vc * denom;
+    (a + ab * v + ac * w, Vector3::new(1.0 - v - w, v, w))
+}
+
+/// Unit normal of the triangle `abc`, flipped to face against `direction`.
+pub fn triangle_normal_facing(
+    a: &Vector3,
+    b: &Vector3,
+    c: &Vector3,
+    direction: &Vector3,
+) -> Vector3 {
+    let normal = (b - a).cross(&(c - a)).normalize();
+    if normal.dot(direction) > 0.0 {
+        -normal
+    } else {
+        normal
+    }
+}
+
+/// Area weighted vertex normals of a triangle mesh. Vertices without triangles get a zero
+/// normal.
+pub fn compute_vertex_normals(vertices: &[Vector3], indices: &[u32]) -> Vec<Vector3> {
+    let mut normals = vec![Vector3::zeros(); vertices.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [i0, i1, i2] = [
+            triangle[0] as usize,
+            triangle[1] as usize,
+            triangle[2] as usize,
+        ];
+        let v0 = vertices[i0];
+        let normal = (vertices[i1] - v0).cross(&(vertices[i2] - v0));
+        normals[i0] += normal;
+        normals[i1] += normal;
+        normals[i2] += normal;
+    }
+    for normal in normals.iter_mut() {
+        *normal = normal.try_normalize(0.0).unwrap_or_else(Vector3::zeros);
+    }
+    normals
+}
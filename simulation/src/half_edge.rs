@@ -0,0 +1,269 @@
+use std::{collections::HashMap, fmt};
+
+use crate::Mesh;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HalfEdgeError {
+    /// The directed edge appears in more than one triangle, either because more than two
+    /// triangles share the edge or because the neighboring triangles have opposite orientations.
+    NonManifoldEdge { from: usize, to: usize },
+    /// A triangle uses the same vertex twice.
+    DegenerateTriangle { triangle_index: usize },
+    /// A triangle refers to a vertex the mesh does not have.
+    IndexOutOfRange {
+        triangle_index: usize,
+        vertex_index: usize,
+    },
+}
+
+impl fmt::Display for HalfEdgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HalfEdgeError::NonManifoldEdge { from, to } => {
+                write!(
+                    f,
+                    "edge {} -> {} is used by more than one triangle",
+                    from, to
+                )
+            }
+            HalfEdgeError::DegenerateTriangle { triangle_index } => {
+                write!(f, "triangle {} uses a vertex twice", triangle_index)
+            }
+            HalfEdgeError::IndexOutOfRange {
+                triangle_index,
+                vertex_index,
+            } => write!(
+                f,
+                "triangle {} refers to vertex {} out of range",
+                triangle_index, vertex_index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HalfEdgeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HalfEdge {
+    /// The vertex the half edge starts from.
+    pub origin: usize,
+    pub face: usize,
+    pub next: usize,
+    /// The opposite half edge of the neighboring face, `None` on the boundary.
+    pub twin: Option<usize>,
+}
+
+/// Half edge representation of a triangle mesh, for local topology queries.
+///
+/// The half edges of face `f` are `3 * f`, `3 * f + 1` and `3 * f + 2`, in the order of the
+/// mesh indices.
+pub struct HalfEdgeMesh {
+    half_edges: Vec<HalfEdge>,
+    /// An outgoing half edge of each vertex, the boundary one for boundary vertices.
+    vertex_half_edges: Vec<Option<usize>>,
+}
+
+impl HalfEdgeMesh {
+    pub fn from_mesh(mesh: &Mesh) -> Result<Self, HalfEdgeError> {
+        let indices = mesh.indices();
+        let num_vertices = mesh.vertices().len();
+        let num_faces = indices.len() / 3;
+        let mut half_edges = Vec::with_capacity(num_faces * 3);
+        let mut directed_edges = HashMap::with_capacity(num_faces * 3);
+        for face in 0..num_faces {
+            let vertices = [
+                indices[face * 3] as usize,
+                indices[face * 3 + 1] as usize,
+                indices[face * 3 + 2] as usize,
+            ];
+            if let Some(&vertex_index) = vertices.iter().find(|&&i| i >= num_vertices) {
+                return Err(HalfEdgeError::IndexOutOfRange {
+                    triangle_index: face,
+                    vertex_index,
+                });
+            }
+            if vertices[0] == vertices[1]
+                || vertices[1] == vertices[2]
+                || vertices[2] == vertices[0]
+            {
+                return Err(HalfEdgeError::DegenerateTriangle {
+                    triangle_index: face,
+                });
+            }
+            for k in 0..3 {
+                let half_edge = face * 3 + k;
+                let from = vertices[k];
+                let to = vertices[(k + 1) % 3];
+                if directed_edges.insert((from, to), half_edge).is_some() {
+                    return Err(HalfEdgeError::NonManifoldEdge { from, to });
+                }
+                half_edges.push(HalfEdge {
+                    origin: from,
+                    face,
+                    next: face * 3 + (k + 1) % 3,
+                    twin: None,
+                });
+            }
+        }
+
+        let mut vertex_half_edges = vec![None; num_vertices];
+        for half_edge in 0..half_edges.len() {
+            let from = half_edges[half_edge].origin;
+            let to = half_edges[half_edges[half_edge].next].origin;
+            let twin = directed_edges.get(&(to, from)).copied();
+            half_edges[half_edge].twin = twin;
+            // prefer the boundary half edge, so that circulating from it visits the whole fan
+            if vertex_half_edges[from].is_none() || twin.is_none() {
+                vertex_half_edges[from] = Some(half_edge);
+            }
+        }
+        Ok(Self {
+            half_edges,
+            vertex_half_edges,
+        })
+    }
+
+    #[inline]
+    pub fn num_vertices(&self) -> usize {
+        self.vertex_half_edges.len()
+    }
+
+    #[inline]
+    pub fn num_faces(&self) -> usize {
+        self.half_edges.len() / 3
+    }
+
+    #[inline]
+    pub fn num_half_edges(&self) -> usize {
+        self.half_edges.len()
+    }
+
+    #[inline]
+    pub fn half_edge(&self, half_edge: usize) -> &HalfEdge {
+        &self.half_edges[half_edge]
+    }
+
+    #[inline]
+    pub fn next(&self, half_edge: usize) -> usize {
+        self.half_edges[half_edge].next
+    }
+
+    #[inline]
+    pub fn prev(&self, half_edge: usize) -> usize {
+        let face_start = half_edge - half_edge % 3;
+        face_start + (half_edge + 2) % 3
+    }
+
+    #[inline]
+    pub fn twin(&self, half_edge: usize) -> Option<usize> {
+        self.half_edges[half_edge].twin
+    }
+
+    #[inline]
+    pub fn origin(&self, half_edge: usize) -> usize {
+        self.half_edges[half_edge].origin
+    }
+
+    /// The vertex the half edge points to.
+    #[inline]
+    pub fn target(&self, half_edge: usize) -> usize {
+        self.origin(self.next(half_edge))
+    }
+
+    #[inline]
+    pub fn is_boundary_half_edge(&self, half_edge: usize) -> bool {
+        self.twin(half_edge).is_none()
+    }
+
+    /// Whether the vertex is on the boundary. Isolated vertices are not.
+    pub fn is_boundary_vertex(&self, vertex: usize) -> bool {
+        match self.vertex_half_edges[vertex] {
+            Some(half_edge) => {
+                self.is_boundary_half_edge(half_edge)
+                    || self.is_boundary_half_edge(self.prev(half_edge))
+            }
+            None => false,
+        }
+    }
+
+    #[inline]
+    pub fn face_half_edges(&self, face: usize) -> [usize; 3] {
+        [face * 3, face * 3 + 1, face * 3 + 2]
+    }
+
+    #[inline]
+    pub fn face_vertices(&self, face: usize) -> [usize; 3] {
+        self.face_half_edges(face)
+            .map(|half_edge| self.origin(half_edge))
+    }
+
+    /// The faces sharing an edge with `face`.
+    pub fn face_neighbors(&self, face: usize) -> impl Iterator<Item = usize> + '_ {
+        self.face_half_edges(face)
+            .into_iter()
+            .filter_map(|half_edge| self.twin(half_edge))
+            .map(|twin| self.half_edges[twin].face)
+    }
+
+    /// The half edges starting from `vertex`, circulating around it.
+    pub fn vertex_outgoing_half_edges(&self, vertex: usize) -> VertexCirculator<'_> {
+        let start = self.vertex_half_edges[vertex];
+        VertexCirculator {
+            mesh: self,
+            start,
+            current: start,
+        }
+    }
+
+    /// The faces around `vertex`.
+    pub fn vertex_faces(&self, vertex: usize) -> impl Iterator<Item = usize> + '_ {
+        self.vertex_outgoing_half_edges(vertex)
+            .map(|half_edge| self.half_edges[half_edge].face)
+    }
+
+    /// The vertices connected to `vertex` by an edge.
+    pub fn vertex_neighbors(&self, vertex: usize) -> impl Iterator<Item = usize> + '_ {
+        let targets = self
+            .vertex_outgoing_half_edges(vertex)
+            .map(|half_edge| self.target(half_edge));
+        // on the boundary, the fan ends with an incoming half edge without twin whose origin
+        // is not the target of any outgoing half edge
+        let closing = self
+            .vertex_outgoing_half_edges(vertex)
+            .last()
+            .map(|half_edge| self.prev(half_edge))
+            .filter(|&incoming| self.is_boundary_half_edge(incoming))
+            .map(|incoming| self.origin(incoming));
+        targets.chain(closing)
+    }
+
+    /// The two faces of the edge of `half_edge`, the second one is `None` on the boundary.
+    pub fn edge_faces(&self, half_edge: usize) -> (usize, Option<usize>) {
+        (
+            self.half_edges[half_edge].face,
+            self.twin(half_edge).map(|twin| self.half_edges[twin].face),
+        )
+    }
+}
+
+/// Iterate over the outgoing half edges of a vertex. Starting from the boundary half edge of
+/// a boundary vertex, the whole fan is visited. Vertices joining several fans only visit one.
+pub struct VertexCirculator<'a> {
+    mesh: &'a HalfEdgeMesh,
+    start: Option<usize>,
+    current: Option<usize>,
+}
+
+impl<'a> Iterator for VertexCirculator<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let current = self.current?;
+        // the twin of the previous half edge leaves the same vertex
+        self.current = self
+            .mesh
+            .twin(self.mesh.prev(current))
+            .filter(|&next| Some(next) != self.start);
+        Some(current)
+    }
+}
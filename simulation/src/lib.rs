@@ -1,9 +1,29 @@
+mod aabb;
+mod bvh;
 mod collision;
 mod fixed_frame;
 mod fps_counter;
+mod geometry;
+mod half_edge;
 pub mod math;
 mod mesh;
+mod obj;
+#[cfg(feature = "parry")]
+mod parry;
+mod ply;
+mod ray;
+mod spatial_hash;
+pub use aabb::*;
+pub use bvh::*;
 pub use collision::*;
 pub use fixed_frame::*;
-pub use fps_counter::FPSCounter;
+pub use fps_counter::{FPSCounter, FrameTimeStats};
+pub use geometry::*;
+pub use half_edge::*;
 pub use mesh::*;
+pub use obj::*;
+#[cfg(feature = "parry")]
+pub use parry::*;
+pub use ply::*;
+pub use ray::*;
+pub use spatial_hash::*;
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use nalgebra::Point3;
 
@@ -11,6 +11,11 @@ pub struct Mesh {
 }
 
 impl Mesh {
+    pub fn new(vertices: Vec<Vector3>, indices: Vec<u32>) -> Self {
+        assert_eq!(indices.len() % 3, 0);
+        Self { vertices, indices }
+    }
+
     #[inline]
     pub fn vertices(&self) -> &[Vector3] {
         &self.vertices
@@ -21,7 +26,8 @@ impl Mesh {
         &self.indices
     }
 
-    /// Compute the edges of the mesh without duplicates.
+    /// Compute the edges of the mesh without duplicates, in the order they first appear in the
+    /// triangles. The set only tests membership, so the order is the same on every run.
     pub fn compute_edges(&self) -> Vec<Edge> {
         let mut edge_set = HashSet::new();
         let mut edge_vec = vec![];
@@ -39,6 +45,58 @@ impl Mesh {
         }
         edge_vec
     }
+
+    #[inline]
+    fn triangle_edges(&self, triangle_index: usize) -> [Edge; 3] {
+        let i0 = self.indices[triangle_index * 3] as usize;
+        let i1 = self.indices[triangle_index * 3 + 1] as usize;
+        let i2 = self.indices[triangle_index * 3 + 2] as usize;
+        [Edge::new(i0, i1), Edge::new(i1, i2), Edge::new(i2, i0)]
+    }
+
+    /// Map each edge to the indices of the triangles sharing it. Interior edges of a manifold
+    /// mesh have two triangles, boundary edges one.
+    ///
+    /// The map iterates in a different order on every run, so visit the edges in the order of
+    /// [`Self::compute_edges`] when the order matters.
+    pub fn compute_edge_triangles(&self) -> HashMap<Edge, Vec<usize>> {
+        let mut edge_triangles: HashMap<Edge, Vec<usize>> = HashMap::new();
+        for triangle_index in 0..self.indices.len() / 3 {
+            for edge in self.triangle_edges(triangle_index) {
+                edge_triangles.entry(edge).or_default().push(triangle_index);
+            }
+        }
+        edge_triangles
+    }
+
+    /// Compute the edges used by a single triangle, in the order they appear in the triangles.
+    pub fn compute_boundary_edges(&self) -> Vec<Edge> {
+        let edge_triangles = self.compute_edge_triangles();
+        let mut boundary_edges = vec![];
+        for triangle_index in 0..self.indices.len() / 3 {
+            for edge in self.triangle_edges(triangle_index) {
+                if edge_triangles[&edge].len() == 1 {
+                    boundary_edges.push(edge);
+                }
+            }
+        }
+        boundary_edges
+    }
+
+    /// Compute the vertices on the boundary edges, without duplicates, e.g. to pin the border
+    /// of a cloth.
+    pub fn compute_boundary_vertices(&self) -> Vec<usize> {
+        let mut vertex_set = HashSet::new();
+        let mut vertices = vec![];
+        for edge in self.compute_boundary_edges() {
+            for vertex in [edge.v0(), edge.v1()] {
+                if vertex_set.insert(vertex) {
+                    vertices.push(vertex);
+                }
+            }
+        }
+        vertices
+    }
 }
 
 #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
@@ -142,3 +200,101 @@ impl GridPlaneBuilder {
         self.width_segments * (self.height_segments + 1) + self.height_segments
     }
 }
+
+/// A sphere made by subdividing an icosahedron, with evenly sized triangles. The triangles
+/// are counter clockwise seen from outside.
+pub struct IcosphereBuilder {
+    pub radius: f32,
+    /// Each subdivision splits every triangle in four.
+    pub subdivisions: usize,
+    pub transform: Isometry3,
+}
+
+impl IcosphereBuilder {
+    pub fn new(radius: f32, subdivisions: usize) -> Self {
+        Self {
+            radius,
+            subdivisions,
+            transform: Isometry3::identity(),
+        }
+    }
+
+    #[inline]
+    pub fn with_transform(mut self, transform: Isometry3) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn build(self) -> Mesh {
+        let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+        let mut vertices: Vec<Vector3> = [
+            [-1.0, t, 0.0],
+            [1.0, t, 0.0],
+            [-1.0, -t, 0.0],
+            [1.0, -t, 0.0],
+            [0.0, -1.0, t],
+            [0.0, 1.0, t],
+            [0.0, -1.0, -t],
+            [0.0, 1.0, -t],
+            [t, 0.0, -1.0],
+            [t, 0.0, 1.0],
+            [-t, 0.0, -1.0],
+            [-t, 0.0, 1.0],
+        ]
+        .iter()
+        .map(|v| Vector3::from(*v).normalize())
+        .collect();
+        let mut triangles: Vec<[u32; 3]> = vec![
+            [0, 11, 5],
+            [0, 5, 1],
+            [0, 1, 7],
+            [0, 7, 10],
+            [0, 10, 11],
+            [1, 5, 9],
+            [5, 11, 4],
+            [11, 10, 2],
+            [10, 7, 6],
+            [7, 1, 8],
+            [3, 9, 4],
+            [3, 4, 2],
+            [3, 2, 6],
+            [3, 6, 8],
+            [3, 8, 9],
+            [4, 9, 5],
+            [2, 4, 11],
+            [6, 2, 10],
+            [8, 6, 7],
+            [9, 8, 1],
+        ];
+
+        for _ in 0..self.subdivisions {
+            // the midpoint of each edge, shared by the two triangles of the edge
+            let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+            let mut midpoint = |a: u32, b: u32, vertices: &mut Vec<Vector3>| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let v = (vertices[a as usize] + vertices[b as usize]).normalize();
+                    vertices.push(v);
+                    (vertices.len() - 1) as u32
+                })
+            };
+            let mut subdivided = Vec::with_capacity(triangles.len() * 4);
+            for [a, b, c] in triangles {
+                let ab = midpoint(a, b, &mut vertices);
+                let bc = midpoint(b, c, &mut vertices);
+                let ca = midpoint(c, a, &mut vertices);
+                subdivided.push([a, ab, ca]);
+                subdivided.push([b, bc, ab]);
+                subdivided.push([c, ca, bc]);
+                subdivided.push([ab, bc, ca]);
+            }
+            triangles = subdivided;
+        }
+
+        let vertices = vertices
+            .into_iter()
+            .map(|v| (self.transform * Point3::from(v * self.radius)).coords)
+            .collect();
+        let indices = triangles.into_iter().flatten().collect();
+        Mesh { vertices, indices }
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    fmt, fs,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{math::Vector3, Mesh};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(err) => write!(f, "failed to read obj file: {}", err),
+            ObjError::Parse { line, message } => write!(f, "obj line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<io::Error> for ObjError {
+    fn from(err: io::Error) -> Self {
+        ObjError::Io(err)
+    }
+}
+
+impl Mesh {
+    /// Write the mesh to a Wavefront OBJ file.
+    pub fn save_obj(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = BufWriter::new(fs::File::create(path)?);
+        write_obj(file, self.vertices(), self.indices(), None)
+    }
+
+    pub fn load_obj(path: impl AsRef<Path>) -> Result<Mesh, ObjError> {
+        let source = fs::read_to_string(path)?;
+        Self::from_obj_str(&source)
+    }
+
+    /// Parse the vertex positions and faces of a Wavefront OBJ file.
+    ///
+    /// Polygons are triangulated as fans, all other statements are ignored.
+    pub fn from_obj_str(source: &str) -> Result<Mesh, ObjError> {
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        for (line_index, line) in source.lines().enumerate() {
+            let line_number = line_index + 1;
+            let parse_error = |message: String| ObjError::Parse {
+                line: line_number,
+                message,
+            };
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let mut coords = [0.0; 3];
+                    for coord in coords.iter_mut() {
+                        let token = tokens
+                            .next()
+                            .ok_or_else(|| parse_error("missing vertex coordinate".into()))?;
+                        *coord = token
+                            .parse()
+                            .map_err(|_| parse_error(format!("invalid coordinate `{}`", token)))?;
+                    }
+                    vertices.push(Vector3::new(coords[0], coords[1], coords[2]));
+                }
+                Some("f") => {
+                    let mut face = vec![];
+                    for token in tokens {
+                        // `v`, `v/vt`, `v//vn` or `v/vt/vn`, only the position is used
+                        let index_token = token.split('/').next().unwrap_or(token);
+                        let index: i64 = index_token
+                            .parse()
+                            .map_err(|_| parse_error(format!("invalid face index `{}`", token)))?;
+                        let index = if index < 0 {
+                            vertices.len() as i64 + index
+                        } else {
+                            index - 1
+                        };
+                        if index < 0 || index >= vertices.len() as i64 {
+                            return Err(parse_error(format!(
+                                "face index `{}` out of range",
+                                token
+                            )));
+                        }
+                        face.push(index as u32);
+                    }
+                    if face.len() < 3 {
+                        return Err(parse_error("face with less than 3 vertices".into()));
+                    }
+                    for i in 1..face.len() - 1 {
+                        indices.extend([face[0], face[i], face[i + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Mesh::new(vertices, indices))
+    }
+}
+
+/// Write the triangles and optionally the vertex normals as a Wavefront OBJ file.
+pub fn write_obj(
+    mut writer: impl Write,
+    vertices: &[Vector3],
+    indices: &[u32],
+    normals: Option<&[Vector3]>,
+) -> io::Result<()> {
+    for v in vertices {
+        writeln!(writer, "v {} {} {}", v.x, v.y, v.z)?;
+    }
+    if let Some(normals) = normals {
+        assert_eq!(normals.len(), vertices.len());
+        for n in normals {
+            writeln!(writer, "vn {} {} {}", n.x, n.y, n.z)?;
+        }
+    }
+    // obj indices start at 1
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0] + 1, triangle[1] + 1, triangle[2] + 1];
+        if normals.is_some() {
+            writeln!(writer, "f {0}//{0} {1}//{1} {2}//{2}", a, b, c)?;
+        } else {
+            writeln!(writer, "f {} {} {}", a, b, c)?;
+        }
+    }
+    writer.flush()
+}
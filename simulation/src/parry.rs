@@ -0,0 +1,90 @@
+use parry3d::{
+    query::{PointQuery, Ray as ParryRay, RayCast},
+    shape::SharedShape,
+};
+
+use crate::{math::Vector3, Aabb, Ray, RayHit};
+
+type Isometry3 = nalgebra::Isometry3<f32>;
+type Point3 = nalgebra::Point3<f32>;
+
+/// Collide with any parry shape, e.g. the shape of a rapier collider, using parry's point
+/// projection.
+pub struct ParryCollider {
+    pub shape: SharedShape,
+    /// Particles are kept at least this far away from the surface.
+    pub thickness: f32,
+}
+
+impl ParryCollider {
+    #[inline]
+    pub fn new(shape: SharedShape, thickness: f32) -> Self {
+        Self { shape, thickness }
+    }
+
+    pub(crate) fn aabb(&self, transform: &Isometry3) -> Aabb {
+        let aabb = self.shape.compute_aabb(transform);
+        Aabb::new(aabb.mins.coords, aabb.maxs.coords).expanded(self.thickness)
+    }
+
+    /// Distance from `point` to the shape offset by the thickness, negative inside.
+    pub(crate) fn distance_to_point(&self, transform: &Isometry3, point: &Point3) -> f32 {
+        let projection = self.shape.project_point(transform, point, false);
+        if projection.is_inside {
+            return -self.thickness;
+        }
+        (point - projection.point).magnitude() - self.thickness
+    }
+
+    pub(crate) fn raycast(
+        &self,
+        transform: &Isometry3,
+        ray: &Ray,
+        max_distance: f32,
+    ) -> Option<RayHit> {
+        let parry_ray = ParryRay::new(ray.origin.into(), ray.direction);
+        let hit = self
+            .shape
+            .cast_ray_and_get_normal(transform, &parry_ray, max_distance, true)?;
+        Some(RayHit {
+            distance: hit.toi,
+            point: ray.point_at(hit.toi),
+            normal: hit.normal,
+            triangle_index: None,
+        })
+    }
+}
+
+impl crate::ComputeCollisionWithPoint for ParryCollider {
+    fn compute_collision_with_point(
+        &self,
+        collider_transform: Isometry3,
+        point: Point3,
+    ) -> Option<Point3> {
+        let projection = self.shape.project_point(&collider_transform, &point, false);
+        let offset: Vector3 = point - projection.point;
+        let distance = offset.magnitude();
+        if projection.is_inside {
+            if distance <= f32::EPSILON {
+                return Some(projection.point);
+            }
+            // the outward normal points from the inside point to the surface
+            Some(projection.point - offset / distance * self.thickness)
+        } else if distance < self.thickness {
+            if distance > f32::EPSILON {
+                Some(projection.point + offset / distance * self.thickness)
+            } else {
+                Some(projection.point)
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl From<ParryCollider> for crate::Collider {
+    #[inline]
+    fn from(parry: ParryCollider) -> Self {
+        Self::Parry(parry)
+    }
+}
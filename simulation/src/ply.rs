@@ -0,0 +1,40 @@
+use std::io::{self, Write};
+
+use crate::math::Vector3;
+
+/// Write the triangles and optionally the vertex normals as an ASCII PLY file.
+pub fn write_ply(
+    mut writer: impl Write,
+    vertices: &[Vector3],
+    indices: &[u32],
+    normals: Option<&[Vector3]>,
+) -> io::Result<()> {
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format ascii 1.0")?;
+    writeln!(writer, "element vertex {}", vertices.len())?;
+    for axis in ["x", "y", "z"] {
+        writeln!(writer, "property float {}", axis)?;
+    }
+    if let Some(normals) = normals {
+        assert_eq!(normals.len(), vertices.len());
+        for axis in ["nx", "ny", "nz"] {
+            writeln!(writer, "property float {}", axis)?;
+        }
+    }
+    writeln!(writer, "element face {}", indices.len() / 3)?;
+    writeln!(writer, "property list uchar uint vertex_indices")?;
+    writeln!(writer, "end_header")?;
+    for (i, v) in vertices.iter().enumerate() {
+        match normals {
+            Some(normals) => {
+                let n = normals[i];
+                writeln!(writer, "{} {} {} {} {} {}", v.x, v.y, v.z, n.x, n.y, n.z)?
+            }
+            None => writeln!(writer, "{} {} {}", v.x, v.y, v.z)?,
+        }
+    }
+    for triangle in indices.chunks_exact(3) {
+        writeln!(writer, "3 {} {} {}", triangle[0], triangle[1], triangle[2])?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,127 @@
+use crate::{
+    math::{Isometry3, Number, Vector3},
+    Aabb,
+};
+
+/// A half line starting at `origin`. The direction is expected to be normalized, so that the
+/// ray parameter is a distance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vector3,
+    pub direction: Vector3,
+}
+
+impl Ray {
+    /// Create a ray, normalizing the direction.
+    #[inline]
+    pub fn new(origin: Vector3, direction: Vector3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    #[inline]
+    pub fn point_at(&self, distance: Number) -> Vector3 {
+        self.origin + self.direction * distance
+    }
+
+    /// The ray in the local space of `transform`.
+    #[inline]
+    pub fn inverse_transformed(&self, transform: &Isometry3) -> Ray {
+        Ray {
+            origin: transform
+                .inverse_transform_point(&self.origin.into())
+                .coords,
+            direction: transform.inverse_transform_vector(&self.direction),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub distance: Number,
+    pub point: Vector3,
+    /// Surface normal at the hit point, facing the ray origin for triangles.
+    pub normal: Vector3,
+    /// The triangle hit, for triangle meshes.
+    pub triangle_index: Option<usize>,
+}
+
+/// Intersect a ray with the triangle `abc`. Returns the distance along the ray and the
+/// barycentric coordinates of the hit point.
+pub fn ray_triangle_intersection(
+    ray: &Ray,
+    a: &Vector3,
+    b: &Vector3,
+    c: &Vector3,
+) -> Option<(Number, Vector3)> {
+    // Möller–Trumbore
+    let ab = b - a;
+    let ac = c - a;
+    let p = ray.direction.cross(&ac);
+    let det = ab.dot(&p);
+    if det.abs() <= Number::EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let ao = ray.origin - a;
+    let u = ao.dot(&p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = ao.cross(&ab);
+    let v = ray.direction.dot(&q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = ac.dot(&q) * inv_det;
+    if t < 0.0 {
+        return None;
+    }
+    Some((t, Vector3::new(1.0 - u - v, u, v)))
+}
+
+/// Intersect a ray with a sphere. A ray starting inside the sphere hits its far side.
+pub fn ray_sphere_intersection(ray: &Ray, center: &Vector3, radius: Number) -> Option<Number> {
+    let oc = ray.origin - center;
+    let b = oc.dot(&ray.direction);
+    let c = oc.magnitude_squared() - radius * radius;
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrt_discriminant = discriminant.sqrt();
+    let t0 = -b - sqrt_discriminant;
+    let t1 = -b + sqrt_discriminant;
+    if t0 >= 0.0 {
+        Some(t0)
+    } else if t1 >= 0.0 {
+        Some(t1)
+    } else {
+        None
+    }
+}
+
+impl Aabb {
+    /// The range of distances along the ray inside the box, if the ray hits it.
+    pub fn ray_intersection(&self, ray: &Ray) -> Option<(Number, Number)> {
+        let mut t_min: Number = 0.0;
+        let mut t_max = Number::INFINITY;
+        for axis in 0..3 {
+            let inv_direction = 1.0 / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inv_direction;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inv_direction;
+            if inv_direction < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // NaN from 0 * inf keeps the current range
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use crate::{
+    math::{Number, Vector3},
+    Aabb,
+};
+
+/// Uniform grid over unbounded space, storing the items overlapping each cell. Suited for many
+/// small items of similar size that move every step, where rebuilding a hierarchy costs more
+/// than hashing them again.
+///
+/// Items are referred to by an index chosen by the caller.
+#[derive(Debug, Clone)]
+pub struct SpatialHash {
+    cell_size: Number,
+    cells: HashMap<[i32; 3], Vec<usize>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: Number) -> Self {
+        assert!(
+            cell_size > 0.0 && cell_size.is_finite(),
+            "cell size must be positive and finite"
+        );
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn cell_size(&self) -> Number {
+        self.cell_size
+    }
+
+    /// Remove every item and change the cell size. The memory of the cells used since the
+    /// previous clear is kept, so that hashing similar items again does not allocate.
+    pub fn clear(&mut self, cell_size: Number) {
+        assert!(
+            cell_size > 0.0 && cell_size.is_finite(),
+            "cell size must be positive and finite"
+        );
+        if cell_size != self.cell_size {
+            self.cells.clear();
+            self.cell_size = cell_size;
+            return;
+        }
+        self.cells.retain(|_, items| !items.is_empty());
+        for items in self.cells.values_mut() {
+            items.clear();
+        }
+    }
+
+    /// Add an item to every cell overlapping `aabb`.
+    pub fn insert(&mut self, item: usize, aabb: &Aabb) {
+        let min = self.cell(&aabb.min);
+        let max = self.cell(&aabb.max);
+        for x in min[0]..=max[0] {
+            for y in min[1]..=max[1] {
+                for z in min[2]..=max[2] {
+                    self.cells.entry([x, y, z]).or_default().push(item);
+                }
+            }
+        }
+    }
+
+    /// The items of the cell containing `point`. An item overlapping several cells is
+    /// reported once per query.
+    pub fn query_point(&self, point: &Vector3) -> &[usize] {
+        self.cells
+            .get(&self.cell(point))
+            .map_or(&[][..], Vec::as_slice)
+    }
+
+    #[inline]
+    fn cell(&self, point: &Vector3) -> [i32; 3] {
+        [0, 1, 2].map(|axis| (point[axis] / self.cell_size).floor() as i32)
+    }
+}
@@ -0,0 +1,104 @@
+//! Circulating around the vertices of a half edge mesh and rejecting invalid meshes.
+
+use std::collections::HashSet;
+
+use simulation::{math::Vector3, HalfEdgeError, HalfEdgeMesh, Mesh};
+
+fn mesh(num_vertices: usize, indices: &[u32]) -> Mesh {
+    let vertices = (0..num_vertices)
+        .map(|i| Vector3::new(i as f32, 0.0, 0.0))
+        .collect();
+    Mesh::new(vertices, indices.to_vec())
+}
+
+/// Vertex 0 surrounded by a closed ring of six triangles.
+fn hexagon_fan() -> HalfEdgeMesh {
+    let indices: Vec<u32> = (1..=6).flat_map(|i| [0, i, i % 6 + 1]).collect();
+    HalfEdgeMesh::from_mesh(&mesh(7, &indices)).expect("manifold mesh")
+}
+
+/// Vertex 0 on the boundary of an open fan of three triangles.
+fn half_fan() -> HalfEdgeMesh {
+    HalfEdgeMesh::from_mesh(&mesh(5, &[0, 1, 2, 0, 2, 3, 0, 3, 4])).expect("manifold mesh")
+}
+
+fn set(items: impl IntoIterator<Item = usize>) -> HashSet<usize> {
+    items.into_iter().collect()
+}
+
+#[test]
+fn circulates_around_interior_vertex() {
+    let mesh = hexagon_fan();
+    assert!(!mesh.is_boundary_vertex(0));
+    let outgoing: Vec<usize> = mesh.vertex_outgoing_half_edges(0).collect();
+    assert_eq!(outgoing.len(), 6);
+    assert!(outgoing
+        .iter()
+        .all(|&half_edge| mesh.origin(half_edge) == 0));
+    assert_eq!(set(mesh.vertex_faces(0)), set(0..6));
+    assert_eq!(set(mesh.vertex_neighbors(0)), set(1..7));
+    assert_eq!(mesh.vertex_neighbors(0).count(), 6);
+    assert!(mesh.is_boundary_vertex(1));
+    assert_eq!(set(mesh.vertex_neighbors(1)), set([0, 2, 6]));
+}
+
+#[test]
+fn circulates_around_boundary_vertex() {
+    let mesh = half_fan();
+    assert!(mesh.is_boundary_vertex(0));
+    assert_eq!(set(mesh.vertex_faces(0)), set(0..3));
+    // the neighbor closing the fan is only reached by an incoming half edge
+    assert_eq!(set(mesh.vertex_neighbors(0)), set(1..5));
+    assert_eq!(mesh.vertex_neighbors(0).count(), 4);
+    assert_eq!(set(mesh.vertex_faces(1)), set([0]));
+    assert_eq!(set(mesh.vertex_neighbors(1)), set([0, 2]));
+    assert_eq!(set(mesh.face_neighbors(1)), set([0, 2]));
+}
+
+#[test]
+fn circulates_one_fan_of_non_manifold_vertex() {
+    // two triangles touching only at vertex 0
+    let mesh = HalfEdgeMesh::from_mesh(&mesh(5, &[0, 1, 2, 0, 3, 4])).expect("manifold edges");
+    assert!(mesh.is_boundary_vertex(0));
+    let faces = set(mesh.vertex_faces(0));
+    assert_eq!(faces.len(), 1);
+    let neighbors = set(mesh.vertex_neighbors(0));
+    assert!(
+        neighbors == set([1, 2]) || neighbors == set([3, 4]),
+        "{:?}",
+        neighbors
+    );
+}
+
+#[test]
+fn isolated_vertex_has_no_half_edges() {
+    let mesh = HalfEdgeMesh::from_mesh(&mesh(4, &[0, 1, 2])).expect("manifold mesh");
+    assert!(!mesh.is_boundary_vertex(3));
+    assert_eq!(mesh.vertex_outgoing_half_edges(3).count(), 0);
+    assert_eq!(mesh.vertex_neighbors(3).count(), 0);
+}
+
+#[test]
+fn rejects_invalid_meshes() {
+    assert_eq!(
+        HalfEdgeMesh::from_mesh(&mesh(3, &[0, 1, 1])).err(),
+        Some(HalfEdgeError::DegenerateTriangle { triangle_index: 0 })
+    );
+    assert_eq!(
+        HalfEdgeMesh::from_mesh(&mesh(3, &[0, 1, 2, 0, 2, 3])).err(),
+        Some(HalfEdgeError::IndexOutOfRange {
+            triangle_index: 1,
+            vertex_index: 3
+        })
+    );
+    // the second triangle has the opposite orientation
+    assert_eq!(
+        HalfEdgeMesh::from_mesh(&mesh(4, &[0, 1, 2, 0, 1, 3])).err(),
+        Some(HalfEdgeError::NonManifoldEdge { from: 0, to: 1 })
+    );
+    // three triangles share the edge 0-1
+    assert_eq!(
+        HalfEdgeMesh::from_mesh(&mesh(5, &[0, 1, 2, 1, 0, 3, 1, 0, 4])).err(),
+        Some(HalfEdgeError::NonManifoldEdge { from: 1, to: 0 })
+    );
+}
@@ -0,0 +1,150 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use three_d::{Event, FrameInput, Key};
+
+/// Save the rendered frames to PNG files, either a single screenshot on demand
+/// or every Nth frame while recording.
+pub struct FrameCapture {
+    screenshot_requested: bool,
+    recording: bool,
+    /// Save one of every `frame_interval` frames while recording.
+    frame_interval: u32,
+    output_dir: String,
+    frame_counter: u32,
+    screenshot_index: u32,
+    recorded_frames: u32,
+    status: Option<String>,
+}
+
+impl Default for FrameCapture {
+    fn default() -> Self {
+        Self {
+            screenshot_requested: false,
+            recording: false,
+            frame_interval: 1,
+            output_dir: "captures".to_string(),
+            frame_counter: 0,
+            screenshot_index: 0,
+            recorded_frames: 0,
+            status: None,
+        }
+    }
+}
+
+impl FrameCapture {
+    /// Ctrl+P takes a screenshot.
+    pub fn handle_events(&mut self, events: &mut [Event]) {
+        for event in events.iter_mut() {
+            if let Event::KeyPress {
+                kind: Key::P,
+                modifiers,
+                handled,
+            } = event
+            {
+                if modifiers.ctrl && !*handled {
+                    self.screenshot_requested = true;
+                    *handled = true;
+                }
+            }
+        }
+    }
+
+    /// Save the current content of the screen if a screenshot was requested or a recorded
+    /// frame is due. Should be called after the scene is rendered and before the gui.
+    pub fn capture(&mut self, frame_input: &FrameInput) {
+        let record_frame = self.recording
+            && self
+                .frame_counter
+                .is_multiple_of(self.frame_interval.max(1));
+        if self.recording {
+            self.frame_counter += 1;
+        }
+        if !self.screenshot_requested && !record_frame {
+            return;
+        }
+        let pixels: Vec<[u8; 4]> = frame_input.screen().read_color();
+        let width = frame_input.viewport.width;
+        let height = frame_input.viewport.height;
+
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            let path = Path::new(&self.output_dir)
+                .join(format!("screenshot_{:04}.png", self.screenshot_index));
+            self.screenshot_index += 1;
+            self.status = Some(match save_png(&path, width, height, &pixels) {
+                Ok(()) => format!("saved {}", path.display()),
+                Err(err) => format!("failed to save {}: {}", path.display(), err),
+            });
+        }
+        if record_frame {
+            let path = Path::new(&self.output_dir)
+                .join("frames")
+                .join(format!("frame_{:05}.png", self.recorded_frames));
+            self.recorded_frames += 1;
+            if let Err(err) = save_png(&path, width, height, &pixels) {
+                self.recording = false;
+                self.status = Some(format!(
+                    "recording stopped, failed to save {}: {}",
+                    path.display(),
+                    err
+                ));
+            }
+        }
+    }
+
+    pub fn show_ui(&mut self, ui: &mut three_d::egui::Ui) {
+        use three_d::egui::*;
+        CollapsingHeader::new("Capture").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Output folder:");
+                ui.text_edit_singleline(&mut self.output_dir);
+            });
+            if ui.button("Screenshot (Ctrl+P)").clicked() {
+                self.screenshot_requested = true;
+            }
+            Slider::new(&mut self.frame_interval, 1..=30)
+                .text("Record Every Nth Frame")
+                .clamp_to_range(true)
+                .ui(ui);
+            let label = if self.recording {
+                "Stop Recording"
+            } else {
+                "Start Recording"
+            };
+            if ui.button(label).clicked() {
+                self.recording = !self.recording;
+                if self.recording {
+                    self.frame_counter = 0;
+                    self.recorded_frames = 0;
+                    self.status = None;
+                } else {
+                    self.status = Some(format!("recorded {} frames", self.recorded_frames));
+                }
+            }
+            if self.recording {
+                ui.label(format!("recording: {} frames", self.recorded_frames));
+            }
+            if let Some(status) = &self.status {
+                ui.label(status.as_str());
+            }
+        });
+    }
+}
+
+fn save_png(
+    path: &Path,
+    width: u32,
+    height: u32,
+    pixels: &[[u8; 4]],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels.concat().as_slice())?;
+    Ok(())
+}
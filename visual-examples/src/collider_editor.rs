@@ -0,0 +1,212 @@
+use fast_mass_spring::solver::{ColliderHandle, FastMassSpringSolver};
+use simulation::{
+    math::{Isometry3, Vector3},
+    BoxCollider, Collider, PlaneCollider, Ray, SphereCollider,
+};
+use three_d::{egui, Camera, Event, InnerSpace, MouseButton};
+
+/// Max distance at which a collider can be picked with the mouse.
+const PICK_DISTANCE: f32 = 100.0;
+
+struct Grab {
+    handle: ColliderHandle,
+    /// Distance along the view ray of the point grabbed.
+    depth: f32,
+    /// From the grabbed point to the collider origin.
+    offset: Vector3,
+}
+
+/// Panel to add, move and remove the colliders of the running demo, and a gizmo to drag them
+/// with the mouse while holding ctrl. The colliders are edited through their handles, so the
+/// edits are lost on restart.
+pub struct ColliderEditor {
+    sphere_radius: f32,
+    box_half_extents: [f32; 3],
+    /// Where new colliders are placed.
+    spawn_position: [f32; 3],
+    grab: Option<Grab>,
+}
+
+impl Default for ColliderEditor {
+    fn default() -> Self {
+        Self {
+            sphere_radius: 0.5,
+            box_half_extents: [0.5, 0.5, 0.5],
+            spawn_position: [0.0, -1.5, 0.0],
+            grab: None,
+        }
+    }
+}
+
+impl ColliderEditor {
+    pub fn show_ui(&mut self, ui: &mut egui::Ui, solver: &mut FastMassSpringSolver) {
+        use three_d::egui::*;
+        CollapsingHeader::new("Colliders").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for value in self.spawn_position.iter_mut() {
+                    DragValue::new(value).speed(0.05).ui(ui);
+                }
+                ui.label("New Position");
+            });
+            let spawn_transform = translation(&self.spawn_position);
+            ui.horizontal(|ui| {
+                Slider::new(&mut self.sphere_radius, 0.05..=3.0).ui(ui);
+                if ui.button("Add Sphere").clicked() {
+                    solver.add_collider(SphereCollider::new(self.sphere_radius), spawn_transform);
+                }
+            });
+            ui.horizontal(|ui| {
+                for value in self.box_half_extents.iter_mut() {
+                    DragValue::new(value)
+                        .speed(0.02)
+                        .clamp_range(0.01..=10.0)
+                        .ui(ui);
+                }
+                if ui.button("Add Box").clicked() {
+                    let half_extents = Vector3::from(self.box_half_extents);
+                    solver.add_collider(BoxCollider::new(half_extents), spawn_transform);
+                }
+            });
+            if ui.button("Add Plane").clicked() {
+                solver.add_collider(PlaneCollider, spawn_transform);
+            }
+
+            ui.separator();
+            let mut removed = None;
+            let handles = solver.collider_handles().to_vec();
+            for (index, handle) in handles.into_iter().enumerate() {
+                let transform = solver.collider_transform(handle);
+                let mut position = transform.translation.vector;
+                let mut moved = false;
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} {}", index, shape_name(solver, handle)));
+                    for value in position.iter_mut() {
+                        moved |= DragValue::new(value).speed(0.02).ui(ui).changed();
+                    }
+                    if ui.button("remove").clicked() {
+                        removed = Some(handle);
+                    }
+                });
+                if moved {
+                    solver.set_collider_transform(
+                        handle,
+                        Isometry3::from_parts(position.into(), transform.rotation),
+                    );
+                }
+            }
+            if let Some(handle) = removed {
+                solver.remove_collider(handle);
+            }
+            ui.label("Hold ctrl and drag a collider to move it.");
+        });
+    }
+
+    /// Drag the collider under the mouse in the plane facing the camera, while ctrl and the
+    /// left button are held. Should be called before the particle dragger, which uses the
+    /// left button too.
+    pub fn handle_events(
+        &mut self,
+        solver: &mut FastMassSpringSolver,
+        camera: &Camera,
+        events: &mut [Event],
+    ) {
+        for event in events.iter_mut() {
+            match event {
+                Event::MousePress {
+                    button: MouseButton::Left,
+                    position,
+                    modifiers,
+                    handled,
+                    ..
+                } if !*handled && modifiers.ctrl => {
+                    let ray = mouse_ray(camera, *position);
+                    if let Some((handle, distance)) = pick_collider(solver, &ray) {
+                        let point = ray.point_at(distance);
+                        let origin = solver.collider_transform(handle).translation.vector;
+                        self.grab = Some(Grab {
+                            handle,
+                            depth: distance,
+                            offset: origin - point,
+                        });
+                        *handled = true;
+                    }
+                }
+                Event::MouseMotion {
+                    position, handled, ..
+                } => {
+                    if let Some(grab) = &self.grab {
+                        if solver.collider(grab.handle).is_none() {
+                            // removed from the panel while dragged
+                            self.grab = None;
+                            continue;
+                        }
+                        let ray = mouse_ray(camera, *position);
+                        let transform = solver.collider_transform(grab.handle);
+                        let origin = ray.point_at(grab.depth) + grab.offset;
+                        solver.set_collider_transform(
+                            grab.handle,
+                            Isometry3::from_parts(origin.into(), transform.rotation),
+                        );
+                        *handled = true;
+                    }
+                }
+                Event::MouseRelease {
+                    button: MouseButton::Left,
+                    handled,
+                    ..
+                } => {
+                    *handled |= self.grab.take().is_some();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_dragging(&self) -> bool {
+        self.grab.is_some()
+    }
+
+    /// Forget the current grab, e.g. after the demo restarted.
+    pub fn reset(&mut self) {
+        self.grab = None;
+    }
+}
+
+fn mouse_ray(camera: &Camera, position: impl Into<three_d::PhysicalPoint>) -> Ray {
+    let position = position.into();
+    let origin = camera.position_at_pixel(position);
+    let direction = camera.view_direction_at_pixel(position).normalize();
+    Ray::new(
+        Vector3::new(origin.x, origin.y, origin.z),
+        Vector3::new(direction.x, direction.y, direction.z),
+    )
+}
+
+/// The closest collider hit by the ray and the distance to it.
+fn pick_collider(solver: &FastMassSpringSolver, ray: &Ray) -> Option<(ColliderHandle, f32)> {
+    solver
+        .colliders()
+        .iter()
+        .zip(solver.collider_handles())
+        .filter_map(|(collider, &handle)| {
+            collider
+                .raycast(ray, PICK_DISTANCE)
+                .map(|hit| (handle, hit.distance))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+fn shape_name(solver: &FastMassSpringSolver, handle: ColliderHandle) -> &'static str {
+    match solver.collider(handle).map(|collider| &collider.collider) {
+        Some(Collider::Sphere(_)) => "sphere",
+        Some(Collider::Box(_)) => "box",
+        Some(Collider::Plane(_)) => "plane",
+        Some(Collider::TriMesh(_)) => "mesh",
+        _ => "collider",
+    }
+}
+
+fn translation(position: &[f32; 3]) -> Isometry3 {
+    Isometry3::translation(position[0], position[1], position[2])
+}
@@ -1,4 +1,11 @@
-use simulation::math::Vector3;
+use fast_mass_spring::{
+    solver::{DampingMode, FastMassSpringSolver, Integrator, IterationBudget, SleepSettings},
+    wind::{Aerodynamics, Turbulence, Wind},
+};
+use serde::{Deserialize, Serialize};
+use simulation::{math::Vector3, Aabb};
+
+use crate::{config::SceneConfig, render::RenderOptions};
 use three_d::{Camera, FrameInput};
 
 pub trait Demo {
@@ -6,6 +13,65 @@ pub trait Demo {
     fn restart(&mut self, context: &three_d::Context);
     fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult;
     fn show_options_gui(&mut self, ui: &mut three_d::egui::Ui);
+
+    fn set_render_options(&mut self, options: RenderOptions);
+
+    /// Take the options of a scene configuration, used from the next restart.
+    fn apply_config(&mut self, _config: &SceneConfig) {}
+
+    /// The solver of the running scene, used for mouse interaction.
+    fn solver_mut(&mut self) -> Option<&mut FastMassSpringSolver> {
+        None
+    }
+
+    /// The camera pose applied after each restart. Frames the cloth of the running scene
+    /// from the front by default.
+    fn camera_preset(&mut self) -> CameraPreset {
+        self.solver_mut()
+            .map(|solver| {
+                CameraPreset::frame_aabb(&solver.cloth_aabb(), Vector3::new(0.0, 0.0, -1.0))
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Vertical field of view of the demo camera, in degrees.
+pub const CAMERA_FOV_Y: f32 = 45.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CameraPreset {
+    pub position: Vector3,
+    pub target: Vector3,
+}
+
+impl Default for CameraPreset {
+    fn default() -> Self {
+        Self {
+            position: Vector3::new(0.0, 0.0, 10.0),
+            target: Vector3::zeros(),
+        }
+    }
+}
+
+impl CameraPreset {
+    /// Look at the center of the box along `view_direction`, far enough for the box to fit
+    /// in the view with some margin.
+    pub fn frame_aabb(aabb: &Aabb, view_direction: Vector3) -> Self {
+        if aabb.is_empty() {
+            return Self::default();
+        }
+        let direction = view_direction
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(|| Vector3::new(0.0, 0.0, -1.0));
+        let radius = (0.5 * aabb.extents().magnitude()).max(0.1);
+        let half_fov = 0.5 * CAMERA_FOV_Y.to_radians();
+        let distance = 1.2 * radius / half_fov.sin();
+        let target = aabb.center();
+        Self {
+            position: target - direction * distance,
+            target,
+        }
+    }
 }
 
 pub struct DemoLoopResult {
@@ -22,11 +88,33 @@ impl DemoLoopResult {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SolverOptions {
     pub time_step: f32,
     pub gravity: Vector3,
     pub num_iterations: usize,
+    /// Adjust the iterations between 1 and `num_iterations` to keep each step within
+    /// `step_budget_ms`.
+    pub adaptive_iterations: bool,
+    pub step_budget_ms: f32,
+    pub substeps: usize,
+    /// Integrate the spring forces explicitly instead of with the implicit solver, to compare
+    /// their stability.
+    pub explicit_integration: bool,
+    pub damping: f32,
+    /// Damp only the deformation, not the fall or spin of the cloth as a whole.
+    pub damp_deformation_only: bool,
+    pub contact_velocity_correction: f32,
+    pub wind_direction: Vector3,
+    pub wind_strength: f32,
+    pub turbulence_amplitude: f32,
+    pub turbulence_frequency: f32,
+    /// Coefficients of the [`Aerodynamics`] on the cloth triangles, zero disables them.
+    pub aero_drag: f32,
+    pub aero_lift: f32,
+    /// Stop stepping the cloth once it settles, with the default [`SleepSettings`].
+    pub sleep: bool,
 }
 
 impl Default for SolverOptions {
@@ -35,18 +123,103 @@ impl Default for SolverOptions {
             time_step: 1.0 / 60.0,
             gravity: Vector3::new(0.0, -9.8, 0.0),
             num_iterations: 2,
+            adaptive_iterations: false,
+            step_budget_ms: 4.0,
+            substeps: 1,
+            explicit_integration: false,
+            damping: 1.0,
+            damp_deformation_only: false,
+            contact_velocity_correction: 1.0,
+            wind_direction: Vector3::new(0.0, 0.0, 1.0),
+            wind_strength: 0.0,
+            turbulence_amplitude: 0.0,
+            turbulence_frequency: 0.5,
+            aero_drag: 0.0,
+            aero_lift: 0.0,
+            sleep: false,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl SolverOptions {
+    /// Apply every option except the time step, which is fixed when the solver is created.
+    pub fn apply_live(&self, solver: &mut FastMassSpringSolver) {
+        solver.set_num_iterations(self.num_iterations);
+        solver
+            .set_iteration_budget(self.adaptive_iterations.then(|| {
+                IterationBudget::from_millis(self.step_budget_ms, 1, self.num_iterations)
+            }));
+        solver.set_substeps(self.substeps);
+        solver.set_integrator(if self.explicit_integration {
+            Integrator::SymplecticEuler
+        } else {
+            Integrator::ProjectiveDynamics
+        });
+        solver.set_gravity(self.gravity);
+        solver.set_damping(self.damping);
+        solver.set_damping_mode(if self.damp_deformation_only {
+            DampingMode::Deformation
+        } else {
+            DampingMode::Velocity
+        });
+        solver.set_contact_velocity_correction(self.contact_velocity_correction);
+        let wind_direction = self
+            .wind_direction
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::zeros);
+        solver.set_wind(Wind {
+            velocity: wind_direction * self.wind_strength,
+            drag: 1.0,
+            turbulence: Turbulence {
+                amplitude: self.turbulence_amplitude,
+                frequency: self.turbulence_frequency,
+                ..Default::default()
+            },
+        });
+        solver.set_aerodynamics(Aerodynamics {
+            drag_coefficient: self.aero_drag,
+            lift_coefficient: self.aero_lift,
+            ..Default::default()
+        });
+        solver.set_sleep(self.sleep.then(SleepSettings::default));
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ClothOptions {
+    #[serde(rename = "structural_spring_stiffness")]
     pub structual_spring_stiffness: f32,
     pub shear_spring_stiffness: f32,
     pub mass: f32,
     pub resolution: usize,
 }
 
+impl ClothOptions {
+    /// Set the spring stiffness of a running cloth built by
+    /// [`ClothBuilder`](fast_mass_spring::cloth::ClothBuilder). The system is refactorized on
+    /// the next step, so the cloth does not need to be rebuilt.
+    pub fn apply_live(&self, solver: &mut FastMassSpringSolver) {
+        // the cloths of the options are square, and the resolution may have been changed for
+        // the next restart, so take the running one
+        let resolution = (solver.cloth().num_particles() as f32).sqrt().round() as usize;
+        for index in 0..solver.cloth().springs.len() {
+            let spring = solver.cloth().springs[index].clone();
+            // shear springs connect diagonal neighbours of the grid
+            let (i0, i1) = (spring.particle_index_0, spring.particle_index_1);
+            let shear = i0 / resolution != i1 / resolution && i0 % resolution != i1 % resolution;
+            let stiffness = if shear {
+                self.shear_spring_stiffness
+            } else {
+                self.structual_spring_stiffness
+            };
+            solver
+                .update_spring(index, stiffness, spring.rest_length)
+                .expect("the stiffness sliders are in range");
+        }
+    }
+}
+
 impl Default for ClothOptions {
     fn default() -> Self {
         Self {
@@ -0,0 +1,289 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use fast_mass_spring::{cloth::Attachment, solver::FastMassSpringSolver};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use simulation::{math::Isometry3, Aabb, Mesh, ObjError, SphereCollider, TriMeshCollider};
+
+use crate::common::{ClothOptions, SolverOptions};
+
+/// A scene loaded from a TOML or JSON file, to reproduce a setup without describing the slider
+/// positions. Everything is optional:
+///
+/// ```toml
+/// demo = "Drop Cloth"
+///
+/// [solver]
+/// num_iterations = 10
+///
+/// [cloth]
+/// resolution = 30
+///
+/// [[colliders]]
+/// shape = "sphere"
+/// radius = 0.5
+/// position = [1.0, 0.0, 0.0]
+///
+/// [[attachments]]
+/// particles = [0, 19]
+/// stiffness = 50.0
+/// ```
+///
+/// The `solver` and `cloth` tables override the given options of the demo and keep its defaults
+/// for the others. The colliders and attachments are added to the scene on every restart.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneConfig {
+    /// The demo to start, by name. Without it, the options apply to every demo.
+    pub demo: Option<String>,
+    #[serde(default)]
+    solver: Option<Value>,
+    #[serde(default)]
+    cloth: Option<Value>,
+    #[serde(default)]
+    pub colliders: Vec<ColliderConfig>,
+    #[serde(default)]
+    pub attachments: Vec<AttachmentConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ColliderConfig {
+    Sphere {
+        radius: f32,
+        /// Keep the cloth inside the sphere.
+        #[serde(default)]
+        inverted: bool,
+        #[serde(default)]
+        position: [f32; 3],
+    },
+    /// A triangle mesh loaded from an obj file, relative to the working directory.
+    Mesh {
+        path: PathBuf,
+        thickness: f32,
+        #[serde(default)]
+        position: [f32; 3],
+    },
+}
+
+/// Attach the listed particles, and the particles within a box, where they start.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AttachmentConfig {
+    #[serde(default)]
+    pub particles: Vec<usize>,
+    #[serde(default)]
+    pub region: Option<RegionConfig>,
+    pub stiffness: f32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegionConfig {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    /// An option table does not match the options, e.g. a misspelled field.
+    InvalidOptions {
+        table: &'static str,
+        error: serde_json::Error,
+    },
+    Obj {
+        path: PathBuf,
+        error: ObjError,
+    },
+    UnknownDemo(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "failed to read the config: {}", err),
+            ConfigError::Toml(err) => write!(f, "invalid toml config: {}", err),
+            ConfigError::Json(err) => write!(f, "invalid json config: {}", err),
+            ConfigError::InvalidOptions { table, error } => {
+                write!(f, "invalid [{}] options: {}", table, error)
+            }
+            ConfigError::Obj { path, error } => {
+                write!(f, "collider mesh {}: {}", path.display(), error)
+            }
+            ConfigError::UnknownDemo(name) => write!(f, "there is no demo named {:?}", name),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl SceneConfig {
+    /// Load a config, as JSON if the file has the `json` extension and as TOML otherwise.
+    /// The options and the collider meshes are checked, so that a broken config fails here
+    /// rather than on restart.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        let config: SceneConfig = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&source).map_err(ConfigError::Json)?
+        } else {
+            toml::from_str(&source).map_err(ConfigError::Toml)?
+        };
+        config.solver_options(SolverOptions::default())?;
+        config.cloth_options(ClothOptions::default())?;
+        for collider in &config.colliders {
+            if let ColliderConfig::Mesh { path, .. } = collider {
+                Mesh::load_obj(path).map_err(|error| ConfigError::Obj {
+                    path: path.clone(),
+                    error,
+                })?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Whether the options apply to the demo named `name`.
+    pub fn applies_to(&self, name: &str) -> bool {
+        self.demo.as_deref().is_none_or(|demo| demo == name)
+    }
+
+    /// `options` with the fields given by the `solver` table replaced.
+    pub fn solver_options(&self, options: SolverOptions) -> Result<SolverOptions, ConfigError> {
+        override_fields(options, self.solver.as_ref()).map_err(|error| {
+            ConfigError::InvalidOptions {
+                table: "solver",
+                error,
+            }
+        })
+    }
+
+    /// `options` with the fields given by the `cloth` table replaced.
+    pub fn cloth_options(&self, options: ClothOptions) -> Result<ClothOptions, ConfigError> {
+        override_fields(options, self.cloth.as_ref()).map_err(|error| ConfigError::InvalidOptions {
+            table: "cloth",
+            error,
+        })
+    }
+
+    /// Override a field of the `solver` table.
+    pub fn set_solver_option(&mut self, key: &str, value: impl Into<Value>) {
+        set_field(&mut self.solver, key, value.into());
+    }
+
+    /// Override a field of the `cloth` table.
+    pub fn set_cloth_option(&mut self, key: &str, value: impl Into<Value>) {
+        set_field(&mut self.cloth, key, value.into());
+    }
+
+    /// Add the colliders and attachments of the config to a freshly restarted scene.
+    pub fn add_to_scene(&self, solver: &mut FastMassSpringSolver) {
+        for collider in &self.colliders {
+            match collider {
+                ColliderConfig::Sphere {
+                    radius,
+                    inverted,
+                    position,
+                } => {
+                    let sphere = SphereCollider {
+                        radius: *radius,
+                        inverted: *inverted,
+                    };
+                    solver.add_collider(sphere, translation(position));
+                }
+                ColliderConfig::Mesh {
+                    path,
+                    thickness,
+                    position,
+                } => match Mesh::load_obj(path) {
+                    Ok(mesh) => {
+                        solver.add_collider(
+                            TriMeshCollider::new(&mesh, *thickness),
+                            translation(position),
+                        );
+                    }
+                    Err(err) => eprintln!("failed to load {}: {}", path.display(), err),
+                },
+            }
+        }
+
+        let cloth = solver.cloth();
+        let num_particles = cloth.num_particles();
+        let mut attachments = vec![];
+        for config in &self.attachments {
+            let region = config
+                .region
+                .map(|region| Aabb::new(region.min.into(), region.max.into()));
+            let in_region = (0..num_particles).filter(|&i| {
+                region.is_some_and(|region| region.contains_point(&cloth.get_particle_position(i)))
+            });
+            for particle_index in config.particles.iter().copied().chain(in_region) {
+                if particle_index >= num_particles {
+                    eprintln!(
+                        "skipping the attachment of particle {}, the cloth has {} particles",
+                        particle_index, num_particles
+                    );
+                    continue;
+                }
+                attachments.push(Attachment {
+                    particle_index,
+                    target_position: cloth.get_particle_position(particle_index),
+                    stiffness: config.stiffness,
+                });
+            }
+        }
+        if let Err(err) = solver.add_attachments(attachments) {
+            eprintln!("skipping the attachments of the cloth: {}", err);
+        }
+    }
+}
+
+fn translation(position: &[f32; 3]) -> Isometry3 {
+    Isometry3::translation(position[0], position[1], position[2])
+}
+
+/// Replace the fields of `options` given in `overrides`, going through their serialized form.
+fn override_fields<T: Serialize + DeserializeOwned>(
+    options: T,
+    overrides: Option<&Value>,
+) -> Result<T, serde_json::Error> {
+    let overrides = match overrides {
+        Some(overrides) => overrides,
+        None => return Ok(options),
+    };
+    let mut value = serde_json::to_value(options)?;
+    merge(&mut value, overrides);
+    serde_json::from_value(value)
+}
+
+fn set_field(table: &mut Option<Value>, key: &str, value: Value) {
+    let table = table.get_or_insert_with(|| Value::Object(Default::default()));
+    if let Value::Object(fields) = table {
+        fields.insert(key.to_string(), value);
+    }
+}
+
+fn merge(value: &mut Value, overrides: &Value) {
+    match (value, overrides) {
+        (Value::Object(fields), Value::Object(overrides)) => {
+            for (key, field_override) in overrides {
+                merge(
+                    fields.entry(key.clone()).or_insert(Value::Null),
+                    field_override,
+                );
+            }
+        }
+        (value, overrides) => *value = overrides.clone(),
+    }
+}
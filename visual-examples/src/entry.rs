@@ -1,14 +1,34 @@
+mod cloth_grid_demo;
+mod cut_cloth_demo;
+mod drape_cloth_demo;
 mod drop_cloth_demo;
 mod hang_cloth_demo;
+mod inflate_balloon_demo;
+mod moving_collider_demo;
+mod trampoline_demo;
 
 use std::{collections::VecDeque, time::Duration};
 
 use simulation::FPSCounter;
-use three_d::{Camera, FrameInput};
+use three_d::{Camera, Event, FrameInput};
 
-use crate::common::Demo;
+use crate::{
+    capture::FrameCapture,
+    collider_editor::ColliderEditor,
+    common::{CameraPreset, Demo},
+    config::{ConfigError, SceneConfig},
+    gui::RenderOptionsGUI,
+    interaction::ParticleDragger,
+    plots::DiagnosticsPlots,
+    render::RenderOptions,
+};
 
-use self::{drop_cloth_demo::DropClothDemo, hang_cloth_demo::HangClothDemo};
+use self::{
+    cloth_grid_demo::ClothGridDemo, cut_cloth_demo::CutClothDemo, drape_cloth_demo::DrapeClothDemo,
+    drop_cloth_demo::DropClothDemo, hang_cloth_demo::HangClothDemo,
+    inflate_balloon_demo::InflateBalloonDemo, moving_collider_demo::MovingColliderDemo,
+    trampoline_demo::TrampolineDemo,
+};
 
 pub struct DemoEntry {
     gui: three_d::GUI,
@@ -16,6 +36,15 @@ pub struct DemoEntry {
     selected_demo_index: Option<usize>,
     fps_counter: FPSCounter,
     stats: Stats,
+    dragger: ParticleDragger,
+    collider_editor: ColliderEditor,
+    pointer_over_gui: bool,
+    render_options: RenderOptions,
+    capture: FrameCapture,
+    camera_preset: Option<CameraPreset>,
+    config: Option<SceneConfig>,
+    show_plots: bool,
+    plots: DiagnosticsPlots,
 }
 
 impl DemoEntry {
@@ -27,9 +56,24 @@ impl DemoEntry {
             selected_demo_index: None,
             fps_counter: FPSCounter::default(),
             stats: Stats::default(),
+            dragger: ParticleDragger::default(),
+            collider_editor: ColliderEditor::default(),
+            pointer_over_gui: false,
+            render_options: RenderOptions::default(),
+            capture: FrameCapture::default(),
+            camera_preset: None,
+            config: None,
+            show_plots: false,
+            plots: DiagnosticsPlots::default(),
         };
         slf.add_demo(HangClothDemo::default());
         slf.add_demo(DropClothDemo::default());
+        slf.add_demo(CutClothDemo::default());
+        slf.add_demo(DrapeClothDemo::default());
+        slf.add_demo(MovingColliderDemo::default());
+        slf.add_demo(InflateBalloonDemo::default());
+        slf.add_demo(ClothGridDemo::default());
+        slf.add_demo(TrampolineDemo::default());
         slf
     }
 
@@ -37,6 +81,96 @@ impl DemoEntry {
         self.demos.push(Box::new(demo));
     }
 
+    /// Apply a scene configuration to the demos it names, and start the named demo.
+    pub fn load_config(
+        &mut self,
+        context: &three_d::Context,
+        config: SceneConfig,
+    ) -> Result<(), ConfigError> {
+        let named_demo = match &config.demo {
+            Some(name) => Some(
+                self.demos
+                    .iter()
+                    .position(|demo| demo.name() == name)
+                    .ok_or_else(|| ConfigError::UnknownDemo(name.clone()))?,
+            ),
+            None => None,
+        };
+        for demo in &mut self.demos {
+            if config.applies_to(demo.name()) {
+                demo.apply_config(&config);
+            }
+        }
+        self.config = Some(config);
+        if let Some(index) = named_demo.or(self.selected_demo_index) {
+            self.selected_demo_index = Some(index);
+            self.restart_demo(context, index);
+        }
+        Ok(())
+    }
+
+    fn restart_demo(&mut self, context: &three_d::Context, index: usize) {
+        let demo = &mut self.demos[index];
+        demo.restart(context);
+        if let Some(config) = &self.config {
+            if config.applies_to(demo.name()) {
+                if let Some(solver) = demo.solver_mut() {
+                    config.add_to_scene(solver);
+                }
+            }
+        }
+        self.dragger.reset();
+        self.collider_editor.reset();
+        self.plots.clear();
+        self.camera_preset = Some(demo.camera_preset());
+    }
+
+    /// The frame and step times of the run so far, printed at the end of a timed run.
+    pub fn run_summary(&self) -> String {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let demo = self
+            .selected_demo_index
+            .map_or("none", |index| self.demos[index].name());
+        let mut summary = format!("demo: {}\nfps: {}\n", demo, self.fps_counter.fps());
+        if let Some(stats) = self.fps_counter.frame_time_stats() {
+            summary += &format!(
+                "frame: min {:.2} / p50 {:.2} / p95 {:.2} / p99 {:.2} / max {:.2} ms\n",
+                ms(stats.min),
+                ms(stats.p50),
+                ms(stats.p95),
+                ms(stats.p99),
+                ms(stats.max)
+            );
+        }
+        summary += &format!(
+            "step: {:.3} ms on average over {} updates",
+            ms(self.stats.total_avg_step_cost()),
+            self.stats.num_updates
+        );
+        summary
+    }
+
+    /// The camera pose requested by the last restart of a demo, if not taken yet.
+    pub fn take_camera_preset(&mut self) -> Option<CameraPreset> {
+        self.camera_preset.take()
+    }
+
+    /// Handle mouse interaction with the selected demo. Should be called before the camera control
+    /// so that dragging a particle does not orbit the camera.
+    pub fn handle_events(&mut self, camera: &Camera, events: &mut [Event]) {
+        self.capture.handle_events(events);
+        let dragging = self.dragger.is_dragging() || self.collider_editor.is_dragging();
+        if self.pointer_over_gui && !dragging {
+            return;
+        }
+        if let Some(index) = self.selected_demo_index {
+            if let Some(solver) = self.demos[index].solver_mut() {
+                self.collider_editor.handle_events(solver, camera, events);
+                self.dragger.handle_events(solver, camera, events);
+            }
+        }
+    }
+
     pub fn render_loop(
         &mut self,
         context: &three_d::Context,
@@ -45,13 +179,25 @@ impl DemoEntry {
     ) {
         if let Some(index) = self.selected_demo_index {
             let demo = &mut self.demos[index];
+            demo.set_render_options(self.render_options);
+            if let Some(solver) = demo.solver_mut() {
+                solver.set_diagnostics(self.show_plots);
+            }
             let result = demo.on_frame_loop(camera, frame_input);
             if result.updated {
                 self.stats.add_step_cost(result.step_cost);
+                if let Some(solver) = demo.solver_mut() {
+                    if let Some(diagnostics) = solver.diagnostics() {
+                        self.plots
+                            .push(solver.time(), diagnostics, result.step_cost);
+                    }
+                }
             }
         }
+        self.capture.capture(frame_input);
         self.fps_counter.update();
 
+        let mut restart = None;
         self.gui.update(
             &mut frame_input.events,
             frame_input.accumulated_time,
@@ -67,32 +213,69 @@ impl DemoEntry {
                     ComboBox::from_label("")
                         .selected_text(select_text)
                         .show_ui(ui, |ui| {
-                            for (index, demo) in self.demos.iter_mut().enumerate() {
+                            for (index, demo) in self.demos.iter().enumerate() {
                                 let r = ui.selectable_value(
                                     &mut self.selected_demo_index,
                                     Some(index),
                                     demo.name(),
                                 );
                                 if r.changed() && Some(index) == self.selected_demo_index {
-                                    demo.restart(context);
+                                    restart = Some(index);
                                 }
                             }
                         });
 
                     if let Some(index) = self.selected_demo_index {
                         self.demos[index].show_options_gui(ui);
+                        if let Some(solver) = self.demos[index].solver_mut() {
+                            self.collider_editor.show_ui(ui, solver);
+                        }
                     }
+                    RenderOptionsGUI::new(&mut self.render_options).show_ui(ui);
+                    self.capture.show_ui(ui);
+                    ui.checkbox(&mut self.show_plots, "Plots");
 
-                    if ui.button("restart").clicked() {
-                        if let Some(index) = self.selected_demo_index {
-                            self.demos[index].restart(context);
+                    ui.horizontal(|ui| {
+                        if ui.button("restart").clicked() {
+                            restart = self.selected_demo_index;
                         }
-                    }
+                        if ui.button("reset camera").clicked() {
+                            if let Some(index) = self.selected_demo_index {
+                                self.camera_preset = Some(self.demos[index].camera_preset());
+                            }
+                        }
+                    });
                 });
+                if self.show_plots {
+                    Window::new("Diagnostics")
+                        .default_width(300.0)
+                        .show(gui_context, |ui| self.plots.show_ui(ui));
+                }
+                self.pointer_over_gui = gui_context.is_pointer_over_area();
 
                 Area::new("screen_overlay").show(gui_context, |ui| {
                     ui.vertical(|ui| {
                         ui.colored_label(Rgba::BLACK, format!("fps: {}", self.fps_counter.fps()));
+                        if let Some(stats) = self.fps_counter.frame_time_stats() {
+                            let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+                            ui.colored_label(
+                                Rgba::BLACK,
+                                format!(
+                                    "frame: p50 {:.1} / p95 {:.1} / p99 {:.1} ms",
+                                    ms(stats.p50),
+                                    ms(stats.p95),
+                                    ms(stats.p99)
+                                ),
+                            );
+                            ui.colored_label(
+                                Rgba::BLACK,
+                                format!(
+                                    "frame: min {:.1} / max {:.1} ms",
+                                    ms(stats.min),
+                                    ms(stats.max)
+                                ),
+                            );
+                        }
                         ui.colored_label(
                             Rgba::BLACK,
                             format!(
@@ -104,6 +287,9 @@ impl DemoEntry {
                 });
             },
         );
+        if let Some(index) = restart {
+            self.restart_demo(context, index);
+        }
         frame_input.screen().write(|| {
             self.gui.render();
         });
@@ -114,10 +300,15 @@ impl DemoEntry {
 struct Stats {
     step_costs: VecDeque<Duration>,
     sum_step_costs: Duration,
+    /// Since the start, for the summary of a timed run.
+    num_updates: u32,
+    total_step_cost: Duration,
 }
 
 impl Stats {
     pub fn add_step_cost(&mut self, cost: Duration) {
+        self.num_updates += 1;
+        self.total_step_cost += cost;
         if self.step_costs.len() == 1 {
             let first = self.step_costs.pop_front().unwrap();
             self.sum_step_costs -= first;
@@ -132,4 +323,12 @@ impl Stats {
         }
         self.sum_step_costs / self.step_costs.len() as u32
     }
+
+    /// The average step cost since the start.
+    pub fn total_avg_step_cost(&self) -> Duration {
+        if self.num_updates == 0 {
+            return Duration::default();
+        }
+        self.total_step_cost / self.num_updates
+    }
 }
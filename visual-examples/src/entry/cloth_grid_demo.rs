@@ -0,0 +1,285 @@
+use std::time::Instant;
+
+use fast_mass_spring::{
+    cloth::{Cloth, ClothBuilder},
+    solver::FastMassSpringSolver,
+    world::World,
+};
+use simulation::{
+    math::{Isometry3, UnitQuaternion, Vector3},
+    Aabb, FixedFrames, GridPlaneBuilder, Mesh, SphereCollider,
+};
+use three_d::{
+    egui::{Slider, Widget},
+    Camera, FrameInput,
+};
+
+use crate::{
+    common::{CameraPreset, ClothOptions, Demo, DemoLoopResult, SolverOptions},
+    config::SceneConfig,
+    gui::{ClothOptionsGUI, SolverOptionsGUI},
+    render::{ColliderRender, Lights, MultiClothRender, RenderOptions},
+};
+
+const CLOTH_SIZE: f32 = 0.8;
+/// Distance between the centers of neighbouring cells of the grid.
+const SPACING: f32 = 1.0;
+const POST_RADIUS: f32 = 0.2;
+const FLOOR_RADIUS: f32 = 50.0;
+const FLOOR_HEIGHT: f32 = -0.6;
+
+/// A grid of small cloths, each with its own solver in a shared [`World`], falling on a post
+/// and piling up on the floor.
+pub struct ClothGridScene {
+    world: World,
+    cloth_render: MultiClothRender,
+    fixed_frames: FixedFrames,
+    collider_render: ColliderRender,
+    lights: Lights,
+}
+
+impl ClothGridScene {
+    fn new(context: &three_d::Context, scene_options: SceneOptions) -> Self {
+        let solver_options = scene_options.solver_options;
+        let mut world = World::new();
+        let mut cloth_render = MultiClothRender::new(context);
+        let grid_size = scene_options.grid_size;
+        for row in 0..grid_size {
+            for column in 0..grid_size {
+                let center = cell_center(grid_size, row, column);
+                let (cloth, mesh) = create_cloth(
+                    scene_options.cloth_options,
+                    center,
+                    row * grid_size + column,
+                );
+                cloth_render.add_cloth(mesh.indices(), cloth.num_particles());
+                let mut solver = FastMassSpringSolver::new(cloth, solver_options.time_step)
+                    .expect("failed to build the solver");
+                solver_options.apply_live(&mut solver);
+                world.add_cloth(solver);
+                world.add_collider(
+                    SphereCollider::new(POST_RADIUS),
+                    Isometry3::translation(center.x, 0.0, center.z),
+                );
+            }
+        }
+        world.add_collider(
+            SphereCollider::new(FLOOR_RADIUS),
+            Isometry3::translation(0.0, FLOOR_HEIGHT - FLOOR_RADIUS, 0.0),
+        );
+        world.set_cloth_thickness(scene_options.cloth_thickness);
+
+        Self {
+            world,
+            cloth_render,
+            fixed_frames: FixedFrames::new(solver_options.time_step),
+            collider_render: ColliderRender::new(context),
+            lights: Lights::new(context),
+        }
+    }
+
+    pub fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult {
+        let mut step_count = 0;
+        let time = Instant::now();
+        for _ in self
+            .fixed_frames
+            .iter((frame_input.accumulated_time / 1000.0) as f32, 1)
+        {
+            self.world.step();
+            step_count += 1;
+        }
+
+        let result = if step_count > 0 {
+            let step_cost = time.elapsed() / step_count;
+            self.cloth_render
+                .set_cloths(self.world.solvers().iter().map(|solver| solver.cloth()));
+            DemoLoopResult {
+                updated: true,
+                step_cost,
+            }
+        } else {
+            DemoLoopResult::not_updated()
+        };
+
+        self.collider_render.update(self.world.colliders());
+        frame_input
+            .screen()
+            .write(|| {
+                self.cloth_render
+                    .draw(camera, frame_input.viewport, &self.lights);
+            })
+            .render(camera, self.collider_render.objects(), &self.lights.array());
+        result
+    }
+}
+
+#[derive(Default)]
+pub struct ClothGridDemo {
+    scene: Option<ClothGridScene>,
+    scene_options: SceneOptions,
+}
+
+impl Demo for ClothGridDemo {
+    fn name(&self) -> &'static str {
+        "Cloth Grid"
+    }
+
+    fn restart(&mut self, context: &three_d::Context) {
+        self.scene = Some(ClothGridScene::new(context, self.scene_options));
+    }
+
+    fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult {
+        if let Some(scene) = &mut self.scene {
+            scene.on_frame_loop(camera, frame_input)
+        } else {
+            DemoLoopResult::not_updated()
+        }
+    }
+
+    fn show_options_gui(&mut self, ui: &mut three_d::egui::Ui) {
+        if SolverOptionsGUI::new(&mut self.scene_options.solver_options).show_ui(ui) {
+            let solver_options = self.scene_options.solver_options;
+            if let Some(scene) = &mut self.scene {
+                for solver in scene.world.solvers_mut() {
+                    solver_options.apply_live(solver);
+                }
+            }
+        }
+        if ClothOptionsGUI::new(&mut self.scene_options.cloth_options).show_ui(ui) {
+            let cloth_options = self.scene_options.cloth_options;
+            if let Some(scene) = &mut self.scene {
+                for solver in scene.world.solvers_mut() {
+                    cloth_options.apply_live(solver);
+                }
+            }
+        }
+        let options = &mut self.scene_options;
+        Slider::new(&mut options.grid_size, 1..=10)
+            .text("Grid Size")
+            .ui(ui);
+        if Slider::new(&mut options.cloth_thickness, 0.0..=0.05)
+            .text("Cloth Thickness")
+            .ui(ui)
+            .changed()
+        {
+            if let Some(scene) = &mut self.scene {
+                scene.world.set_cloth_thickness(options.cloth_thickness);
+            }
+        }
+        if let Some(scene) = &self.scene {
+            ui.label(format!(
+                "{} cloths ({} sleeping), {} particles",
+                scene.world.solvers().len(),
+                scene
+                    .world
+                    .solvers()
+                    .iter()
+                    .filter(|solver| solver.is_sleeping())
+                    .count(),
+                scene
+                    .world
+                    .solvers()
+                    .iter()
+                    .map(|solver| solver.cloth().num_particles())
+                    .sum::<usize>()
+            ));
+        }
+    }
+
+    fn apply_config(&mut self, config: &SceneConfig) {
+        let options = &mut self.scene_options;
+        options.solver_options = config
+            .solver_options(options.solver_options)
+            .expect("checked when loading the config");
+        options.cloth_options = config
+            .cloth_options(options.cloth_options)
+            .expect("checked when loading the config");
+    }
+
+    fn set_render_options(&mut self, options: RenderOptions) {
+        if let Some(scene) = &mut self.scene {
+            scene.cloth_render.set_options(options);
+        }
+    }
+
+    /// Frame the whole grid.
+    fn camera_preset(&mut self) -> CameraPreset {
+        let half_extent = 0.5 * self.scene_options.grid_size as f32 * SPACING;
+        let aabb = Aabb::new(
+            Vector3::new(-half_extent, FLOOR_HEIGHT, -half_extent),
+            Vector3::new(half_extent, 1.0, half_extent),
+        );
+        CameraPreset::frame_aabb(&aabb, Vector3::new(0.0, -0.8, -1.0))
+    }
+}
+
+fn cell_center(grid_size: usize, row: usize, column: usize) -> Vector3 {
+    let start = -0.5 * (grid_size - 1) as f32 * SPACING;
+    Vector3::new(
+        start + column as f32 * SPACING,
+        0.0,
+        start + row as f32 * SPACING,
+    )
+}
+
+/// A horizontal cloth above the post at `center`. The cloths are turned and raised by
+/// different amounts so that they do not all land alike.
+fn create_cloth(options: ClothOptions, center: Vector3, index: usize) -> (Cloth, Mesh) {
+    let resolution = options.resolution;
+    let yaw = (index * 37 % 90) as f32;
+    let height = 0.4 + 0.1 * (index * 7 % 5) as f32;
+    let transform = Isometry3 {
+        rotation: UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw.to_radians())
+            * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f32::consts::FRAC_PI_2),
+        translation: Vector3::new(center.x, height, center.z).into(),
+    };
+    let render_mesh_data =
+        GridPlaneBuilder::new(CLOTH_SIZE, CLOTH_SIZE, resolution - 1, resolution - 1)
+            .with_transform(transform)
+            .build();
+
+    let physics_cloth = ClothBuilder {
+        width: CLOTH_SIZE,
+        height: CLOTH_SIZE,
+        width_segments: resolution - 1,
+        height_segments: resolution - 1,
+        structural_spring_stiffness: options.structual_spring_stiffness,
+        shear_spring_stiffness: options.shear_spring_stiffness,
+        mass: options.mass,
+        transform,
+        stiffness_map: None,
+    }
+    .build();
+
+    (physics_cloth, render_mesh_data)
+}
+
+#[derive(Clone, Copy)]
+struct SceneOptions {
+    solver_options: SolverOptions,
+    cloth_options: ClothOptions,
+    /// Number of cloths along each side of the grid.
+    grid_size: usize,
+    /// See [`World::set_cloth_thickness`].
+    cloth_thickness: f32,
+}
+
+impl Default for SceneOptions {
+    fn default() -> Self {
+        Self {
+            solver_options: SolverOptions {
+                time_step: 1.0 / 120.0,
+                sleep: true,
+                ..Default::default()
+            },
+            cloth_options: ClothOptions {
+                structual_spring_stiffness: 40.0,
+                shear_spring_stiffness: 0.4,
+                mass: 0.2,
+                resolution: 8,
+            },
+            grid_size: 6,
+            cloth_thickness: 0.02,
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::time::Instant;
+
+use fast_mass_spring::{
+    cloth::{Cloth, ClothBuilder},
+    solver::FastMassSpringSolver,
+};
+use simulation::{math::Isometry3, Edge, FixedFrames, GridPlaneBuilder};
+use three_d::{vec3, Camera, ClearState, Event, FrameInput, MouseButton, PhysicalPoint};
+
+use crate::{
+    common::{ClothOptions, Demo, DemoLoopResult, SolverOptions},
+    config::SceneConfig,
+    gui::{ClothOptionsGUI, SolverOptionsGUI},
+    render::{ClothRender, Lights, RenderOptions},
+};
+
+pub struct CutClothScene {
+    solver: FastMassSpringSolver,
+    render: ClothRender,
+    fixed_frame_generator: FixedFrames,
+    lights: Lights,
+    indices: Vec<u32>,
+    stroke: Vec<PhysicalPoint>,
+}
+
+impl CutClothScene {
+    fn new(context: &three_d::Context, scene_options: SceneOptions) -> Self {
+        let solver_options = scene_options.solver_options;
+        let mut render = ClothRender::new(context);
+        let (cloth, mesh) = create_cloth(scene_options);
+        render.set_indices(mesh.indices());
+        render.set_vertices_from_slice(cloth.particle_positions.as_slice());
+
+        let time_step = solver_options.time_step;
+        let mut solver: FastMassSpringSolver =
+            FastMassSpringSolver::new(cloth, time_step).expect("failed to build the solver");
+        solver_options.apply_live(&mut solver);
+
+        Self {
+            solver,
+            render,
+            fixed_frame_generator: FixedFrames::new(time_step),
+            lights: Lights::new(context),
+            indices: mesh.indices().to_vec(),
+            stroke: vec![],
+        }
+    }
+
+    pub fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult {
+        self.handle_cut_stroke(camera, &frame_input.events);
+
+        let mut step_count = 0;
+        let time = Instant::now();
+        for _ in self
+            .fixed_frame_generator
+            .iter((frame_input.accumulated_time / 1000.0) as f32, 1)
+        {
+            self.solver.step();
+            step_count += 1;
+        }
+
+        let result = if step_count > 0 {
+            let cost = time.elapsed() / step_count;
+            self.render.set_cloth(self.solver.cloth());
+            DemoLoopResult {
+                updated: true,
+                step_cost: cost,
+            }
+        } else {
+            DemoLoopResult::not_updated()
+        };
+        frame_input
+            .screen()
+            .clear(ClearState::color_and_depth(0.8, 0.8, 0.8, 1.0, 1.0))
+            .write(|| {
+                self.render.draw(camera, frame_input.viewport, &self.lights);
+            });
+        result
+    }
+
+    /// Record the stroke while the right button is held and cut the cloth along it on release.
+    fn handle_cut_stroke(&mut self, camera: &Camera, events: &[Event]) {
+        for event in events {
+            match event {
+                Event::MousePress {
+                    button: MouseButton::Right,
+                    position,
+                    ..
+                } => {
+                    self.stroke.clear();
+                    self.stroke.push((*position).into());
+                }
+                Event::MouseMotion {
+                    button: Some(MouseButton::Right),
+                    position,
+                    ..
+                } if !self.stroke.is_empty() => {
+                    self.stroke.push((*position).into());
+                }
+                Event::MouseRelease {
+                    button: MouseButton::Right,
+                    ..
+                } => {
+                    self.cut_along_stroke(camera);
+                    self.stroke.clear();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn cut_along_stroke(&mut self, camera: &Camera) {
+        if self.stroke.len() < 2 {
+            return;
+        }
+        let screen_positions: Vec<PhysicalPoint> = (0..self.solver.cloth().num_particles())
+            .map(|i| {
+                let p = self.solver.cloth().get_particle_position(i);
+                camera.pixel_at_position(vec3(p.x, p.y, p.z))
+            })
+            .collect();
+        let stroke = &self.stroke;
+        let removed = self.solver.remove_springs(|spring| {
+            let p0 = screen_positions[spring.particle_index_0];
+            let p1 = screen_positions[spring.particle_index_1];
+            stroke
+                .windows(2)
+                .any(|segment| segments_intersect(p0, p1, segment[0], segment[1]))
+        });
+        if removed.is_empty() {
+            return;
+        }
+
+        // Drop the render triangles that lost one of their edges.
+        let cut_edges: Vec<Edge> = removed
+            .iter()
+            .map(|spring| Edge::new(spring.particle_index_0, spring.particle_index_1))
+            .collect();
+        let mut indices = Vec::with_capacity(self.indices.len());
+        for triangle in self.indices.chunks(3) {
+            let i0 = triangle[0] as usize;
+            let i1 = triangle[1] as usize;
+            let i2 = triangle[2] as usize;
+            let edges = [Edge::new(i0, i1), Edge::new(i1, i2), Edge::new(i2, i0)];
+            if !edges.iter().any(|edge| cut_edges.contains(edge)) {
+                indices.extend_from_slice(triangle);
+            }
+        }
+        self.indices = indices;
+        self.render.set_indices(&self.indices);
+    }
+}
+
+fn segments_intersect(
+    a0: PhysicalPoint,
+    a1: PhysicalPoint,
+    b0: PhysicalPoint,
+    b1: PhysicalPoint,
+) -> bool {
+    let cross = |o: PhysicalPoint, p: PhysicalPoint, q: PhysicalPoint| {
+        (p.x - o.x) * (q.y - o.y) - (p.y - o.y) * (q.x - o.x)
+    };
+    let d0 = cross(b0, b1, a0);
+    let d1 = cross(b0, b1, a1);
+    let d2 = cross(a0, a1, b0);
+    let d3 = cross(a0, a1, b1);
+    d0 * d1 < 0.0 && d2 * d3 < 0.0
+}
+
+#[derive(Default)]
+pub struct CutClothDemo {
+    scene: Option<CutClothScene>,
+    scene_options: SceneOptions,
+}
+
+impl Demo for CutClothDemo {
+    fn name(&self) -> &'static str {
+        "Cut Cloth"
+    }
+
+    fn restart(&mut self, context: &three_d::Context) {
+        self.scene = Some(CutClothScene::new(context, self.scene_options));
+    }
+
+    fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult {
+        if let Some(scene) = self.scene.as_mut() {
+            scene.on_frame_loop(camera, frame_input)
+        } else {
+            DemoLoopResult::not_updated()
+        }
+    }
+
+    fn show_options_gui(&mut self, ui: &mut three_d::egui::Ui) {
+        if SolverOptionsGUI::new(&mut self.scene_options.solver_options).show_ui(ui) {
+            let solver_options = self.scene_options.solver_options;
+            if let Some(solver) = self.solver_mut() {
+                solver_options.apply_live(solver);
+            }
+        }
+        if ClothOptionsGUI::new(&mut self.scene_options.cloth_options).show_ui(ui) {
+            let cloth_options = self.scene_options.cloth_options;
+            if let Some(solver) = self.solver_mut() {
+                cloth_options.apply_live(solver);
+            }
+        }
+        ui.label("Drag with the right mouse button to cut the cloth.");
+    }
+
+    fn apply_config(&mut self, config: &SceneConfig) {
+        let options = &mut self.scene_options;
+        options.solver_options = config
+            .solver_options(options.solver_options)
+            .expect("checked when loading the config");
+        options.cloth_options = config
+            .cloth_options(options.cloth_options)
+            .expect("checked when loading the config");
+    }
+
+    fn set_render_options(&mut self, options: RenderOptions) {
+        if let Some(scene) = &mut self.scene {
+            scene.render.set_options(options);
+        }
+    }
+
+    fn solver_mut(&mut self) -> Option<&mut FastMassSpringSolver> {
+        self.scene.as_mut().map(|scene| &mut scene.solver)
+    }
+}
+
+fn create_cloth(options: SceneOptions) -> (Cloth, simulation::Mesh) {
+    let cloth_options = options.cloth_options;
+    let resolution = cloth_options.resolution;
+    let cloth_size = 3.0;
+    let transform = Isometry3::translation(0.0, 0.0, 0.0);
+
+    let cloth_builder = ClothBuilder {
+        width: cloth_size,
+        height: cloth_size,
+        width_segments: resolution - 1,
+        height_segments: resolution - 1,
+        structural_spring_stiffness: cloth_options.structual_spring_stiffness,
+        shear_spring_stiffness: cloth_options.shear_spring_stiffness,
+        mass: cloth_options.mass,
+        transform,
+        stiffness_map: None,
+    };
+    let top_row = cloth_builder.top_row_indices();
+    let mut cloth = cloth_builder.build();
+    cloth.attach_particles(top_row, options.attachment_stiffness);
+
+    let render_mesh_data =
+        GridPlaneBuilder::new(cloth_size, cloth_size, resolution - 1, resolution - 1)
+            .with_transform(transform)
+            .build();
+    (cloth, render_mesh_data)
+}
+
+#[derive(Clone, Copy)]
+struct SceneOptions {
+    solver_options: SolverOptions,
+    cloth_options: ClothOptions,
+    attachment_stiffness: f32,
+}
+
+impl Default for SceneOptions {
+    fn default() -> Self {
+        Self {
+            solver_options: SolverOptions::default(),
+            cloth_options: ClothOptions {
+                structual_spring_stiffness: 100.0,
+                shear_spring_stiffness: 0.2,
+                ..Default::default()
+            },
+            attachment_stiffness: 50.0,
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::time::Instant;
+
+use fast_mass_spring::{
+    cloth::{Cloth, ClothBuilder},
+    solver::FastMassSpringSolver,
+};
+use simulation::{
+    math::{Isometry3, UnitQuaternion, Vector3},
+    Aabb, FixedFrames, GridPlaneBuilder, Mesh, TriMeshCollider,
+};
+use three_d::{
+    egui::{Slider, Widget},
+    Camera, FrameInput,
+};
+
+use crate::{
+    common::{CameraPreset, ClothOptions, Demo, DemoLoopResult, SolverOptions},
+    config::SceneConfig,
+    gui::{ClothOptionsGUI, SolverOptionsGUI},
+    render::{ClothRender, ColliderRender, Lights, RenderOptions},
+};
+
+const DEFAULT_OBJ_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/torus.obj");
+
+/// The imported mesh is scaled so that its largest extent matches this size.
+const OBSTACLE_SIZE: f32 = 2.5;
+
+pub struct DrapeClothScene {
+    solver: FastMassSpringSolver,
+    cloth_render: ClothRender,
+    fixed_frames: FixedFrames,
+    collider_render: ColliderRender,
+    lights: Lights,
+}
+
+impl DrapeClothScene {
+    fn new(context: &three_d::Context, scene_options: SceneOptions, obstacle: Mesh) -> Self {
+        let solver_options = scene_options.solver_options;
+        let mut render = ClothRender::new(context);
+        let (cloth, mesh) = create_cloth(scene_options.cloth_options);
+        render.set_indices(mesh.indices());
+
+        let mut solver: FastMassSpringSolver =
+            FastMassSpringSolver::new(cloth, solver_options.time_step)
+                .expect("failed to build the solver");
+        solver_options.apply_live(&mut solver);
+        solver.set_friction(scene_options.friction);
+        solver.add_collider(
+            TriMeshCollider::new(&obstacle, scene_options.collider_thickness),
+            Isometry3::identity(),
+        );
+
+        Self {
+            solver,
+            cloth_render: render,
+            fixed_frames: FixedFrames::new(solver_options.time_step),
+            collider_render: ColliderRender::new(context),
+            lights: Lights::new(context),
+        }
+    }
+
+    pub fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult {
+        let mut step_count = 0;
+        let time = Instant::now();
+        for _ in self
+            .fixed_frames
+            .iter((frame_input.accumulated_time / 1000.0) as f32, 1)
+        {
+            self.solver.step();
+            step_count += 1;
+        }
+
+        let result = if step_count > 0 {
+            let step_cost = time.elapsed() / step_count;
+            self.cloth_render.set_cloth(self.solver.cloth());
+            DemoLoopResult {
+                updated: true,
+                step_cost,
+            }
+        } else {
+            DemoLoopResult::not_updated()
+        };
+
+        self.collider_render.update(self.solver.colliders());
+        frame_input
+            .screen()
+            .write(|| {
+                self.cloth_render
+                    .draw(camera, frame_input.viewport, &self.lights);
+            })
+            .render(camera, self.collider_render.objects(), &self.lights.array());
+        result
+    }
+}
+
+pub struct DrapeClothDemo {
+    scene: Option<DrapeClothScene>,
+    scene_options: SceneOptions,
+    obj_path: String,
+    load_error: Option<String>,
+}
+
+impl Default for DrapeClothDemo {
+    fn default() -> Self {
+        Self {
+            scene: None,
+            scene_options: SceneOptions::default(),
+            obj_path: DEFAULT_OBJ_PATH.to_string(),
+            load_error: None,
+        }
+    }
+}
+
+impl Demo for DrapeClothDemo {
+    fn name(&self) -> &'static str {
+        "Drape Cloth On Mesh"
+    }
+
+    fn restart(&mut self, context: &three_d::Context) {
+        match Mesh::load_obj(&self.obj_path) {
+            Ok(mesh) => {
+                self.load_error = None;
+                let obstacle = normalize_mesh(&mesh, OBSTACLE_SIZE);
+                self.scene = Some(DrapeClothScene::new(context, self.scene_options, obstacle));
+            }
+            Err(err) => {
+                self.load_error = Some(err.to_string());
+                self.scene = None;
+            }
+        }
+    }
+
+    fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult {
+        if let Some(scene) = &mut self.scene {
+            scene.on_frame_loop(camera, frame_input)
+        } else {
+            DemoLoopResult::not_updated()
+        }
+    }
+
+    fn show_options_gui(&mut self, ui: &mut three_d::egui::Ui) {
+        if SolverOptionsGUI::new(&mut self.scene_options.solver_options).show_ui(ui) {
+            let solver_options = self.scene_options.solver_options;
+            if let Some(solver) = self.solver_mut() {
+                solver_options.apply_live(solver);
+            }
+        }
+        if ClothOptionsGUI::new(&mut self.scene_options.cloth_options).show_ui(ui) {
+            let cloth_options = self.scene_options.cloth_options;
+            if let Some(solver) = self.solver_mut() {
+                cloth_options.apply_live(solver);
+            }
+        }
+        Slider::new(&mut self.scene_options.friction, 0.0..=1.0)
+            .text("Friction")
+            .ui(ui);
+        Slider::new(&mut self.scene_options.collider_thickness, 0.0..=0.1)
+            .text("Collider Thickness")
+            .ui(ui);
+        ui.label("OBJ file (restart to load):");
+        ui.text_edit_singleline(&mut self.obj_path);
+        if let Some(err) = &self.load_error {
+            ui.colored_label(three_d::egui::Color32::RED, err.as_str());
+        }
+    }
+
+    fn apply_config(&mut self, config: &SceneConfig) {
+        let options = &mut self.scene_options;
+        options.solver_options = config
+            .solver_options(options.solver_options)
+            .expect("checked when loading the config");
+        options.cloth_options = config
+            .cloth_options(options.cloth_options)
+            .expect("checked when loading the config");
+    }
+
+    fn set_render_options(&mut self, options: RenderOptions) {
+        if let Some(scene) = &mut self.scene {
+            scene.cloth_render.set_options(options);
+        }
+    }
+
+    fn solver_mut(&mut self) -> Option<&mut FastMassSpringSolver> {
+        self.scene.as_mut().map(|scene| &mut scene.solver)
+    }
+
+    /// Look down at the obstacle the cloth falls on.
+    fn camera_preset(&mut self) -> CameraPreset {
+        let obstacle = Aabb::new(
+            Vector3::repeat(-0.5 * OBSTACLE_SIZE),
+            Vector3::repeat(0.5 * OBSTACLE_SIZE),
+        );
+        let aabb = match &self.scene {
+            Some(scene) => scene.solver.cloth_aabb().merged(&obstacle),
+            None => obstacle,
+        };
+        CameraPreset::frame_aabb(&aabb, Vector3::new(0.0, -0.5, -1.0))
+    }
+}
+
+/// Center the mesh at the origin and scale it so that its largest extent equals `size`.
+fn normalize_mesh(mesh: &Mesh, size: f32) -> Mesh {
+    let aabb = Aabb::from_points(mesh.vertices());
+    let center = aabb.center();
+    let scale = size / aabb.extents().max().max(f32::EPSILON);
+    let vertices = mesh
+        .vertices()
+        .iter()
+        .map(|v| (v - center) * scale)
+        .collect();
+    Mesh::new(vertices, mesh.indices().to_vec())
+}
+
+fn create_cloth(options: ClothOptions) -> (Cloth, Mesh) {
+    let resolution = options.resolution;
+    let cloth_size = 4.0;
+    let transform = Isometry3 {
+        rotation: UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f32::consts::PI / 2.0),
+        translation: Vector3::new(0.0, 0.5 * OBSTACLE_SIZE + 0.3, 0.0).into(),
+    };
+    let render_mesh_data =
+        GridPlaneBuilder::new(cloth_size, cloth_size, resolution - 1, resolution - 1)
+            .with_transform(transform)
+            .build();
+
+    let physics_cloth = ClothBuilder {
+        width: cloth_size,
+        height: cloth_size,
+        width_segments: resolution - 1,
+        height_segments: resolution - 1,
+        structural_spring_stiffness: options.structual_spring_stiffness,
+        shear_spring_stiffness: options.shear_spring_stiffness,
+        mass: options.mass,
+        transform,
+        stiffness_map: None,
+    }
+    .build();
+
+    (physics_cloth, render_mesh_data)
+}
+
+#[derive(Clone, Copy)]
+struct SceneOptions {
+    solver_options: SolverOptions,
+    cloth_options: ClothOptions,
+    friction: f32,
+    collider_thickness: f32,
+}
+
+impl Default for SceneOptions {
+    fn default() -> Self {
+        Self {
+            solver_options: SolverOptions {
+                time_step: 1.0 / 120.0,
+                ..Default::default()
+            },
+            cloth_options: ClothOptions {
+                structual_spring_stiffness: 80.0,
+                shear_spring_stiffness: 0.2,
+                resolution: 30,
+                ..Default::default()
+            },
+            friction: 0.3,
+            collider_thickness: 0.02,
+        }
+    }
+}
@@ -4,23 +4,27 @@ use fast_mass_spring::{
     cloth::{Cloth, ClothBuilder},
     solver::FastMassSpringSolver,
 };
-use simulation::{math::Isometry3, FixedFrames, GridPlaneBuilder, SphereCollider};
+use simulation::{
+    math::{Isometry3, Vector3},
+    Aabb, FixedFrames, GridPlaneBuilder, SphereCollider,
+};
 use three_d::{
-    AmbientLight, Camera, CpuMaterial, CpuMesh, DirectionalLight, FrameInput, Gm, PhysicalMaterial,
-    Srgba,
+    egui::{Slider, Widget},
+    Camera, FrameInput,
 };
 
 use crate::{
-    common::{ClothOptions, Demo, DemoLoopResult, SolverOptions},
+    common::{CameraPreset, ClothOptions, Demo, DemoLoopResult, SolverOptions},
+    config::SceneConfig,
     gui::{ClothOptionsGUI, SolverOptionsGUI},
-    render::ClothRender,
+    render::{ClothRender, ColliderRender, Lights, RenderOptions},
 };
 
 pub struct DropClothScene {
     solver: FastMassSpringSolver,
     cloth_render: ClothRender,
     fixed_frames: FixedFrames,
-    sphere_render: Gm<three_d::Mesh, PhysicalMaterial>,
+    collider_render: ColliderRender,
     lights: Lights,
 }
 
@@ -28,15 +32,18 @@ impl DropClothScene {
     fn new(context: &three_d::Context, scene_options: SceneOptions) -> Self {
         let solver_options = scene_options.solver_options;
         let mut render = ClothRender::new(context);
-        let (cloth, mesh) = create_cloth(scene_options.cloth_options);
+        let (mut cloth, mesh) = create_cloth(scene_options.cloth_options);
+        if scene_options.area_stiffness > 0.0 {
+            cloth.add_area_constraints(scene_options.area_stiffness);
+        }
         render.set_indices(mesh.indices());
 
         let mut solver: FastMassSpringSolver =
-            FastMassSpringSolver::new(cloth, solver_options.time_step);
-        solver.set_num_iterations(solver_options.num_iterations);
-        solver.set_gravity(solver_options.gravity);
+            FastMassSpringSolver::new(cloth, solver_options.time_step)
+                .expect("failed to build the solver");
+        solver_options.apply_live(&mut solver);
         solver.add_collider(
-            SphereCollider { radius: 1.0 },
+            SphereCollider::new(1.0),
             simulation::math::Isometry3::identity(),
         );
 
@@ -46,7 +53,7 @@ impl DropClothScene {
             solver,
             cloth_render: render,
             fixed_frames: fixed_frame_generator,
-            sphere_render: create_sphere_render(context),
+            collider_render: ColliderRender::new(context),
             lights: Lights::new(context),
         }
     }
@@ -64,8 +71,7 @@ impl DropClothScene {
 
         let result = if step_count > 0 {
             let step_cost = time.elapsed() / step_count;
-            self.cloth_render
-                .set_vertices_from_slice(self.solver.cloth().particle_positions.as_slice());
+            self.cloth_render.set_cloth(self.solver.cloth());
             DemoLoopResult {
                 updated: true,
                 step_cost,
@@ -74,12 +80,14 @@ impl DropClothScene {
             DemoLoopResult::not_updated()
         };
 
+        self.collider_render.update(self.solver.colliders());
         frame_input
             .screen()
             .write(|| {
-                self.cloth_render.draw(camera, frame_input.viewport);
+                self.cloth_render
+                    .draw(camera, frame_input.viewport, &self.lights);
             })
-            .render(camera, [&self.sphere_render], &self.lights.array());
+            .render(camera, self.collider_render.objects(), &self.lights.array());
         result
     }
 }
@@ -108,8 +116,51 @@ impl Demo for DropClothDemo {
     }
 
     fn show_options_gui(&mut self, ui: &mut three_d::egui::Ui) {
-        SolverOptionsGUI::new(&mut self.scene_options.solver_options).show_ui(ui);
-        ClothOptionsGUI::new(&mut self.scene_options.cloth_options).show_ui(ui)
+        if SolverOptionsGUI::new(&mut self.scene_options.solver_options).show_ui(ui) {
+            let solver_options = self.scene_options.solver_options;
+            if let Some(solver) = self.solver_mut() {
+                solver_options.apply_live(solver);
+            }
+        }
+        if ClothOptionsGUI::new(&mut self.scene_options.cloth_options).show_ui(ui) {
+            let cloth_options = self.scene_options.cloth_options;
+            if let Some(solver) = self.solver_mut() {
+                cloth_options.apply_live(solver);
+            }
+        }
+        Slider::new(&mut self.scene_options.area_stiffness, 0.0..=500.0)
+            .text("Area Stiffness (on restart)")
+            .ui(ui);
+    }
+
+    fn apply_config(&mut self, config: &SceneConfig) {
+        let options = &mut self.scene_options;
+        options.solver_options = config
+            .solver_options(options.solver_options)
+            .expect("checked when loading the config");
+        options.cloth_options = config
+            .cloth_options(options.cloth_options)
+            .expect("checked when loading the config");
+    }
+
+    fn set_render_options(&mut self, options: RenderOptions) {
+        if let Some(scene) = &mut self.scene {
+            scene.cloth_render.set_options(options);
+        }
+    }
+
+    fn solver_mut(&mut self) -> Option<&mut FastMassSpringSolver> {
+        self.scene.as_mut().map(|scene| &mut scene.solver)
+    }
+
+    /// Look down at the obstacle the cloth falls on.
+    fn camera_preset(&mut self) -> CameraPreset {
+        let obstacle = Aabb::new(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(1.0, 1.0, 1.0));
+        let aabb = match &self.scene {
+            Some(scene) => scene.solver.cloth_aabb().merged(&obstacle),
+            None => obstacle,
+        };
+        CameraPreset::frame_aabb(&aabb, Vector3::new(0.0, -0.5, -1.0))
     }
 }
 
@@ -129,39 +180,27 @@ fn create_cloth(options: ClothOptions) -> (Cloth, simulation::Mesh) {
             .build();
 
     let physics_cloth = ClothBuilder {
-        size: cloth_size,
-        resolution,
+        width: cloth_size,
+        height: cloth_size,
+        width_segments: resolution - 1,
+        height_segments: resolution - 1,
         structural_spring_stiffness: options.structual_spring_stiffness,
         shear_spring_stiffness: options.shear_spring_stiffness,
         mass: options.mass,
         transform,
+        stiffness_map: None,
     }
     .build();
 
     (physics_cloth, render_mesh_data)
 }
 
-fn create_sphere_render(context: &three_d::Context) -> Gm<three_d::Mesh, PhysicalMaterial> {
-    use three_d::Mat4;
-    let mut sphere = CpuMesh::sphere(64);
-    sphere.transform(&Mat4::from_scale(0.95)).unwrap();
-    Gm::new(
-        three_d::Mesh::new(context, &sphere),
-        PhysicalMaterial::new_opaque(
-            context,
-            &CpuMaterial {
-                albedo: Srgba::BLUE,
-                metallic: 1.0,
-                ..Default::default()
-            },
-        ),
-    )
-}
-
 #[derive(Clone, Copy)]
 struct SceneOptions {
     solver_options: SolverOptions,
     cloth_options: ClothOptions,
+    /// Stiffness of the area constraints of the triangles, zero for none.
+    area_stiffness: f32,
 }
 
 impl Default for SceneOptions {
@@ -176,29 +215,7 @@ impl Default for SceneOptions {
                 shear_spring_stiffness: 0.2,
                 ..Default::default()
             },
+            area_stiffness: 0.0,
         }
     }
 }
-
-struct Lights {
-    ambient: AmbientLight,
-    directional: DirectionalLight,
-}
-
-impl Lights {
-    pub fn new(context: &three_d::Context) -> Self {
-        Self {
-            ambient: AmbientLight::new(context, 0.4, Srgba::WHITE),
-            directional: DirectionalLight::new(
-                context,
-                2.0,
-                Srgba::WHITE,
-                &three_d::vec3(-1.0, -1.0, -1.0),
-            ),
-        }
-    }
-
-    pub fn array(&self) -> [&dyn three_d::Light; 2] {
-        [&self.ambient, &self.directional]
-    }
-}
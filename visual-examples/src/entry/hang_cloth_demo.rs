@@ -12,14 +12,18 @@ use three_d::{
 
 use crate::{
     common::{ClothOptions, Demo, DemoLoopResult, SolverOptions},
+    config::SceneConfig,
     gui::{ClothOptionsGUI, SolverOptionsGUI},
-    render::ClothRender,
+    render::{ClothRender, Lights, RenderOptions},
 };
 
 pub struct HangClothScene {
     solver: FastMassSpringSolver,
     render: ClothRender,
     fixed_frame_generator: FixedFrames,
+    lights: Lights,
+    /// The attachments of the corners come first, before those of a scene config.
+    num_corner_attachments: usize,
 }
 
 impl HangClothScene {
@@ -31,16 +35,19 @@ impl HangClothScene {
         render.set_vertices_from_slice(cloth.particle_positions.as_slice());
 
         let time_step = solver_options.time_step;
-        let mut solver: FastMassSpringSolver = FastMassSpringSolver::new(cloth, time_step);
-        solver.set_num_iterations(solver_options.num_iterations);
-        solver.set_gravity(solver_options.gravity);
+        let mut solver: FastMassSpringSolver =
+            FastMassSpringSolver::new(cloth, time_step).expect("failed to build the solver");
+        solver_options.apply_live(&mut solver);
 
         let fixed_frame_generator = FixedFrames::new(time_step);
+        let num_corner_attachments = solver.cloth().attachments.len();
 
         Self {
             solver,
             render,
             fixed_frame_generator,
+            lights: Lights::new(context),
+            num_corner_attachments,
         }
     }
 
@@ -57,8 +64,7 @@ impl HangClothScene {
 
         let result = if step_count > 0 {
             let cost = time.elapsed() / step_count;
-            self.render
-                .set_vertices_from_slice(self.solver.cloth().particle_positions.as_slice());
+            self.render.set_cloth(self.solver.cloth());
             DemoLoopResult {
                 updated: true,
                 step_cost: cost,
@@ -70,7 +76,7 @@ impl HangClothScene {
             .screen()
             .clear(ClearState::color_and_depth(0.8, 0.8, 0.8, 1.0, 1.0))
             .write(|| {
-                self.render.draw(camera, frame_input.viewport);
+                self.render.draw(camera, frame_input.viewport, &self.lights);
             });
         result
     }
@@ -100,13 +106,60 @@ impl Demo for HangClothDemo {
     }
 
     fn show_options_gui(&mut self, ui: &mut three_d::egui::Ui) {
-        SolverOptionsGUI::new(&mut self.scene_options.solver_options).show_ui(ui);
-        ClothOptionsGUI::new(&mut self.scene_options.cloth_options).show_ui(ui);
-        Slider::new(&mut self.scene_options.attachment_stiffness, 0.1..=100.0)
+        if SolverOptionsGUI::new(&mut self.scene_options.solver_options).show_ui(ui) {
+            let solver_options = self.scene_options.solver_options;
+            if let Some(solver) = self.solver_mut() {
+                solver_options.apply_live(solver);
+            }
+        }
+        if ClothOptionsGUI::new(&mut self.scene_options.cloth_options).show_ui(ui) {
+            let cloth_options = self.scene_options.cloth_options;
+            if let Some(solver) = self.solver_mut() {
+                cloth_options.apply_live(solver);
+            }
+        }
+        let attachment_stiffness = &mut self.scene_options.attachment_stiffness;
+        if Slider::new(attachment_stiffness, 0.1..=100.0)
             .text("Attachment Stiffness")
-            .ui(ui);
+            .ui(ui)
+            .changed()
+        {
+            let stiffness = *attachment_stiffness;
+            if let Some(scene) = &mut self.scene {
+                for index in 0..scene.num_corner_attachments {
+                    scene
+                        .solver
+                        .update_attachment_stiffness(index, stiffness)
+                        .expect("the stiffness slider is in range");
+                }
+            }
+        }
         ui.checkbox(&mut self.scene_options.fix_left_top, "Fix Left Top");
         ui.checkbox(&mut self.scene_options.fix_right_top, "Fix Right Top");
+        ui.checkbox(
+            &mut self.scene_options.long_range_attachments,
+            "Long Range Attachments",
+        );
+    }
+
+    fn apply_config(&mut self, config: &SceneConfig) {
+        let options = &mut self.scene_options;
+        options.solver_options = config
+            .solver_options(options.solver_options)
+            .expect("checked when loading the config");
+        options.cloth_options = config
+            .cloth_options(options.cloth_options)
+            .expect("checked when loading the config");
+    }
+
+    fn set_render_options(&mut self, options: RenderOptions) {
+        if let Some(scene) = &mut self.scene {
+            scene.render.set_options(options);
+        }
+    }
+
+    fn solver_mut(&mut self) -> Option<&mut FastMassSpringSolver> {
+        self.scene.as_mut().map(|scene| &mut scene.solver)
     }
 }
 
@@ -117,12 +170,15 @@ fn create_cloth(options: SceneOptions) -> (Cloth, simulation::Mesh) {
     let transform = Isometry3::translation(0.0, 0.0, 0.0);
 
     let physics_cloth_builder: ClothBuilder = ClothBuilder {
-        size: cloth_size,
-        resolution,
+        width: cloth_size,
+        height: cloth_size,
+        width_segments: resolution - 1,
+        height_segments: resolution - 1,
         structural_spring_stiffness: cloth_options.structual_spring_stiffness,
         shear_spring_stiffness: cloth_options.shear_spring_stiffness,
         mass: cloth_options.mass,
         transform,
+        stiffness_map: None,
     };
 
     let top_left = physics_cloth_builder.top_left_vertex_index();
@@ -146,6 +202,10 @@ fn create_cloth(options: SceneOptions) -> (Cloth, simulation::Mesh) {
         }]);
     }
 
+    if options.long_range_attachments {
+        cloth.add_long_range_attachments(options.attachment_stiffness);
+    }
+
     let render_mesh_data =
         GridPlaneBuilder::new(cloth_size, cloth_size, resolution - 1, resolution - 1)
             .with_transform(transform)
@@ -159,6 +219,8 @@ struct SceneOptions {
     cloth_options: ClothOptions,
     fix_left_top: bool,
     fix_right_top: bool,
+    /// Limit the stretching away from the fixed corners.
+    long_range_attachments: bool,
     attachment_stiffness: f32,
 }
 
@@ -173,6 +235,7 @@ impl Default for SceneOptions {
             },
             fix_left_top: true,
             fix_right_top: true,
+            long_range_attachments: false,
             attachment_stiffness: 50.0,
         }
     }
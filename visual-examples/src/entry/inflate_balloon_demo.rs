@@ -0,0 +1,288 @@
+use std::time::Instant;
+
+use fast_mass_spring::{
+    cloth::{Cloth, ClothFromMeshBuilder},
+    solver::{ColliderHandle, FastMassSpringSolver},
+};
+use simulation::{
+    math::{Isometry3, Vector3},
+    Aabb, BoxCollider, FixedFrames, IcosphereBuilder, Mesh, SphereCollider,
+};
+use three_d::{
+    egui::{Slider, Widget},
+    Camera, FrameInput,
+};
+
+use crate::{
+    common::{CameraPreset, Demo, DemoLoopResult, SolverOptions},
+    config::SceneConfig,
+    gui::SolverOptionsGUI,
+    render::{ClothRender, ColliderRender, Lights, RenderOptions},
+};
+
+const BALLOON_RADIUS: f32 = 0.8;
+/// The floor is the top of a large sphere, flat enough under the balloon.
+const FLOOR_RADIUS: f32 = 20.0;
+const PRESS_HALF_EXTENTS: [f32; 3] = [2.0, 0.1, 2.0];
+
+pub struct InflateBalloonScene {
+    solver: FastMassSpringSolver,
+    cloth_render: ClothRender,
+    fixed_frames: FixedFrames,
+    collider_render: ColliderRender,
+    lights: Lights,
+    rest_volume: f32,
+    inflation: f32,
+    inflation_time: f32,
+    /// Lowered onto the inflated balloon to squeeze it against the floor.
+    press: Option<ColliderHandle>,
+    squeeze: f32,
+}
+
+impl InflateBalloonScene {
+    fn new(context: &three_d::Context, scene_options: SceneOptions) -> Self {
+        let solver_options = scene_options.solver_options;
+        let mut render = ClothRender::new(context);
+        let (cloth, mesh) = create_balloon(scene_options);
+        render.set_indices(mesh.indices());
+        let rest_volume = cloth.volume_constraints[0].target_volume;
+
+        let mut solver: FastMassSpringSolver =
+            FastMassSpringSolver::new(cloth, solver_options.time_step)
+                .expect("failed to build the solver");
+        solver_options.apply_live(&mut solver);
+        solver.set_friction(0.5);
+        solver.add_collider(
+            SphereCollider::new(FLOOR_RADIUS),
+            Isometry3::translation(0.0, -FLOOR_RADIUS - BALLOON_RADIUS, 0.0),
+        );
+        let press = (scene_options.squeeze > 0.0).then(|| {
+            let height = press_height(scene_options.inflation, scene_options.squeeze, 0.0);
+            solver.add_collider(
+                BoxCollider::new(Vector3::from(PRESS_HALF_EXTENTS)),
+                Isometry3::translation(0.0, height, 0.0),
+            )
+        });
+
+        Self {
+            solver,
+            cloth_render: render,
+            fixed_frames: FixedFrames::new(solver_options.time_step),
+            collider_render: ColliderRender::new(context),
+            lights: Lights::new(context),
+            rest_volume,
+            inflation: scene_options.inflation,
+            inflation_time: scene_options.inflation_time,
+            press,
+            squeeze: scene_options.squeeze,
+        }
+    }
+
+    pub fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult {
+        let mut step_count = 0;
+        let time = Instant::now();
+        for _ in self
+            .fixed_frames
+            .iter((frame_input.accumulated_time / 1000.0) as f32, 1)
+        {
+            // pump the balloon up to its final volume over the inflation time
+            let progress = (self.solver.time() / self.inflation_time).min(1.0);
+            let target_volume = self.rest_volume * (1.0 + (self.inflation - 1.0) * progress);
+            self.solver.set_target_volume(0, target_volume);
+            // then lower the press over as long again
+            if let Some(press) = self.press {
+                if self.solver.collider(press).is_some() {
+                    let progress = (self.solver.time() / self.inflation_time - 1.0).clamp(0.0, 1.0);
+                    let height = press_height(self.inflation, self.squeeze, progress);
+                    self.solver
+                        .set_collider_transform(press, Isometry3::translation(0.0, height, 0.0));
+                }
+            }
+            self.solver.step();
+            step_count += 1;
+        }
+
+        let result = if step_count > 0 {
+            let step_cost = time.elapsed() / step_count;
+            self.cloth_render.set_cloth(self.solver.cloth());
+            DemoLoopResult {
+                updated: true,
+                step_cost,
+            }
+        } else {
+            DemoLoopResult::not_updated()
+        };
+
+        self.collider_render.update(self.solver.colliders());
+        frame_input
+            .screen()
+            .write(|| {
+                self.cloth_render
+                    .draw(camera, frame_input.viewport, &self.lights);
+            })
+            .render(camera, self.collider_render.objects(), &self.lights.array());
+        result
+    }
+}
+
+#[derive(Default)]
+pub struct InflateBalloonDemo {
+    scene: Option<InflateBalloonScene>,
+    scene_options: SceneOptions,
+}
+
+impl Demo for InflateBalloonDemo {
+    fn name(&self) -> &'static str {
+        "Inflate Balloon"
+    }
+
+    fn restart(&mut self, context: &three_d::Context) {
+        self.scene = Some(InflateBalloonScene::new(context, self.scene_options));
+    }
+
+    fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult {
+        if let Some(scene) = &mut self.scene {
+            scene.on_frame_loop(camera, frame_input)
+        } else {
+            DemoLoopResult::not_updated()
+        }
+    }
+
+    fn show_options_gui(&mut self, ui: &mut three_d::egui::Ui) {
+        if SolverOptionsGUI::new(&mut self.scene_options.solver_options).show_ui(ui) {
+            let solver_options = self.scene_options.solver_options;
+            if let Some(solver) = self.solver_mut() {
+                solver_options.apply_live(solver);
+            }
+        }
+        let options = &mut self.scene_options;
+        Slider::new(&mut options.subdivisions, 1..=3)
+            .text("Subdivisions")
+            .ui(ui);
+        Slider::new(&mut options.spring_stiffness, 1.0..=200.0)
+            .text("Spring Stiffness")
+            .ui(ui);
+        Slider::new(&mut options.bend_stiffness, 0.0..=100.0)
+            .text("Bend Stiffness")
+            .ui(ui);
+        Slider::new(&mut options.volume_stiffness, 1.0..=500.0)
+            .text("Volume Stiffness")
+            .ui(ui);
+        Slider::new(&mut options.squeeze, 0.0..=0.6)
+            .text("Squeeze")
+            .ui(ui);
+        if Slider::new(&mut options.inflation, 0.2..=3.0)
+            .text("Inflation")
+            .ui(ui)
+            .changed()
+        {
+            if let Some(scene) = &mut self.scene {
+                scene.inflation = options.inflation;
+            }
+        }
+        if Slider::new(&mut options.inflation_time, 0.5..=10.0)
+            .text("Inflation Time")
+            .ui(ui)
+            .changed()
+        {
+            if let Some(scene) = &mut self.scene {
+                scene.inflation_time = options.inflation_time;
+            }
+        }
+        if let Some(scene) = &self.scene {
+            let cloth = scene.solver.cloth();
+            let volume = &cloth.volume_constraints[0];
+            ui.label(format!(
+                "volume {:.3}, target {:.3}",
+                volume.volume(&cloth.particle_positions),
+                volume.target_volume
+            ));
+        }
+    }
+
+    fn apply_config(&mut self, config: &SceneConfig) {
+        let options = &mut self.scene_options;
+        options.solver_options = config
+            .solver_options(options.solver_options)
+            .expect("checked when loading the config");
+    }
+
+    fn set_render_options(&mut self, options: RenderOptions) {
+        if let Some(scene) = &mut self.scene {
+            scene.cloth_render.set_options(options);
+        }
+    }
+
+    fn solver_mut(&mut self) -> Option<&mut FastMassSpringSolver> {
+        self.scene.as_mut().map(|scene| &mut scene.solver)
+    }
+
+    /// Leave room for the balloon to grow.
+    fn camera_preset(&mut self) -> CameraPreset {
+        let extent = BALLOON_RADIUS * self.scene_options.inflation.max(1.0).cbrt();
+        let aabb = Aabb::new(
+            Vector3::new(-extent, -BALLOON_RADIUS, -extent),
+            Vector3::new(extent, 2.0 * extent - BALLOON_RADIUS, extent),
+        );
+        CameraPreset::frame_aabb(&aabb, Vector3::new(0.0, -0.3, -1.0))
+    }
+}
+
+/// Height of the center of the press, from above the inflated balloon at `progress` zero to
+/// `squeeze` of its height below that at one.
+fn press_height(inflation: f32, squeeze: f32, progress: f32) -> f32 {
+    let diameter = 2.0 * BALLOON_RADIUS * inflation.cbrt();
+    let top = diameter - BALLOON_RADIUS;
+    let bottom = top + 0.1 - (0.1 + squeeze * diameter) * progress;
+    bottom + PRESS_HALF_EXTENTS[1]
+}
+
+fn create_balloon(options: SceneOptions) -> (Cloth, Mesh) {
+    let mesh = IcosphereBuilder::new(BALLOON_RADIUS, options.subdivisions).build();
+    let mut cloth = ClothFromMeshBuilder {
+        mesh: &mesh,
+        mass: options.mass,
+        spring_stiffness: options.spring_stiffness,
+        bend_stiffness: options.bend_stiffness,
+        stiffness_multipliers: None,
+    }
+    .build();
+    cloth.add_volume_constraint(options.volume_stiffness);
+    (cloth, mesh)
+}
+
+#[derive(Clone, Copy)]
+struct SceneOptions {
+    solver_options: SolverOptions,
+    subdivisions: usize,
+    mass: f32,
+    spring_stiffness: f32,
+    bend_stiffness: f32,
+    volume_stiffness: f32,
+    /// The final volume of the balloon, relative to the volume of the uninflated sphere.
+    inflation: f32,
+    /// Seconds until the balloon reaches its final volume.
+    inflation_time: f32,
+    /// Fraction of the height of the inflated balloon the press squeezes it by, zero for no
+    /// press.
+    squeeze: f32,
+}
+
+impl Default for SceneOptions {
+    fn default() -> Self {
+        Self {
+            solver_options: SolverOptions {
+                time_step: 1.0 / 120.0,
+                ..Default::default()
+            },
+            subdivisions: 2,
+            mass: 1.0,
+            spring_stiffness: 40.0,
+            bend_stiffness: 4.0,
+            volume_stiffness: 100.0,
+            inflation: 2.0,
+            inflation_time: 3.0,
+            squeeze: 0.0,
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::time::Instant;
+
+use fast_mass_spring::{
+    cloth::{Cloth, ClothBuilder},
+    solver::{ColliderHandle, FastMassSpringSolver},
+};
+use simulation::{math::Isometry3, FixedFrames, GridPlaneBuilder, SphereCollider};
+use three_d::{
+    egui::{Slider, Widget},
+    Camera, FrameInput,
+};
+
+use crate::{
+    common::{ClothOptions, Demo, DemoLoopResult, SolverOptions},
+    config::SceneConfig,
+    gui::{ClothOptionsGUI, SolverOptionsGUI},
+    render::{ClothRender, ColliderRender, Lights, RenderOptions},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MotionPath {
+    /// Move back and forth through the cloth.
+    Oscillate,
+    /// Circle around the vertical axis, crossing the cloth twice per turn.
+    Circle,
+}
+
+impl MotionPath {
+    fn position(&self, time: f32, options: &SceneOptions) -> simulation::math::Vector3 {
+        let phase = std::f32::consts::TAU * options.frequency * time;
+        let amplitude = options.amplitude;
+        match self {
+            MotionPath::Oscillate => {
+                simulation::math::Vector3::new(0.0, -0.3, amplitude * phase.sin())
+            }
+            MotionPath::Circle => simulation::math::Vector3::new(
+                amplitude * phase.cos(),
+                -0.3,
+                amplitude * phase.sin(),
+            ),
+        }
+    }
+}
+
+pub struct MovingColliderScene {
+    solver: FastMassSpringSolver,
+    cloth_render: ClothRender,
+    fixed_frames: FixedFrames,
+    sphere: ColliderHandle,
+    collider_render: ColliderRender,
+    lights: Lights,
+    scene_options: SceneOptions,
+    start_time: Option<f32>,
+}
+
+impl MovingColliderScene {
+    fn new(context: &three_d::Context, scene_options: SceneOptions) -> Self {
+        let solver_options = scene_options.solver_options;
+        let mut render = ClothRender::new(context);
+        let (cloth, mesh) = create_cloth(scene_options);
+        render.set_indices(mesh.indices());
+        render.set_vertices_from_slice(cloth.particle_positions.as_slice());
+
+        let mut solver: FastMassSpringSolver =
+            FastMassSpringSolver::new(cloth, solver_options.time_step)
+                .expect("failed to build the solver");
+        solver_options.apply_live(&mut solver);
+        solver.set_friction(scene_options.friction);
+        let start = scene_options.path.position(0.0, &scene_options);
+        let sphere = solver.add_collider(
+            SphereCollider::new(scene_options.sphere_radius),
+            Isometry3::translation(start.x, start.y, start.z),
+        );
+
+        Self {
+            solver,
+            cloth_render: render,
+            fixed_frames: FixedFrames::new(solver_options.time_step),
+            sphere,
+            collider_render: ColliderRender::new(context),
+            lights: Lights::new(context),
+            scene_options,
+            start_time: None,
+        }
+    }
+
+    pub fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult {
+        let mut step_count = 0;
+        let time = Instant::now();
+        for simulation_time in self
+            .fixed_frames
+            .iter((frame_input.accumulated_time / 1000.0) as f32, 1)
+        {
+            let start_time = *self.start_time.get_or_insert(simulation_time);
+            let position = self
+                .scene_options
+                .path
+                .position(simulation_time - start_time, &self.scene_options);
+            // the sphere may have been removed in the collider editor
+            if self.solver.collider(self.sphere).is_some() {
+                self.solver.set_collider_transform(
+                    self.sphere,
+                    Isometry3::translation(position.x, position.y, position.z),
+                );
+            }
+            self.solver.step();
+            step_count += 1;
+        }
+
+        let result = if step_count > 0 {
+            let step_cost = time.elapsed() / step_count;
+            self.cloth_render.set_cloth(self.solver.cloth());
+            DemoLoopResult {
+                updated: true,
+                step_cost,
+            }
+        } else {
+            DemoLoopResult::not_updated()
+        };
+
+        self.collider_render.update(self.solver.colliders());
+        frame_input
+            .screen()
+            .write(|| {
+                self.cloth_render
+                    .draw(camera, frame_input.viewport, &self.lights);
+            })
+            .render(camera, self.collider_render.objects(), &self.lights.array());
+        result
+    }
+}
+
+#[derive(Default)]
+pub struct MovingColliderDemo {
+    scene: Option<MovingColliderScene>,
+    scene_options: SceneOptions,
+}
+
+impl Demo for MovingColliderDemo {
+    fn name(&self) -> &'static str {
+        "Moving Collider"
+    }
+
+    fn restart(&mut self, context: &three_d::Context) {
+        self.scene = Some(MovingColliderScene::new(context, self.scene_options));
+    }
+
+    fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult {
+        if let Some(scene) = &mut self.scene {
+            scene.on_frame_loop(camera, frame_input)
+        } else {
+            DemoLoopResult::not_updated()
+        }
+    }
+
+    fn show_options_gui(&mut self, ui: &mut three_d::egui::Ui) {
+        if SolverOptionsGUI::new(&mut self.scene_options.solver_options).show_ui(ui) {
+            let solver_options = self.scene_options.solver_options;
+            if let Some(solver) = self.solver_mut() {
+                solver_options.apply_live(solver);
+            }
+        }
+        if ClothOptionsGUI::new(&mut self.scene_options.cloth_options).show_ui(ui) {
+            let cloth_options = self.scene_options.cloth_options;
+            if let Some(solver) = self.solver_mut() {
+                cloth_options.apply_live(solver);
+            }
+        }
+        let options = &mut self.scene_options;
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut options.path, MotionPath::Oscillate, "Oscillate");
+            ui.radio_value(&mut options.path, MotionPath::Circle, "Circle");
+        });
+        Slider::new(&mut options.amplitude, 0.0..=3.0)
+            .text("Amplitude")
+            .ui(ui);
+        Slider::new(&mut options.frequency, 0.0..=2.0)
+            .text("Frequency")
+            .ui(ui);
+        Slider::new(&mut options.friction, 0.0..=1.0)
+            .text("Friction")
+            .ui(ui);
+
+        // the path can be changed while running
+        if let Some(scene) = &mut self.scene {
+            scene.scene_options.path = options.path;
+            scene.scene_options.amplitude = options.amplitude;
+            scene.scene_options.frequency = options.frequency;
+            scene.solver.set_friction(options.friction);
+        }
+    }
+
+    fn apply_config(&mut self, config: &SceneConfig) {
+        let options = &mut self.scene_options;
+        options.solver_options = config
+            .solver_options(options.solver_options)
+            .expect("checked when loading the config");
+        options.cloth_options = config
+            .cloth_options(options.cloth_options)
+            .expect("checked when loading the config");
+    }
+
+    fn set_render_options(&mut self, options: RenderOptions) {
+        if let Some(scene) = &mut self.scene {
+            scene.cloth_render.set_options(options);
+        }
+    }
+
+    fn solver_mut(&mut self) -> Option<&mut FastMassSpringSolver> {
+        self.scene.as_mut().map(|scene| &mut scene.solver)
+    }
+}
+
+fn create_cloth(options: SceneOptions) -> (Cloth, simulation::Mesh) {
+    let cloth_options = options.cloth_options;
+    let resolution = cloth_options.resolution;
+    let cloth_size = 3.0;
+    let transform = Isometry3::translation(0.0, 0.0, 0.0);
+
+    let cloth_builder = ClothBuilder {
+        width: cloth_size,
+        height: cloth_size,
+        width_segments: resolution - 1,
+        height_segments: resolution - 1,
+        structural_spring_stiffness: cloth_options.structual_spring_stiffness,
+        shear_spring_stiffness: cloth_options.shear_spring_stiffness,
+        mass: cloth_options.mass,
+        transform,
+        stiffness_map: None,
+    };
+    let top_row = cloth_builder.top_row_indices();
+    let mut cloth = cloth_builder.build();
+    cloth.attach_particles(top_row, options.attachment_stiffness);
+
+    let render_mesh_data =
+        GridPlaneBuilder::new(cloth_size, cloth_size, resolution - 1, resolution - 1)
+            .with_transform(transform)
+            .build();
+    (cloth, render_mesh_data)
+}
+
+#[derive(Clone, Copy)]
+struct SceneOptions {
+    solver_options: SolverOptions,
+    cloth_options: ClothOptions,
+    attachment_stiffness: f32,
+    sphere_radius: f32,
+    path: MotionPath,
+    amplitude: f32,
+    frequency: f32,
+    friction: f32,
+}
+
+impl Default for SceneOptions {
+    fn default() -> Self {
+        Self {
+            solver_options: SolverOptions {
+                time_step: 1.0 / 120.0,
+                ..Default::default()
+            },
+            cloth_options: ClothOptions {
+                structual_spring_stiffness: 100.0,
+                shear_spring_stiffness: 0.2,
+                ..Default::default()
+            },
+            attachment_stiffness: 50.0,
+            sphere_radius: 0.5,
+            path: MotionPath::Oscillate,
+            amplitude: 1.5,
+            frequency: 0.3,
+            friction: 0.3,
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use std::time::Instant;
+
+use fast_mass_spring::{
+    cloth::{Cloth, ClothBuilder},
+    solver::{ColliderHandle, FastMassSpringSolver},
+};
+use simulation::{
+    math::{Isometry3, UnitQuaternion, Vector3},
+    Aabb, FixedFrames, GridPlaneBuilder, Mesh, SphereCollider,
+};
+use three_d::{
+    egui::{Slider, Widget},
+    Camera, FrameInput,
+};
+
+use crate::{
+    common::{CameraPreset, ClothOptions, Demo, DemoLoopResult, SolverOptions},
+    config::SceneConfig,
+    gui::{ClothOptionsGUI, SolverOptionsGUI},
+    render::{ClothRender, ColliderRender, Lights, RenderOptions},
+};
+
+const CLOTH_SIZE: f32 = 4.0;
+/// Spheres falling below this are dropped again from the top.
+const RESPAWN_HEIGHT: f32 = -5.0;
+
+/// A sphere collider moved by gravity and by the push of the cloth, so that it bounces.
+struct Ball {
+    handle: ColliderHandle,
+    position: Vector3,
+    velocity: Vector3,
+    drop_position: Vector3,
+}
+
+/// A cloth attached along its whole border, with balls dropped on it. The cloth pushes the
+/// balls back with the momentum it receives from the contacts.
+pub struct TrampolineScene {
+    solver: FastMassSpringSolver,
+    cloth_render: ClothRender,
+    fixed_frames: FixedFrames,
+    collider_render: ColliderRender,
+    lights: Lights,
+    balls: Vec<Ball>,
+    ball_mass: f32,
+    gravity: Vector3,
+}
+
+impl TrampolineScene {
+    fn new(context: &three_d::Context, scene_options: SceneOptions) -> Self {
+        let solver_options = scene_options.solver_options;
+        let mut render = ClothRender::new(context);
+        let (cloth, mesh) = create_cloth(&scene_options);
+        render.set_indices(mesh.indices());
+
+        let mut solver: FastMassSpringSolver =
+            FastMassSpringSolver::new(cloth, solver_options.time_step)
+                .expect("failed to build the solver");
+        solver_options.apply_live(&mut solver);
+        solver.set_contact_reporting(true);
+
+        let balls = (0..scene_options.num_balls)
+            .map(|i| {
+                // on a spiral above the cloth, so that they land one after the other
+                let angle = i as f32 * 2.4;
+                let distance = 0.25 * CLOTH_SIZE * (i as f32 / scene_options.num_balls as f32);
+                let drop_position = Vector3::new(
+                    distance * angle.cos(),
+                    2.0 + 0.8 * i as f32,
+                    distance * angle.sin(),
+                );
+                let handle = solver.add_collider(
+                    SphereCollider::new(scene_options.ball_radius),
+                    Isometry3::translation(drop_position.x, drop_position.y, drop_position.z),
+                );
+                Ball {
+                    handle,
+                    position: drop_position,
+                    velocity: Vector3::zeros(),
+                    drop_position,
+                }
+            })
+            .collect();
+
+        Self {
+            solver,
+            cloth_render: render,
+            fixed_frames: FixedFrames::new(solver_options.time_step),
+            collider_render: ColliderRender::new(context),
+            lights: Lights::new(context),
+            balls,
+            ball_mass: scene_options.ball_mass,
+            gravity: solver_options.gravity,
+        }
+    }
+
+    pub fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult {
+        let time = Instant::now();
+        // `step` needs the whole scene, so count the frames before stepping
+        let step_count = self
+            .fixed_frames
+            .iter((frame_input.accumulated_time / 1000.0) as f32, 1)
+            .count() as u32;
+        for _ in 0..step_count {
+            self.step();
+        }
+
+        let result = if step_count > 0 {
+            let step_cost = time.elapsed() / step_count;
+            self.cloth_render.set_cloth(self.solver.cloth());
+            DemoLoopResult {
+                updated: true,
+                step_cost,
+            }
+        } else {
+            DemoLoopResult::not_updated()
+        };
+
+        self.collider_render.update(self.solver.colliders());
+        frame_input
+            .screen()
+            .write(|| {
+                self.cloth_render
+                    .draw(camera, frame_input.viewport, &self.lights);
+            })
+            .render(camera, self.collider_render.objects(), &self.lights.array());
+        result
+    }
+
+    fn step(&mut self) {
+        let time_step = self.solver.time_step();
+        for ball in &mut self.balls {
+            if self.solver.collider(ball.handle).is_none() {
+                // removed in the collider editor
+                continue;
+            }
+            if ball.position.y < RESPAWN_HEIGHT {
+                ball.position = ball.drop_position;
+                ball.velocity = Vector3::zeros();
+            }
+            ball.velocity += self.gravity * time_step;
+            ball.position += ball.velocity * time_step;
+            self.solver.set_collider_transform(
+                ball.handle,
+                Isometry3::translation(ball.position.x, ball.position.y, ball.position.z),
+            );
+        }
+        self.solver.step();
+
+        // a particle pushed by `depth` within a substep gained `mass * depth / h` momentum,
+        // which the ball loses
+        let substep_time_step = self.solver.substep_time_step();
+        let contacts: Vec<_> = self.solver.drain_contact_events().collect();
+        for contact in contacts {
+            if let Some(ball) = self
+                .balls
+                .iter_mut()
+                .find(|ball| !contact.shared && ball.handle == contact.collider)
+            {
+                let particle_mass = self.solver.cloth().particle_masses[contact.particle_index];
+                ball.velocity -= contact.normal
+                    * (particle_mass * contact.depth / (substep_time_step * self.ball_mass));
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TrampolineDemo {
+    scene: Option<TrampolineScene>,
+    scene_options: SceneOptions,
+}
+
+impl Demo for TrampolineDemo {
+    fn name(&self) -> &'static str {
+        "Trampoline"
+    }
+
+    fn restart(&mut self, context: &three_d::Context) {
+        self.scene = Some(TrampolineScene::new(context, self.scene_options));
+    }
+
+    fn on_frame_loop(&mut self, camera: &Camera, frame_input: &FrameInput) -> DemoLoopResult {
+        if let Some(scene) = &mut self.scene {
+            scene.on_frame_loop(camera, frame_input)
+        } else {
+            DemoLoopResult::not_updated()
+        }
+    }
+
+    fn show_options_gui(&mut self, ui: &mut three_d::egui::Ui) {
+        if SolverOptionsGUI::new(&mut self.scene_options.solver_options).show_ui(ui) {
+            let solver_options = self.scene_options.solver_options;
+            if let Some(scene) = &mut self.scene {
+                solver_options.apply_live(&mut scene.solver);
+                scene.gravity = solver_options.gravity;
+            }
+        }
+        if ClothOptionsGUI::new(&mut self.scene_options.cloth_options).show_ui(ui) {
+            let cloth_options = self.scene_options.cloth_options;
+            if let Some(solver) = self.solver_mut() {
+                cloth_options.apply_live(solver);
+            }
+        }
+        let options = &mut self.scene_options;
+        Slider::new(&mut options.border_stiffness, 1.0..=500.0)
+            .text("Border Stiffness")
+            .ui(ui);
+        Slider::new(&mut options.num_balls, 1..=16)
+            .text("Balls")
+            .ui(ui);
+        Slider::new(&mut options.ball_radius, 0.05..=0.8)
+            .text("Ball Radius")
+            .ui(ui);
+        if Slider::new(&mut options.ball_mass, 0.05..=10.0)
+            .text("Ball Mass")
+            .logarithmic(true)
+            .ui(ui)
+            .changed()
+        {
+            if let Some(scene) = &mut self.scene {
+                scene.ball_mass = options.ball_mass;
+            }
+        }
+    }
+
+    fn apply_config(&mut self, config: &SceneConfig) {
+        let options = &mut self.scene_options;
+        options.solver_options = config
+            .solver_options(options.solver_options)
+            .expect("checked when loading the config");
+        options.cloth_options = config
+            .cloth_options(options.cloth_options)
+            .expect("checked when loading the config");
+    }
+
+    fn set_render_options(&mut self, options: RenderOptions) {
+        if let Some(scene) = &mut self.scene {
+            scene.cloth_render.set_options(options);
+        }
+    }
+
+    fn solver_mut(&mut self) -> Option<&mut FastMassSpringSolver> {
+        self.scene.as_mut().map(|scene| &mut scene.solver)
+    }
+
+    /// Frame the cloth and the height the balls drop from.
+    fn camera_preset(&mut self) -> CameraPreset {
+        let half_size = 0.5 * CLOTH_SIZE;
+        let aabb = Aabb::new(
+            Vector3::new(-half_size, -1.0, -half_size),
+            Vector3::new(half_size, 3.0, half_size),
+        );
+        CameraPreset::frame_aabb(&aabb, Vector3::new(0.0, -0.5, -1.0))
+    }
+}
+
+/// A horizontal cloth with every particle of its border attached.
+fn create_cloth(options: &SceneOptions) -> (Cloth, Mesh) {
+    let cloth_options = options.cloth_options;
+    let resolution = cloth_options.resolution;
+    let transform = Isometry3 {
+        rotation: UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f32::consts::FRAC_PI_2),
+        translation: Vector3::zeros().into(),
+    };
+    let render_mesh_data =
+        GridPlaneBuilder::new(CLOTH_SIZE, CLOTH_SIZE, resolution - 1, resolution - 1)
+            .with_transform(transform)
+            .build();
+
+    let builder = ClothBuilder {
+        width: CLOTH_SIZE,
+        height: CLOTH_SIZE,
+        width_segments: resolution - 1,
+        height_segments: resolution - 1,
+        structural_spring_stiffness: cloth_options.structual_spring_stiffness,
+        shear_spring_stiffness: cloth_options.shear_spring_stiffness,
+        mass: cloth_options.mass,
+        transform,
+        stiffness_map: None,
+    };
+    let border: Vec<usize> = builder.border_indices().collect();
+    let mut cloth = builder.build();
+    cloth.attach_particles(border, options.border_stiffness);
+
+    (cloth, render_mesh_data)
+}
+
+#[derive(Clone, Copy)]
+struct SceneOptions {
+    solver_options: SolverOptions,
+    cloth_options: ClothOptions,
+    border_stiffness: f32,
+    num_balls: usize,
+    ball_radius: f32,
+    ball_mass: f32,
+}
+
+impl Default for SceneOptions {
+    fn default() -> Self {
+        Self {
+            solver_options: SolverOptions {
+                time_step: 1.0 / 120.0,
+                num_iterations: 5,
+                ..Default::default()
+            },
+            cloth_options: ClothOptions {
+                structual_spring_stiffness: 100.0,
+                shear_spring_stiffness: 1.0,
+                mass: 2.0,
+                resolution: 24,
+            },
+            border_stiffness: 200.0,
+            num_balls: 5,
+            ball_radius: 0.3,
+            ball_mass: 0.5,
+        }
+    }
+}
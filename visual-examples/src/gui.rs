@@ -1,4 +1,7 @@
-use crate::common::{ClothOptions, SolverOptions};
+use crate::{
+    common::{ClothOptions, SolverOptions},
+    render::{RenderMode, RenderOptions},
+};
 
 pub struct SolverOptionsGUI<'a> {
     options: &'a mut SolverOptions,
@@ -9,8 +12,10 @@ impl<'a> SolverOptionsGUI<'a> {
         Self { options }
     }
 
-    pub fn show_ui(&mut self, ui: &mut three_d::egui::Ui) {
+    /// Returns true if an option that can be applied to a running solver changed.
+    pub fn show_ui(&mut self, ui: &mut three_d::egui::Ui) -> bool {
         use three_d::egui::*;
+        let mut changed = false;
         CollapsingHeader::new("Physics Options").show(ui, |ui| {
             let mut physics_fps = (1.0 / self.options.time_step).ceil() as u32;
             let response = ui.add(
@@ -22,11 +27,94 @@ impl<'a> SolverOptionsGUI<'a> {
                 self.options.time_step = 1.0 / physics_fps as f32;
             }
 
-            Slider::new(&mut self.options.num_iterations, 1..=10)
+            changed |= Slider::new(&mut self.options.num_iterations, 1..=10)
                 .text("Num Iterations")
                 .clamp_to_range(true)
-                .ui(ui);
+                .ui(ui)
+                .changed();
+            changed |= ui
+                .checkbox(
+                    &mut self.options.adaptive_iterations,
+                    "Adaptive Iterations (up to Num Iterations)",
+                )
+                .changed();
+            if self.options.adaptive_iterations {
+                changed |= Slider::new(&mut self.options.step_budget_ms, 0.5..=16.0)
+                    .text("Step Budget (ms)")
+                    .clamp_to_range(true)
+                    .ui(ui)
+                    .changed();
+            }
+            changed |= Slider::new(&mut self.options.substeps, 1..=8)
+                .text("Substeps")
+                .clamp_to_range(true)
+                .ui(ui)
+                .changed();
+            changed |= ui
+                .checkbox(
+                    &mut self.options.explicit_integration,
+                    "Explicit Integration (unstable when stiff)",
+                )
+                .changed();
+            changed |= Slider::new(&mut self.options.damping, 0.9..=1.0)
+                .text("Damping (1 = none)")
+                .clamp_to_range(true)
+                .ui(ui)
+                .changed();
+            changed |= ui
+                .checkbox(
+                    &mut self.options.damp_deformation_only,
+                    "Damp Deformation Only",
+                )
+                .changed();
+            changed |= ui
+                .checkbox(&mut self.options.sleep, "Sleep When Settled")
+                .changed();
+            changed |= Slider::new(&mut self.options.contact_velocity_correction, 0.0..=1.0)
+                .text("Contact Velocity Correction")
+                .clamp_to_range(true)
+                .ui(ui)
+                .changed();
+            ui.horizontal(|ui| {
+                for value in self.options.gravity.iter_mut() {
+                    changed |= DragValue::new(value).speed(0.1).ui(ui).changed();
+                }
+                ui.label("Gravity");
+            });
+            ui.horizontal(|ui| {
+                for value in self.options.wind_direction.iter_mut() {
+                    changed |= DragValue::new(value).speed(0.05).ui(ui).changed();
+                }
+                ui.label("Wind Direction");
+            });
+            changed |= Slider::new(&mut self.options.wind_strength, 0.0..=20.0)
+                .text("Wind Strength")
+                .clamp_to_range(true)
+                .ui(ui)
+                .changed();
+            changed |= Slider::new(&mut self.options.turbulence_amplitude, 0.0..=20.0)
+                .text("Turbulence")
+                .clamp_to_range(true)
+                .ui(ui)
+                .changed();
+            changed |= Slider::new(&mut self.options.turbulence_frequency, 0.05..=4.0)
+                .text("Turbulence Frequency")
+                .logarithmic(true)
+                .clamp_to_range(true)
+                .ui(ui)
+                .changed();
+            changed |= Slider::new(&mut self.options.aero_drag, 0.0..=2.0)
+                .text("Aerodynamic Drag")
+                .clamp_to_range(true)
+                .ui(ui)
+                .changed();
+            changed |= Slider::new(&mut self.options.aero_lift, 0.0..=2.0)
+                .text("Aerodynamic Lift")
+                .clamp_to_range(true)
+                .ui(ui)
+                .changed();
         });
+        changed
     }
 }
 
@@ -39,17 +127,23 @@ impl<'a> ClothOptionsGUI<'a> {
         Self { data }
     }
 
-    pub fn show_ui(&mut self, ui: &mut three_d::egui::Ui) {
+    /// Returns whether a stiffness changed, which [`ClothOptions::apply_live`] applies to the
+    /// running cloth. The other options take effect on restart.
+    pub fn show_ui(&mut self, ui: &mut three_d::egui::Ui) -> bool {
         use three_d::egui::*;
+        let mut stiffness_changed = false;
         CollapsingHeader::new("Cloth Options").show(ui, |ui| {
-            Slider::new(&mut self.data.structual_spring_stiffness, 0.1..=100.0)
-                .text("Structual Stiffness")
-                .clamp_to_range(true)
-                .ui(ui);
-            Slider::new(&mut self.data.shear_spring_stiffness, 0.0..=100.0)
+            stiffness_changed |=
+                Slider::new(&mut self.data.structual_spring_stiffness, 0.1..=100.0)
+                    .text("Structual Stiffness")
+                    .clamp_to_range(true)
+                    .ui(ui)
+                    .changed();
+            stiffness_changed |= Slider::new(&mut self.data.shear_spring_stiffness, 0.0..=100.0)
                 .text("Shear Stiffness")
                 .clamp_to_range(true)
-                .ui(ui);
+                .ui(ui)
+                .changed();
             Slider::new(&mut self.data.mass, 0.01..=100.0)
                 .text("mass")
                 .clamp_to_range(true)
@@ -59,5 +153,64 @@ impl<'a> ClothOptionsGUI<'a> {
                 .clamp_to_range(true)
                 .ui(ui);
         });
+        stiffness_changed
+    }
+}
+
+pub(crate) struct RenderOptionsGUI<'a> {
+    pub data: &'a mut RenderOptions,
+}
+
+impl<'a> RenderOptionsGUI<'a> {
+    pub fn new(data: &'a mut RenderOptions) -> Self {
+        Self { data }
+    }
+
+    pub fn show_ui(&mut self, ui: &mut three_d::egui::Ui) {
+        use three_d::egui::*;
+        CollapsingHeader::new("Render Options").show(ui, |ui| {
+            ComboBox::from_label("Mode")
+                .selected_text(format!("{:?}", self.data.mode))
+                .show_ui(ui, |ui| {
+                    for mode in [
+                        RenderMode::Solid,
+                        RenderMode::Wireframe,
+                        RenderMode::Points,
+                        RenderMode::Combined,
+                        RenderMode::Strain,
+                    ] {
+                        ui.selectable_value(&mut self.data.mode, mode, format!("{:?}", mode));
+                    }
+                });
+            Slider::new(&mut self.data.wireframe_thickness, 0.0005..=0.02)
+                .text("Wireframe Thickness")
+                .clamp_to_range(true)
+                .ui(ui);
+            Slider::new(&mut self.data.point_size, 0.005..=0.1)
+                .text("Point Size")
+                .clamp_to_range(true)
+                .ui(ui);
+            ui.horizontal(|ui| {
+                ui.color_edit_button_rgb(&mut self.data.surface_color);
+                ui.label("Surface Color");
+            });
+            ui.checkbox(&mut self.data.double_sided, "Double Sided");
+            ui.horizontal(|ui| {
+                ui.color_edit_button_rgb(&mut self.data.back_face_color);
+                ui.label("Back Face Color");
+            });
+            ui.horizontal(|ui| {
+                ui.color_edit_button_rgb(&mut self.data.wireframe_color);
+                ui.label("Wireframe Color");
+            });
+            ui.horizontal(|ui| {
+                ui.color_edit_button_rgb(&mut self.data.point_color);
+                ui.label("Point Color");
+            });
+            Slider::new(&mut self.data.max_strain, 0.01..=0.5)
+                .text("Max Strain")
+                .clamp_to_range(true)
+                .ui(ui);
+        });
     }
 }
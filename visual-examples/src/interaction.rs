@@ -0,0 +1,104 @@
+use fast_mass_spring::{cloth::Attachment, solver::FastMassSpringSolver};
+use simulation::math::Vector3;
+use three_d::{Camera, Event, InnerSpace, MouseButton};
+
+/// Max distance between the mouse ray and a particle for it to be picked.
+const PICK_RADIUS: f32 = 0.2;
+const DRAG_STIFFNESS: f32 = 500.0;
+
+struct Grab {
+    attachment_index: usize,
+    depth: f32,
+}
+
+/// Grab the cloth particle under the mouse and pull it with a temporary attachment
+/// while the left button is held.
+///
+/// Adding and removing the attachment refactorizes the system once per press and release.
+/// That cannot be avoided by keeping a permanent drag attachment around: its stiffness sits
+/// on the diagonal of the system matrix at the grabbed particle, so moving it to another
+/// particle or changing its stiffness needs the same refactorization. Pulling with an
+/// explicit force hook instead would not, but is unstable at `DRAG_STIFFNESS`. While the
+/// button is held only the target moves, which does not touch the matrix.
+#[derive(Default)]
+pub struct ParticleDragger {
+    grab: Option<Grab>,
+}
+
+impl ParticleDragger {
+    pub fn handle_events(
+        &mut self,
+        solver: &mut FastMassSpringSolver,
+        camera: &Camera,
+        events: &mut [Event],
+    ) {
+        for event in events.iter_mut() {
+            match event {
+                Event::MousePress {
+                    button: MouseButton::Left,
+                    position,
+                    handled,
+                    ..
+                } if !*handled => {
+                    let origin = camera.position_at_pixel(*position);
+                    let direction = camera.view_direction_at_pixel(*position).normalize();
+                    let ray_origin = Vector3::new(origin.x, origin.y, origin.z);
+                    let ray_direction = Vector3::new(direction.x, direction.y, direction.z);
+                    if let Some(index) =
+                        solver.pick_particle(ray_origin, ray_direction, PICK_RADIUS)
+                    {
+                        let target_position = solver.cloth().get_particle_position(index);
+                        let depth = (target_position - ray_origin).dot(&ray_direction);
+                        let attachment_index = solver
+                            .add_attachment(Attachment {
+                                particle_index: index,
+                                target_position,
+                                stiffness: DRAG_STIFFNESS,
+                            })
+                            .expect("the picked particle exists and the stiffness is valid");
+                        self.grab = Some(Grab {
+                            attachment_index,
+                            depth,
+                        });
+                        *handled = true;
+                    }
+                }
+                Event::MouseMotion {
+                    position, handled, ..
+                } => {
+                    if let Some(grab) = &self.grab {
+                        let origin = camera.position_at_pixel(*position);
+                        let direction = camera.view_direction_at_pixel(*position).normalize();
+                        let target = origin + direction * grab.depth;
+                        solver.set_attachment_target(
+                            grab.attachment_index,
+                            Vector3::new(target.x, target.y, target.z),
+                        );
+                        *handled = true;
+                    }
+                }
+                Event::MouseRelease {
+                    button: MouseButton::Left,
+                    handled,
+                    ..
+                } => {
+                    if let Some(grab) = self.grab.take() {
+                        solver.remove_attachment(grab.attachment_index);
+                        *handled = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_dragging(&self) -> bool {
+        self.grab.is_some()
+    }
+
+    /// Forget the current grab without touching the solver, e.g. after the demo restarted.
+    pub fn reset(&mut self) {
+        self.grab = None;
+    }
+}
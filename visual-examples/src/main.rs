@@ -1,16 +1,92 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use common::CAMERA_FOV_Y;
+use config::SceneConfig;
 use entry::DemoEntry;
 use three_d::*;
+mod capture;
+mod collider_editor;
 mod common;
+mod config;
 mod entry;
 mod gui;
+mod interaction;
+mod plots;
 mod render;
 
+/// Cloth simulation demos.
+#[derive(Parser)]
+struct Args {
+    /// Scene config file, TOML or JSON, applied before the other options.
+    config: Option<PathBuf>,
+    /// The demo to start, by name, e.g. "Drop Cloth".
+    #[arg(long)]
+    demo: Option<String>,
+    /// Number of particles along each side of the cloth.
+    #[arg(long)]
+    resolution: Option<usize>,
+    /// Solver iterations per step.
+    #[arg(long)]
+    iterations: Option<usize>,
+    /// Simulated seconds per step.
+    #[arg(long)]
+    time_step: Option<f32>,
+    /// Render as fast as possible instead of waiting for the display.
+    #[arg(long)]
+    no_vsync: bool,
+    /// Exit after this many seconds and print the frame and step times, for comparing
+    /// performance across machines.
+    #[arg(long, value_name = "SECONDS")]
+    run_for: Option<f64>,
+}
+
+impl Args {
+    /// The config file, if any, with the options given on the command line on top.
+    fn scene_config(&self) -> Result<Option<SceneConfig>, String> {
+        let mut config = match &self.config {
+            Some(path) => {
+                SceneConfig::load(path).map_err(|err| format!("{}: {}", path.display(), err))?
+            }
+            None => SceneConfig::default(),
+        };
+        let mut overridden = self.config.is_some();
+        if let Some(demo) = &self.demo {
+            config.demo = Some(demo.clone());
+            overridden = true;
+        }
+        if let Some(resolution) = self.resolution {
+            config.set_cloth_option("resolution", resolution);
+            overridden = true;
+        }
+        if let Some(iterations) = self.iterations {
+            config.set_solver_option("num_iterations", iterations);
+            overridden = true;
+        }
+        if let Some(time_step) = self.time_step {
+            config.set_solver_option("time_step", time_step);
+            overridden = true;
+        }
+        Ok(overridden.then_some(config))
+    }
+}
+
 pub fn main() {
+    let args = Args::parse();
+    let scene_config = args.scene_config().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+
     // Create a window (a canvas on web)
     let window = Window::new(WindowSettings {
         title: "Simulation!".to_string(),
         #[cfg(not(target_arch = "wasm32"))]
         max_size: Some((1280, 720)),
+        surface_settings: SurfaceSettings {
+            vsync: !args.no_vsync,
+            ..Default::default()
+        },
         ..Default::default()
     })
     .unwrap();
@@ -23,24 +99,45 @@ pub fn main() {
         vec3(0.0, 0.0, 10.0),
         vec3(0.0, 0.0, 0.0),
         vec3(0.0, 1.0, 0.0),
-        degrees(45.0),
+        degrees(CAMERA_FOV_Y),
         0.1,
         100.0,
     );
     let mut control = OrbitControl::new(*camera.target(), 0.5, 100.0);
     let mut demo_entry = DemoEntry::new(&context);
+    if let Some(config) = scene_config {
+        if let Err(err) = demo_entry.load_config(&context, config) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+    let run_for = args.run_for;
     // Start the main render loop
     window.render_loop(
         move |mut frame_input| // Begin a new frame with an updated frame input
     {
         // Ensure the viewport matches the current window viewport which changes if the window is resized
          camera.set_viewport(frame_input.viewport);
+         if let Some(preset) = demo_entry.take_camera_preset() {
+             let position = vec3(preset.position.x, preset.position.y, preset.position.z);
+             let target = vec3(preset.target.x, preset.target.y, preset.target.z);
+             camera.set_view(position, target, vec3(0.0, 1.0, 0.0));
+             control = OrbitControl::new(target, 0.5, 100.0);
+         }
+         demo_entry.handle_events(&camera, &mut frame_input.events);
          control.handle_events(&mut camera, &mut frame_input.events);
             frame_input
             .screen()
             .clear(ClearState::color_and_depth(0.8, 0.8, 0.8, 1.0, 1.0));
          demo_entry.render_loop(&context,&camera,&mut frame_input);
 
+        if run_for.is_some_and(|seconds| frame_input.accumulated_time >= seconds * 1000.0) {
+            println!("{}", demo_entry.run_summary());
+            return FrameOutput {
+                exit: true,
+                ..Default::default()
+            };
+        }
         FrameOutput::default()
     },
     );
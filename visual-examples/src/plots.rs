@@ -0,0 +1,78 @@
+use std::{collections::VecDeque, time::Duration};
+
+use fast_mass_spring::solver::StepDiagnostics;
+use three_d::egui::{
+    plot::{Line, Plot, PlotPoints},
+    Ui,
+};
+
+/// Seconds of simulated time shown by the plots.
+const PLOT_WINDOW: f32 = 5.0;
+
+#[derive(Clone, Copy)]
+struct Sample {
+    time: f32,
+    diagnostics: StepDiagnostics,
+    step_cost: Duration,
+}
+
+/// Plots of the solver diagnostics and the step time over the last seconds of simulation.
+#[derive(Default)]
+pub struct DiagnosticsPlots {
+    samples: VecDeque<Sample>,
+}
+
+impl DiagnosticsPlots {
+    /// Record the diagnostics of the last step, at the simulated `time`.
+    pub fn push(&mut self, time: f32, diagnostics: StepDiagnostics, step_cost: Duration) {
+        // the simulation went back in time, e.g. it was restarted
+        if self.samples.back().is_some_and(|last| last.time > time) {
+            self.samples.clear();
+        }
+        self.samples.push_back(Sample {
+            time,
+            diagnostics,
+            step_cost,
+        });
+        while self
+            .samples
+            .front()
+            .is_some_and(|first| first.time < time - PLOT_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn show_ui(&self, ui: &mut Ui) {
+        self.plot(ui, "kinetic energy", |sample| {
+            sample.diagnostics.kinetic_energy as f64
+        });
+        self.plot(ui, "residual", |sample| sample.diagnostics.residual as f64);
+        self.plot(ui, "iterations", |sample| {
+            sample.diagnostics.num_iterations as f64
+        });
+        self.plot(ui, "step (ms)", |sample| {
+            sample.step_cost.as_secs_f64() * 1000.0
+        });
+    }
+
+    fn plot(&self, ui: &mut Ui, name: &str, value: impl Fn(&Sample) -> f64) {
+        ui.label(name);
+        let points: PlotPoints = self
+            .samples
+            .iter()
+            .map(|sample| [sample.time as f64, value(sample)])
+            .collect();
+        Plot::new(name)
+            .height(80.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .show(ui, |plot_ui| plot_ui.line(Line::new(points).name(name)));
+    }
+}
@@ -1,19 +1,71 @@
 use std::collections::HashSet;
 
+use fast_mass_spring::cloth::Cloth;
+use simulation::{math::Isometry3, Collider, TransformedCollider};
 use three_d::{
-    vec3, Camera, Context, CpuMaterial, CpuMesh, Cull, ElementBuffer, InnerSpace, InstanceBuffer,
-    Mat4, Matrix, PhysicalMaterial, Program, Quat, RenderStates, SquareMatrix, Srgba, Vector3,
-    Vector4, VertexBuffer, Viewport, Zero,
+    degrees, vec3, AmbientLight, Camera, Context, CpuMaterial, CpuMesh, DirectionalLight,
+    ElementBuffer, Gm, Indices, InnerSpace, InstanceBuffer, Mat4, Matrix, PhysicalMaterial,
+    Positions, Program, Quat, RenderStates, SquareMatrix, Srgba, Vector3, Vector4, VertexBuffer,
+    Viewport, Zero,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    Solid,
+    Wireframe,
+    Points,
+    /// Solid surface with the wireframe on top.
+    Combined,
+    /// Surface colored by the strain of the springs around each vertex.
+    Strain,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    pub mode: RenderMode,
+    pub wireframe_thickness: f32,
+    pub point_size: f32,
+    /// Light the back side of the surface with its own normal and color.
+    pub double_sided: bool,
+    pub surface_color: [f32; 3],
+    pub back_face_color: [f32; 3],
+    pub wireframe_color: [f32; 3],
+    pub point_color: [f32; 3],
+    /// Strain mapped to the full heatmap color. Compression is shown in blue,
+    /// stretching in red.
+    pub max_strain: f32,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            mode: RenderMode::Combined,
+            wireframe_thickness: 0.003,
+            point_size: 0.02,
+            double_sided: true,
+            surface_color: [1.0, 0.0, 0.0],
+            back_face_color: [1.0, 0.8, 0.0],
+            wireframe_color: [0.0, 0.0, 0.0],
+            point_color: [0.0, 0.0, 1.0],
+            max_strain: 0.1,
+        }
+    }
+}
+
 pub struct ClothRender {
     positions: VertexBuffer,
+    normal_buffer: VertexBuffer,
+    strain_buffer: VertexBuffer,
     elements: ElementBuffer,
     program: Program,
     wireframe: RenderWireframe,
+    points: RenderPoints,
     indices: Vec<u32>,
     vertices: Vec<Vector3<f32>>,
+    normals: Vec<Vector3<f32>>,
+    strains: Vec<f32>,
     transform_dirty: bool,
+    options: RenderOptions,
 }
 
 impl ClothRender {
@@ -22,25 +74,41 @@ impl ClothRender {
         let elements = ElementBuffer::new(context);
         let program = Program::from_source(
             context,
-            include_str!("shaders/triangle.vert"),
-            include_str!("shaders/triangle.frag"),
+            include_str!("shaders/cloth.vert"),
+            include_str!("shaders/cloth.frag"),
         )
         .unwrap();
         Self {
             positions,
+            normal_buffer: VertexBuffer::new(context),
+            strain_buffer: VertexBuffer::new(context),
             elements,
             program,
             wireframe: RenderWireframe::new(context),
+            points: RenderPoints::new(context),
             indices: vec![],
             vertices: vec![],
+            normals: vec![],
+            strains: vec![],
             transform_dirty: false,
+            options: RenderOptions::default(),
         }
     }
 
+    pub fn set_options(&mut self, options: RenderOptions) {
+        if options.wireframe_thickness != self.options.wireframe_thickness
+            || options.point_size != self.options.point_size
+        {
+            self.transform_dirty = true;
+        }
+        self.options = options;
+    }
+
     pub fn set_indices(&mut self, indices: &[u32]) {
         self.indices.resize(indices.len(), 0);
         self.indices.copy_from_slice(indices);
         self.elements.fill(indices);
+        self.transform_dirty = true;
     }
 
     pub fn set_vertices_from_slice(&mut self, vertices: &[f32]) {
@@ -52,24 +120,161 @@ impl ClothRender {
         self.transform_dirty = true;
     }
 
-    pub fn draw(&mut self, camera: &Camera, viewport: Viewport) {
+    /// Update the vertices from the cloth particles. The strains are only computed when
+    /// they are displayed.
+    pub fn set_cloth(&mut self, cloth: &Cloth) {
+        self.set_vertices_from_slice(cloth.particle_positions.as_slice());
+        if self.options.mode == RenderMode::Strain {
+            self.strains = cloth.particle_strains();
+        }
+    }
+
+    /// Area weighted vertex normals of the current vertices.
+    fn compute_normals(&mut self) {
+        self.normals.clear();
+        self.normals.resize(self.vertices.len(), Vector3::zero());
+        for triangle in self.indices.chunks(3) {
+            let i0 = triangle[0] as usize;
+            let i1 = triangle[1] as usize;
+            let i2 = triangle[2] as usize;
+            let v0 = self.vertices[i0];
+            let normal = (self.vertices[i1] - v0).cross(self.vertices[i2] - v0);
+            self.normals[i0] += normal;
+            self.normals[i1] += normal;
+            self.normals[i2] += normal;
+        }
+        for normal in self.normals.iter_mut() {
+            if normal.magnitude2() > 0.0 {
+                *normal = normal.normalize();
+            }
+        }
+    }
+
+    pub fn draw(&mut self, camera: &Camera, viewport: Viewport, lights: &Lights) {
+        let options = self.options;
+        let (draw_surface, draw_wireframe, draw_points) = match options.mode {
+            RenderMode::Solid => (true, false, false),
+            RenderMode::Wireframe => (false, true, false),
+            RenderMode::Points => (false, false, true),
+            RenderMode::Combined => (true, true, false),
+            RenderMode::Strain => (true, false, false),
+        };
         if self.transform_dirty {
             self.transform_dirty = false;
             self.positions.fill(&self.vertices);
-            self.wireframe.set(&self.vertices, &self.indices, 0.003);
+            self.compute_normals();
+            self.normal_buffer.fill(&self.normals);
+            // keep the attribute the same size as the positions, even before any strain was set
+            self.strains.resize(self.vertices.len(), 0.0);
+            self.strain_buffer.fill(&self.strains);
+            self.wireframe
+                .set(&self.vertices, &self.indices, options.wireframe_thickness);
+            self.points.set(&self.vertices, options.point_size);
         }
-        let program = &self.program;
-        program.use_uniform("model", Mat4::identity());
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
-        program.use_uniform("color", vec3(1.0, 0.0, 0.0));
-        program.use_vertex_attribute("position", &self.positions);
-        program.draw_elements(RenderStates::default(), viewport, &self.elements);
+        if draw_surface {
+            let program = &self.program;
+            program.use_uniform("model", Mat4::identity());
+            program.use_uniform("viewProjection", camera.projection() * camera.view());
+            program.use_uniform("color", Vector3::from(options.surface_color));
+            program.use_uniform("backColor", Vector3::from(options.back_face_color));
+            program.use_uniform(
+                "doubleSided",
+                if options.double_sided { 1.0f32 } else { 0.0f32 },
+            );
+            program.use_uniform(
+                "showStrain",
+                if options.mode == RenderMode::Strain {
+                    1.0f32
+                } else {
+                    0.0f32
+                },
+            );
+            program.use_uniform("maxStrain", options.max_strain.max(f32::EPSILON));
+            program.use_uniform("ambientColor", lights.ambient_color());
+            program.use_uniform("lightColor", lights.directional_color());
+            program.use_uniform("lightDirection", lights.directional_direction());
+            program.use_vertex_attribute("position", &self.positions);
+            program.use_vertex_attribute("normal", &self.normal_buffer);
+            program.use_vertex_attribute("strain", &self.strain_buffer);
+            program.draw_elements(RenderStates::default(), viewport, &self.elements);
+        }
+        if draw_wireframe {
+            self.wireframe
+                .draw(camera, viewport, Vector3::from(options.wireframe_color));
+        }
+        if draw_points {
+            self.points
+                .draw(camera, viewport, Vector3::from(options.point_color));
+        }
+    }
+}
 
-        self.wireframe.draw(camera, viewport);
+/// Draw many cloths with a single [`ClothRender`], their meshes merged into shared buffers so
+/// that a grid of small cloths costs one draw call per render mode rather than one per cloth.
+pub struct MultiClothRender {
+    render: ClothRender,
+    /// Index of the first vertex of each cloth in the merged vertices, followed by the total.
+    vertex_offsets: Vec<usize>,
+}
+
+impl MultiClothRender {
+    pub fn new(context: &three_d::Context) -> Self {
+        Self {
+            render: ClothRender::new(context),
+            vertex_offsets: vec![0],
+        }
+    }
+
+    /// Add the mesh of a cloth with `num_vertices` particles, drawn after the cloths added
+    /// before it.
+    pub fn add_cloth(&mut self, indices: &[u32], num_vertices: usize) {
+        let offset = *self.vertex_offsets.last().unwrap();
+        self.vertex_offsets.push(offset + num_vertices);
+        let render = &mut self.render;
+        render
+            .indices
+            .extend(indices.iter().map(|&index| index + offset as u32));
+        render.elements.fill(&render.indices);
+        render
+            .vertices
+            .resize(offset + num_vertices, Vector3::zero());
+        render.transform_dirty = true;
+    }
+
+    /// Update the vertices from the particles of the cloths, in the order they were added.
+    pub fn set_cloths<'a>(&mut self, cloths: impl IntoIterator<Item = &'a Cloth>) {
+        let render = &mut self.render;
+        let show_strain = render.options.mode == RenderMode::Strain;
+        if show_strain {
+            render.strains.resize(render.vertices.len(), 0.0);
+        }
+        for (cloth, offsets) in cloths.into_iter().zip(self.vertex_offsets.windows(2)) {
+            let positions = cloth.particle_positions.as_slice();
+            debug_assert_eq!(positions.len(), (offsets[1] - offsets[0]) * 3);
+            for (vertex, position) in render.vertices[offsets[0]..offsets[1]]
+                .iter_mut()
+                .zip(positions.chunks(3))
+            {
+                *vertex = vec3(position[0], position[1], position[2]);
+            }
+            if show_strain {
+                render.strains[offsets[0]..offsets[1]].copy_from_slice(&cloth.particle_strains());
+            }
+        }
+        render.transform_dirty = true;
+    }
+
+    pub fn set_options(&mut self, options: RenderOptions) {
+        self.render.set_options(options);
+    }
+
+    pub fn draw(&mut self, camera: &Camera, viewport: Viewport, lights: &Lights) {
+        self.render.draw(camera, viewport, lights);
     }
 }
 
-struct RenderWireframe {
+/// Draw many copies of a mesh, each with its own affine transform.
+struct InstancedMeshRender {
     vertices: VertexBuffer,
     indices: ElementBuffer,
     program: Program,
@@ -81,29 +286,17 @@ struct RenderWireframe {
     transform_row3_buffer: InstanceBuffer,
 }
 
-impl RenderWireframe {
-    pub fn new(context: &Context) -> Self {
+impl InstancedMeshRender {
+    pub fn new(context: &Context, mesh: &CpuMesh) -> Self {
         let program = Program::from_source(
             context,
             include_str!("shaders/wireframe.vert"),
             include_str!("shaders/triangle.frag"),
         )
         .unwrap();
-
-        let mut wireframe_material = PhysicalMaterial::new_opaque(
-            context,
-            &CpuMaterial {
-                albedo: Srgba::new_opaque(220, 50, 50),
-                roughness: 0.7,
-                metallic: 0.8,
-                ..Default::default()
-            },
-        );
-        wireframe_material.render_states.cull = Cull::Back;
-        let cylinder = CpuMesh::cylinder(10);
         Self {
-            vertices: VertexBuffer::new_with_data(context, &cylinder.positions.to_f32()),
-            indices: ElementBuffer::new_with_data(context, &cylinder.indices.to_u32().unwrap()),
+            vertices: VertexBuffer::new_with_data(context, &mesh.positions.to_f32()),
+            indices: ElementBuffer::new_with_data(context, &mesh.indices.to_u32().unwrap()),
             program,
             transform_row1: vec![],
             transform_row2: vec![],
@@ -114,37 +307,34 @@ impl RenderWireframe {
         }
     }
 
-    pub fn set(&mut self, vertices: &[Vector3<f32>], indices: &[u32], thickness: f32) {
-        let mut edge_set = HashSet::new();
+    pub fn clear(&mut self) {
         self.transform_row1.clear();
         self.transform_row2.clear();
         self.transform_row3.clear();
-        for triangle_index in 0..indices.len() / 3 {
-            let i0 = indices[triangle_index * 3] as usize;
-            let i1 = indices[triangle_index * 3 + 1] as usize;
-            let i2 = indices[triangle_index * 3 + 2] as usize;
-            let edges = [Edge::new(i0, i1), Edge::new(i1, i2), Edge::new(i2, i0)];
-            for edge in edges {
-                if !edge_set.contains(&edge) {
-                    edge_set.insert(edge);
-                    let transform = edge.compute_transform(vertices, thickness);
-                    self.transform_row1.push(transform.row(0));
-                    self.transform_row2.push(transform.row(1));
-                    self.transform_row3.push(transform.row(2));
-                }
-            }
-        }
+    }
+
+    pub fn push(&mut self, transform: Mat4) {
+        self.transform_row1.push(transform.row(0));
+        self.transform_row2.push(transform.row(1));
+        self.transform_row3.push(transform.row(2));
+    }
+
+    /// Upload the transforms pushed since the last `clear`.
+    pub fn upload(&mut self) {
         self.transform_row1_buffer.fill(&self.transform_row1[..]);
         self.transform_row2_buffer.fill(&self.transform_row2[..]);
         self.transform_row3_buffer.fill(&self.transform_row3[..]);
     }
 
-    pub fn draw(&self, camera: &Camera, viewport: Viewport) {
+    pub fn draw(&self, camera: &Camera, viewport: Viewport, color: Vector3<f32>) {
         let instance_count = self.transform_row1.len() as u32;
+        if instance_count == 0 {
+            return;
+        }
         let program = &self.program;
         program.use_uniform("model", Mat4::identity());
         program.use_uniform("viewProjection", camera.projection() * camera.view());
-        program.use_uniform("color", vec3(0.0, 0.0, 0.0));
+        program.use_uniform("color", color);
         program.use_vertex_attribute("position", &self.vertices);
         program.use_instance_attribute("instanceTransformRow0", &self.transform_row1_buffer);
         program.use_instance_attribute("instanceTransformRow1", &self.transform_row2_buffer);
@@ -158,6 +348,66 @@ impl RenderWireframe {
     }
 }
 
+struct RenderWireframe {
+    instances: InstancedMeshRender,
+}
+
+impl RenderWireframe {
+    pub fn new(context: &Context) -> Self {
+        Self {
+            instances: InstancedMeshRender::new(context, &CpuMesh::cylinder(10)),
+        }
+    }
+
+    pub fn set(&mut self, vertices: &[Vector3<f32>], indices: &[u32], thickness: f32) {
+        let mut edge_set = HashSet::new();
+        self.instances.clear();
+        for triangle_index in 0..indices.len() / 3 {
+            let i0 = indices[triangle_index * 3] as usize;
+            let i1 = indices[triangle_index * 3 + 1] as usize;
+            let i2 = indices[triangle_index * 3 + 2] as usize;
+            let edges = [Edge::new(i0, i1), Edge::new(i1, i2), Edge::new(i2, i0)];
+            for edge in edges {
+                if !edge_set.contains(&edge) {
+                    edge_set.insert(edge);
+                    self.instances
+                        .push(edge.compute_transform(vertices, thickness));
+                }
+            }
+        }
+        self.instances.upload();
+    }
+
+    pub fn draw(&self, camera: &Camera, viewport: Viewport, color: Vector3<f32>) {
+        self.instances.draw(camera, viewport, color);
+    }
+}
+
+struct RenderPoints {
+    instances: InstancedMeshRender,
+}
+
+impl RenderPoints {
+    pub fn new(context: &Context) -> Self {
+        Self {
+            instances: InstancedMeshRender::new(context, &CpuMesh::sphere(6)),
+        }
+    }
+
+    pub fn set(&mut self, vertices: &[Vector3<f32>], size: f32) {
+        self.instances.clear();
+        for &vertex in vertices {
+            self.instances
+                .push(Mat4::from_translation(vertex) * Mat4::from_scale(0.5 * size));
+        }
+        self.instances.upload();
+    }
+
+    pub fn draw(&self, camera: &Camera, viewport: Viewport, color: Vector3<f32>) {
+        self.instances.draw(camera, viewport, color);
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Edge(usize, usize);
 
@@ -181,3 +431,148 @@ impl Edge {
         t * r * s
     }
 }
+
+/// Half the side of the square drawn for an infinite plane collider.
+const PLANE_RENDER_SIZE: f32 = 10.0;
+
+/// Draw a primitive matching each collider of a solver.
+pub struct ColliderRender {
+    context: Context,
+    objects: Vec<Gm<three_d::Mesh, PhysicalMaterial>>,
+    /// Spheres are drawn this much smaller than their radius, so that the tessellated sphere
+    /// does not poke through the cloth resting on it.
+    pub inset: f32,
+}
+
+impl ColliderRender {
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            objects: vec![],
+            inset: 0.02,
+        }
+    }
+
+    /// Sync the rendered primitives with the colliders.
+    pub fn update(&mut self, colliders: &[TransformedCollider]) {
+        if self.objects.len() != colliders.len() {
+            let objects = colliders
+                .iter()
+                .map(|collider| create_collider_object(&self.context, &collider.collider))
+                .collect();
+            self.objects = objects;
+        }
+        for (object, collider) in self.objects.iter_mut().zip(colliders) {
+            let scale = match &collider.collider {
+                Collider::Sphere(sphere) => Mat4::from_scale((sphere.radius - self.inset).max(0.0)),
+                Collider::Box(box_collider) => {
+                    let half_extents = box_collider.half_extents.add_scalar(-self.inset);
+                    Mat4::from_nonuniform_scale(
+                        half_extents.x.max(0.0),
+                        half_extents.y.max(0.0),
+                        half_extents.z.max(0.0),
+                    )
+                }
+                // the unit square faces +z, turn it to face +y
+                Collider::Plane(_) => {
+                    Mat4::from_translation(vec3(0.0, -self.inset, 0.0))
+                        * Mat4::from_angle_x(degrees(-90.0))
+                        * Mat4::from_scale(PLANE_RENDER_SIZE)
+                }
+                _ => Mat4::identity(),
+            };
+            object.set_transformation(isometry_to_mat4(&collider.transform) * scale);
+        }
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = &Gm<three_d::Mesh, PhysicalMaterial>> {
+        self.objects.iter()
+    }
+}
+
+fn create_collider_object(
+    context: &Context,
+    collider: &Collider,
+) -> Gm<three_d::Mesh, PhysicalMaterial> {
+    let cpu_mesh = match collider {
+        Collider::Sphere(_) => CpuMesh::sphere(64),
+        Collider::Box(_) => CpuMesh::cube(),
+        Collider::Plane(_) => CpuMesh::square(),
+        Collider::TriMesh(tri_mesh) => {
+            let mut cpu_mesh = CpuMesh {
+                positions: Positions::F32(
+                    tri_mesh
+                        .vertices()
+                        .iter()
+                        .map(|v| vec3(v.x, v.y, v.z))
+                        .collect(),
+                ),
+                indices: Indices::U32(tri_mesh.indices().to_vec()),
+                ..Default::default()
+            };
+            cpu_mesh.compute_normals();
+            cpu_mesh
+        }
+        // shapes from other libraries are drawn as a unit cube placeholder
+        _ => CpuMesh::cube(),
+    };
+    Gm::new(
+        three_d::Mesh::new(context, &cpu_mesh),
+        PhysicalMaterial::new_opaque(
+            context,
+            &CpuMaterial {
+                albedo: Srgba::new_opaque(60, 90, 200),
+                metallic: 0.5,
+                roughness: 0.5,
+                ..Default::default()
+            },
+        ),
+    )
+}
+
+fn isometry_to_mat4(isometry: &Isometry3) -> Mat4 {
+    let m = isometry.to_homogeneous();
+    let m = m.as_slice();
+    // both are column major
+    Mat4::new(
+        m[0], m[1], m[2], m[3], m[4], m[5], m[6], m[7], m[8], m[9], m[10], m[11], m[12], m[13],
+        m[14], m[15],
+    )
+}
+
+pub struct Lights {
+    ambient: AmbientLight,
+    directional: DirectionalLight,
+}
+
+impl Lights {
+    pub fn new(context: &three_d::Context) -> Self {
+        Self {
+            ambient: AmbientLight::new(context, 0.4, Srgba::WHITE),
+            directional: DirectionalLight::new(
+                context,
+                2.0,
+                Srgba::WHITE,
+                &three_d::vec3(-1.0, -1.0, -1.0),
+            ),
+        }
+    }
+
+    pub fn array(&self) -> [&dyn three_d::Light; 2] {
+        [&self.ambient, &self.directional]
+    }
+
+    /// Ambient light in linear rgb, scaled by its intensity.
+    pub fn ambient_color(&self) -> Vector3<f32> {
+        self.ambient.color.to_linear_srgb().truncate() * self.ambient.intensity
+    }
+
+    /// Directional light in linear rgb, scaled by its intensity.
+    pub fn directional_color(&self) -> Vector3<f32> {
+        self.directional.color.to_linear_srgb().truncate() * self.directional.intensity
+    }
+
+    pub fn directional_direction(&self) -> Vector3<f32> {
+        self.directional.direction
+    }
+}
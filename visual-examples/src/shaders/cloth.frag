@@ -0,0 +1,47 @@
+uniform vec3 color;
+uniform vec3 backColor;
+uniform float doubleSided;
+uniform vec3 ambientColor;
+uniform vec3 lightColor;
+uniform vec3 lightDirection;
+uniform float showStrain;
+uniform float maxStrain;
+in vec3 v_normal;
+in float v_strain;
+out vec4 fragColor;
+
+vec3 srgb_from_linear_srgb(vec3 rgb) {
+    vec3 a = vec3(0.055, 0.055, 0.055);
+    vec3 ap1 = vec3(1.0, 1.0, 1.0) + a;
+    vec3 g = vec3(2.4, 2.4, 2.4);
+    vec3 ginv = 1.0 / g;
+    vec3 select = step(vec3(0.0031308, 0.0031308, 0.0031308), rgb);
+    vec3 lo = rgb * 12.92;
+    vec3 hi = ap1 * pow(rgb, ginv) - a;
+    return mix(lo, hi, select);
+}
+
+// white at rest, blue when compressed and red when stretched
+vec3 strain_color(float strain) {
+    float t = clamp(strain / maxStrain, -1.0, 1.0);
+    vec3 white = vec3(1.0, 1.0, 1.0);
+    if (t > 0.0) {
+        return mix(white, vec3(1.0, 0.0, 0.0), t);
+    }
+    return mix(white, vec3(0.0, 0.0, 1.0), -t);
+}
+
+void main() {
+    vec3 n = normalize(v_normal);
+    vec3 baseColor = color;
+    if (doubleSided > 0.5 && !gl_FrontFacing) {
+        n = -n;
+        baseColor = backColor;
+    }
+    if (showStrain > 0.5) {
+        baseColor = strain_color(v_strain);
+    }
+    float diffuse = max(dot(n, -normalize(lightDirection)), 0.0);
+    vec3 rgb = baseColor * (ambientColor + lightColor * diffuse);
+    fragColor = vec4(srgb_from_linear_srgb(rgb), 1.0);
+}
@@ -0,0 +1,13 @@
+in vec3 position;
+in vec3 normal;
+in float strain;
+uniform mat4 model;
+uniform mat4 viewProjection;
+out vec3 v_normal;
+out float v_strain;
+
+void main() {
+    gl_Position = viewProjection * model * vec4(position, 1.0);
+    v_normal = mat3(model) * normal;
+    v_strain = strain;
+}